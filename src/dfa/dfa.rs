@@ -1,43 +1,34 @@
 use crate::nfa::nfa::NFA;
-use std::cmp::min;
-use std::collections::{HashMap, HashSet};
-use std::fmt::Debug;
-use std::hash::Hash;
-use std::rc::Rc;
-
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct State(usize);
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 enum Tag {
     Start(usize),
     End(usize),
 }
 
+// A transition on every symbol within the inclusive range `symbol_range`
+#[derive(Clone)]
 struct Transition {
     from_state: State,
-    symbol_onehot_encoding: u128,
+    symbol_range: (u8, u8),
     to_state: State,
     tag: Option<Tag>,
 }
 
 impl Debug for Transition {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        if 0 == self.symbol_onehot_encoding {
-            return write!(
-                f,
-                "{:?} -> {:?}, symbol: {}",
-                self.from_state, self.to_state, "epsilon"
-            );
-        }
-
-        let mut char_vec: Vec<char> = Vec::new();
-        for i in 0..128u8 {
-            let mask = 1u128 << i;
-            if mask & self.symbol_onehot_encoding == mask {
-                char_vec.push(i as char);
-            }
-        }
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let (begin, end) = self.symbol_range;
+        let char_vec: Vec<char> = (begin..=end).map(|c| c as char).collect();
         write!(
             f,
             "{:?} -> {:?}, symbol: {:?}",
@@ -46,16 +37,18 @@ impl Debug for Transition {
     }
 }
 
+#[derive(Clone)]
 pub struct DFA {
     start: State,
     accept: Vec<State>,
     states: Vec<State>,
-    transitions: Vec<Vec<Option<Transition>>>, // from_state -> symbol[index in the length 128 vector] -> transition
+    transitions: Vec<Vec<Transition>>, // from_state -> transitions on disjoint symbol ranges, sorted by symbol
     dfa_to_accepted_nfa_state_mapping: Vec<Option<(usize, crate::nfa::nfa::State)>>, // to determine which NFA gets matched
+    dfa_to_accepted_nfa_ids: Vec<Vec<usize>>, // all the NFAs accepted in each state, in ascending order
 }
 
 impl Debug for DFA {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "DFA( start: {:?}, accept: {:?}, states: {:?}, transitions: {{\n",
@@ -67,12 +60,9 @@ impl Debug for DFA {
             if self.transitions[state_idx].is_empty() {
                 continue;
             }
-            write!(f, "\t{:?}:\n", state)?;
-            for transition_option in self.transitions[state_idx].iter() {
-                if transition_option.is_none() {
-                    continue;
-                }
-                write!(f, "\t\t{:?}\n", transition_option.as_ref().unwrap())?;
+            writeln!(f, "\t{:?}:", state)?;
+            for transition in self.transitions[state_idx].iter() {
+                writeln!(f, "\t\t{:?}", transition)?;
             }
         }
 
@@ -80,9 +70,58 @@ impl Debug for DFA {
     }
 }
 
+// Walks a DFA one byte at a time, and remembers the NFA index and the length of the longest
+// accepted prefix. Input that can't be held at once (e.g. the lexer reading a stream) is fed byte by
+// byte; `DFA::longest_match` feeds a whole slice, and `DfaSimulator` one char per call.
+pub(crate) struct LongestMatchTracker {
+    state: State,
+    num_consumed_bytes: usize,
+    is_dead: bool,
+    longest_match: Option<(usize, usize)>,
+}
+
+impl LongestMatchTracker {
+    pub(crate) fn new(dfa: &DFA) -> Self {
+        let state = dfa.get_root();
+        let longest_match = dfa.is_accept_state(state.clone()).map(|nfa_id| (nfa_id, 0));
+        Self {
+            state,
+            num_consumed_bytes: 0,
+            is_dead: false,
+            longest_match,
+        }
+    }
+
+    // Moves to the next state on the given byte. Returns false, leaving the state unchanged, if
+    // the byte leads to a dead end: the longest match is then final, even if later bytes still
+    // move the state.
+    pub(crate) fn consume(&mut self, dfa: &DFA, c: u8) -> bool {
+        let next_state = match dfa.get_next_state(self.state.clone(), c) {
+            Some(next_state) => next_state,
+            None => {
+                self.is_dead = true;
+                return false;
+            }
+        };
+        if false == self.is_dead {
+            self.num_consumed_bytes += 1;
+            if let Some(nfa_id) = dfa.is_accept_state(next_state.clone()) {
+                self.longest_match = Some((nfa_id, self.num_consumed_bytes));
+            }
+        }
+        self.state = next_state;
+        true
+    }
+
+    // The NFA index and the length of the longest accepted prefix, if any
+    pub(crate) fn get_longest_match(&self) -> Option<(usize, usize)> {
+        self.longest_match
+    }
+}
+
 pub struct DfaSimulator {
-    dfa: Rc<DFA>,
-    current_state: State,
+    dfa: Arc<DFA>,
+    tracker: LongestMatchTracker,
 }
 
 impl DFA {
@@ -92,11 +131,7 @@ impl DFA {
         _states.push(State(0)); // start state is always 0
 
         let mut _transitions = Vec::new();
-        let mut vector = Vec::with_capacity(128);
-        for _ in 0..128 {
-            vector.push(None::<Transition>);
-        }
-        _transitions.push(vector);
+        _transitions.push(Vec::new());
 
         DFA {
             start: State(0),
@@ -104,13 +139,14 @@ impl DFA {
             states: _states,
             transitions: _transitions,
             dfa_to_accepted_nfa_state_mapping: Vec::new(),
+            dfa_to_accepted_nfa_ids: Vec::new(),
         }
     }
 
     fn add_transition(
         &mut self,
         from_state: State,
-        symbol_onehot_encoding: u128,
+        symbol_ranges: &[(u8, u8)],
         to_state: State,
         tag: Option<Tag>,
     ) {
@@ -118,29 +154,88 @@ impl DFA {
         assert!(self.transitions.len() > from_state.0);
         assert!(self.states.len() > to_state.0);
 
-        for i in 0..128 {
-            if (symbol_onehot_encoding & (1 << i)) != 0 {
-                assert_eq!(self.transitions[from_state.0].len(), 128);
-                self.transitions[from_state.0][i] = Some(Transition {
-                    from_state: from_state.clone(),
-                    symbol_onehot_encoding,
-                    to_state: to_state.clone(),
-                    tag: tag.clone(),
-                });
+        let mut destinations = Self::expand_transitions(&self.transitions[from_state.0]);
+        for &(begin, end) in symbol_ranges {
+            for destination in &mut destinations[begin as usize..=end as usize] {
+                *destination = Some((to_state.clone(), tag.clone()));
             }
         }
+        self.transitions[from_state.0] = Self::compress_transitions(&from_state, destinations);
     }
 
-    fn get_transition(
-        transitions_map: &Vec<Option<Transition>>,
-        symbol: char,
-    ) -> Option<&Transition> {
-        let transition = transitions_map.get(symbol as usize);
-        if transition.is_none() {
-            return None;
+    // Converts sparse transitions into the destination (and tag) of each of the 128 symbols
+    fn expand_transitions(transitions: &[Transition]) -> Vec<Option<(State, Option<Tag>)>> {
+        let mut destinations = vec![None; 128];
+        for transition in transitions {
+            let (begin, end) = transition.symbol_range;
+            for symbol in begin..=end {
+                destinations[symbol as usize] =
+                    Some((transition.to_state.clone(), transition.tag.clone()));
+            }
         }
+        destinations
+    }
 
-        transition.unwrap().as_ref()
+    // Converts the destination (and tag) of each of the 128 symbols into sparse transitions, where
+    // consecutive symbols sharing the same destination are merged into a single symbol range
+    fn compress_transitions(
+        from_state: &State,
+        destinations: Vec<Option<(State, Option<Tag>)>>,
+    ) -> Vec<Transition> {
+        let mut transitions: Vec<Transition> = Vec::new();
+        for (symbol, destination) in destinations.into_iter().enumerate() {
+            let Some((to_state, tag)) = destination else {
+                continue;
+            };
+            let symbol = symbol as u8;
+            Self::push_transition(
+                &mut transitions,
+                from_state,
+                (symbol, symbol),
+                to_state,
+                tag,
+            );
+        }
+        transitions.shrink_to_fit();
+        transitions
+    }
+
+    // Appends a transition on a symbol range past all the given transitions, merging it into the
+    // last transition if the ranges are consecutive and share the same destination (and tag)
+    fn push_transition(
+        transitions: &mut Vec<Transition>,
+        from_state: &State,
+        symbol_range: (u8, u8),
+        to_state: State,
+        tag: Option<Tag>,
+    ) {
+        if let Some(last_transition) = transitions.last_mut() {
+            if last_transition.symbol_range.1 + 1 == symbol_range.0
+                && last_transition.to_state == to_state
+                && last_transition.tag == tag
+            {
+                last_transition.symbol_range.1 = symbol_range.1;
+                return;
+            }
+        }
+        transitions.push(Transition {
+            from_state: from_state.clone(),
+            symbol_range,
+            to_state,
+            tag,
+        });
+    }
+
+    // Binary searches the sorted symbol ranges for the transition on the given symbol
+    fn get_transition(transitions: &[Transition], symbol: char) -> Option<&Transition> {
+        if false == symbol.is_ascii() {
+            return None;
+        }
+        let symbol = symbol as u8;
+        let idx = transitions.partition_point(|transition| transition.symbol_range.1 < symbol);
+        transitions
+            .get(idx)
+            .filter(|transition| transition.symbol_range.0 <= symbol)
     }
 
     fn get_accept_nfa_state(&self, s: usize) -> Option<usize> {
@@ -185,7 +280,12 @@ impl DFA {
                 let nfa_state = self.dfa_to_accepted_nfa_state_mapping.get(current_state.0);
 
                 if nfa_state.is_none() {
-                    println!("[WARN] This should only happen when the DFA is created from scratch, not created from NFA(s)");
+                    #[cfg(feature = "log")]
+                    log::warn!(
+                        "DFA accept state {} has no accepted NFA, which should only happen when \
+                        the DFA is created from scratch, not created from NFA(s)",
+                        current_state.0
+                    );
                     return (None, true);
                 }
 
@@ -203,11 +303,63 @@ impl DFA {
     }
 }
 
+// An NFA state along with the index of the NFA it belongs to
+type IndexedNfaState = (usize, crate::nfa::nfa::State);
+
+// An inclusive symbol interval along with the NFA states every symbol of it moves to
+type SymbolIntervalMove = ((u8, u8), Vec<IndexedNfaState>);
+
 // Helper functions for converting multiple NFAs to a single DFA
 impl DFA {
+    // Splits the symbols of all the non-epsilon transitions from the given NFA states into sorted
+    // disjoint intervals, such that every symbol of an interval moves to the same NFA states. Returns
+    // each interval along with the NFA states it moves to.
+    fn move_on_symbol_intervals(
+        nfas: &[NFA],
+        states: &[IndexedNfaState],
+    ) -> Vec<SymbolIntervalMove> {
+        let mut transitions = Vec::new();
+        for (idx, nfa_state) in states.iter() {
+            let nfa_transitions = nfas[*idx].get_transitions_from_state(nfa_state);
+            for transition in nfa_transitions.into_iter().flatten() {
+                if false == transition.is_epsilon() {
+                    transitions.push((*idx, transition));
+                }
+            }
+        }
+
+        // Every symbol range starts an interval, and the symbol after it starts another one
+        let mut boundaries: Vec<u16> = Vec::new();
+        for (_, transition) in transitions.iter() {
+            for &(begin, end) in transition.get_symbol_ranges() {
+                boundaries.push(begin as u16);
+                boundaries.push(end as u16 + 1);
+            }
+        }
+        boundaries.sort();
+        boundaries.dedup();
+
+        let mut intervals = Vec::new();
+        for window in boundaries.windows(2) {
+            let interval = (window[0] as u8, (window[1] - 1) as u8);
+            let mut destination_nfa_states = Vec::new();
+            for (idx, transition) in transitions.iter() {
+                let symbol_ranges = transition.get_symbol_ranges();
+                let range_idx = symbol_ranges.partition_point(|range| range.1 < interval.0);
+                if range_idx < symbol_ranges.len() && symbol_ranges[range_idx].0 <= interval.0 {
+                    destination_nfa_states.push((*idx, transition.get_to_state()));
+                }
+            }
+            if false == destination_nfa_states.is_empty() {
+                intervals.push((interval, destination_nfa_states));
+            }
+        }
+        intervals
+    }
+
     fn epsilon_closure(
-        nfas: &Vec<NFA>,
-        states: &Vec<(usize, crate::nfa::nfa::State)>,
+        nfas: &[NFA],
+        states: &[(usize, crate::nfa::nfa::State)],
     ) -> Vec<(usize, crate::nfa::nfa::State)> {
         let mut closure = Vec::new();
 
@@ -215,12 +367,16 @@ impl DFA {
             let single_nfa_start_epi_closure: Vec<crate::nfa::nfa::State> = nfas
                 .get(*idx)
                 .unwrap()
-                .epsilon_closure(&vec![nfa_start.clone()]);
+                .epsilon_closure(core::slice::from_ref(nfa_start));
             for state in single_nfa_start_epi_closure.iter() {
                 closure.push((*idx, state.clone()));
             }
         }
 
+        // The closure identifies a DFA state, so it must not depend on the order (or the
+        // multiplicity) of the given states
+        closure.sort_by_key(|(idx, state)| (*idx, state.0));
+        closure.dedup();
         closure
     }
 }
@@ -230,24 +386,68 @@ impl DFA {
         if 128 <= c {
             return None;
         }
-        let transitions = &self.transitions[state.0];
-        match &transitions[c as usize] {
-            Some(transition) => Some(transition.to_state.clone()),
-            None => None,
-        }
+        DFA::get_transition(&self.transitions[state.0], c as char)
+            .map(|transition| transition.to_state.clone())
     }
 
     pub fn is_accept_state(&self, state: State) -> Option<usize> {
         self.get_accept_nfa_state(state.0)
     }
 
+    // Returns the indices of all the NFAs accepted in the given state, in ascending order. Unlike
+    // `is_accept_state`, this lets the caller fall back to a lower-priority NFA when the
+    // highest-priority one is rejected by a condition the DFA can't check (e.g. an anchor).
+    pub fn get_accepted_nfa_ids(&self, state: State) -> &[usize] {
+        match self.dfa_to_accepted_nfa_ids.get(state.0) {
+            Some(nfa_ids) => nfa_ids.as_slice(),
+            None => &[],
+        }
+    }
+
+    // Walks the input as far as the DFA allows, and returns the NFA index and the length of the
+    // longest accepted prefix, or `None` if no prefix (including the empty one) is accepted.
+    pub fn longest_match(&self, input: &[u8]) -> Option<(usize, usize)> {
+        let mut tracker = LongestMatchTracker::new(self);
+        for c in input {
+            if false == tracker.consume(self, *c) {
+                break;
+            }
+        }
+        tracker.get_longest_match()
+    }
+
     pub fn get_root(&self) -> State {
         self.start.clone()
     }
+
+    pub fn num_states(&self) -> usize {
+        self.states.len()
+    }
+
+    // The number of (state, symbol) pairs with a next state. Transitions are stored as symbol
+    // ranges, so each range counts for every symbol it covers.
+    pub fn num_transitions(&self) -> usize {
+        self.transitions
+            .iter()
+            .flatten()
+            .map(|transition| {
+                let (begin, end) = transition.symbol_range;
+                (end - begin) as usize + 1
+            })
+            .sum()
+    }
 }
 
 impl DFA {
+    // The ID reported by `is_accept_state` is the index of the accepted NFA within `nfas`. If
+    // multiple NFAs accept, the one with the lowest index wins.
     pub fn from_multiple_nfas(nfas: Vec<NFA>) -> DFA {
+        let mut dfa = DFA::from_multiple_nfas_without_pruning(nfas);
+        dfa.prune_dead_states();
+        dfa
+    }
+
+    fn from_multiple_nfas_without_pruning(nfas: Vec<NFA>) -> DFA {
         // All of the nodes now have a pair of identifiers,
         // 1. the NFA index within the list of NFAs
         // 2. the NFA state index within the NFA
@@ -258,15 +458,16 @@ impl DFA {
             Vec::new();
         let mut dfa_to_accepted_nfa_state_mapping: Vec<Option<(usize, crate::nfa::nfa::State)>> =
             Vec::new();
-        let mut dfa_accept_states = HashSet::new();
-        let mut dfa_transitions: Vec<Vec<Option<Transition>>> = Vec::new();
+        let mut dfa_to_accepted_nfa_ids: Vec<Vec<usize>> = Vec::new();
+        let mut dfa_accept_states = BTreeSet::new();
+        let mut dfa_transitions: Vec<Vec<Transition>> = Vec::new();
 
         // local variables to help create the DFA
         let mut l_worklist: Vec<State> = Vec::new();
-        let mut l_nfa_states_to_dfa_mapping: HashMap<
+        let mut l_nfa_states_to_dfa_mapping: BTreeMap<
             Rc<Vec<(usize, crate::nfa::nfa::State)>>,
             State,
-        > = HashMap::new();
+        > = BTreeMap::new();
 
         // Start with the epsilon closure of the start state
         let mut nfa_starts = Vec::new();
@@ -285,14 +486,11 @@ impl DFA {
         let start_state = 0usize;
         dfa_states.push(State(start_state));
 
-        let mut transition_vector = Vec::with_capacity(128);
-        for _ in 0..128 {
-            transition_vector.push(None::<Transition>);
-        }
-        dfa_transitions.push(transition_vector);
+        dfa_transitions.push(Vec::new());
 
         dfa_to_nfa_state_mapping.push(start_epi_closure.clone());
         dfa_to_accepted_nfa_state_mapping.push(None);
+        dfa_to_accepted_nfa_ids.push(Vec::new());
         l_nfa_states_to_dfa_mapping.insert(start_epi_closure, State(start_state));
         l_worklist.push(State(start_state));
 
@@ -309,6 +507,8 @@ impl DFA {
                         .get(dfa_state.0)
                         .unwrap()
                         .clone();
+                    // Deterministically prefer the lowest NFA index (i.e., the first declared
+                    // schema), regardless of the order of the NFA states in the closure
                     let is_update = registered_accept_state.is_none()
                         || registered_accept_state.unwrap().0 > *idx;
 
@@ -320,46 +520,19 @@ impl DFA {
                             .replace((*idx, nfa_state.clone()));
                     }
 
+                    dfa_to_accepted_nfa_ids[dfa_state.0].push(*idx);
                     dfa_accept_states.insert(dfa_state.clone());
                 }
             }
-
-            // Process the Move operation for all transitions in the NFA states set
-            let mut move_transitions_symbol_to_transitions_vec = vec![Vec::new(); 128];
-            for (idx, nfa_state) in nfa_states.iter() {
-                let transitions = nfas
-                    .get(*idx)
-                    .unwrap()
-                    .get_transitions_from_state(nfa_state);
-                for transition in transitions.into_iter().flatten() {
-                    let symbol_onehot_encoding = transition.get_symbol_onehot_encoding();
-
-                    for i in 0..128 {
-                        // We don't want to track epsilon transitions
-                        if (symbol_onehot_encoding & (1 << i)) != 0 {
-                            move_transitions_symbol_to_transitions_vec
-                                .get_mut(i)
-                                .unwrap()
-                                .push((idx, transition));
-                        }
-                    }
-                }
-            }
-
-            // Process the Epsilon Closure of the Move operation
-            for (symbol, transitions) in move_transitions_symbol_to_transitions_vec
-                .iter()
-                .enumerate()
+            dfa_to_accepted_nfa_ids[dfa_state.0].sort();
+            dfa_to_accepted_nfa_ids[dfa_state.0].dedup();
+
+            // Process the Epsilon Closure of the Move operation, once per symbol interval rather
+            // than once per symbol
+            let mut transitions: Vec<Transition> = Vec::new();
+            for (symbol_interval, destination_nfa_states) in
+                DFA::move_on_symbol_intervals(&nfas, &nfa_states)
             {
-                if transitions.is_empty() {
-                    continue;
-                }
-
-                // Collect all the destination NFA states
-                let mut destination_nfa_states = Vec::new();
-                for (idx, transition) in transitions.iter() {
-                    destination_nfa_states.push((**idx, (**transition).get_to_state()));
-                }
                 let destination_nfa_states =
                     Rc::new(DFA::epsilon_closure(&nfas, &destination_nfa_states));
 
@@ -369,12 +542,9 @@ impl DFA {
                     let destination_dfa_state_idx = dfa_states.len();
 
                     dfa_states.push(State(destination_dfa_state_idx));
-                    let mut transition_vector = Vec::new();
-                    for _ in 0..128 {
-                        transition_vector.push(None::<Transition>);
-                    }
-                    dfa_transitions.push(transition_vector);
+                    dfa_transitions.push(Vec::new());
                     dfa_to_accepted_nfa_state_mapping.push(None);
+                    dfa_to_accepted_nfa_ids.push(Vec::new());
 
                     // Ensure no mutable and immutable borrow overlap
                     dfa_to_nfa_state_mapping.push(destination_nfa_states.clone());
@@ -389,17 +559,18 @@ impl DFA {
                     .get(&destination_nfa_states)
                     .unwrap();
 
-                // Add the transition to the DFA
-                dfa_transitions.get_mut(dfa_state.0).unwrap()[symbol] = Some(Transition {
-                    from_state: dfa_state.clone(),
-                    symbol_onehot_encoding:
-                        crate::nfa::nfa::Transition::convert_char_to_symbol_onehot_encoding(
-                            symbol as u8 as char,
-                        ),
-                    to_state: destination_dfa_state.clone(),
-                    tag: None,
-                });
+                DFA::push_transition(
+                    &mut transitions,
+                    &dfa_state,
+                    symbol_interval,
+                    destination_dfa_state.clone(),
+                    None,
+                );
             }
+
+            // Add the transitions to the DFA
+            transitions.shrink_to_fit();
+            dfa_transitions[dfa_state.0] = transitions;
         }
 
         DFA {
@@ -408,64 +579,225 @@ impl DFA {
             states: dfa_states,
             transitions: dfa_transitions,
             dfa_to_accepted_nfa_state_mapping,
+            dfa_to_accepted_nfa_ids,
         }
     }
 }
 
-impl DfaSimulator {
-    pub fn new(dfa: Rc<DFA>) -> Self {
-        DfaSimulator {
-            dfa: dfa.clone(),
-            current_state: dfa.start.clone(),
+// Helper functions for removing dead states (states that can't reach any accept state)
+impl DFA {
+    fn get_live_states(&self) -> Vec<bool> {
+        let mut reversed_transitions: Vec<Vec<usize>> = vec![Vec::new(); self.states.len()];
+        for (from_state_idx, transitions) in self.transitions.iter().enumerate() {
+            for transition in transitions.iter() {
+                let to_state_idx = transition.to_state.0;
+                if false == reversed_transitions[to_state_idx].contains(&from_state_idx) {
+                    reversed_transitions[to_state_idx].push(from_state_idx);
+                }
+            }
         }
+
+        // Reverse BFS from the accept states
+        let mut is_live = vec![false; self.states.len()];
+        let mut worklist: Vec<usize> = self.accept.iter().map(|state| state.0).collect();
+        for state_idx in worklist.iter() {
+            is_live[*state_idx] = true;
+        }
+        while let Some(state_idx) = worklist.pop() {
+            for from_state_idx in reversed_transitions[state_idx].iter() {
+                if false == is_live[*from_state_idx] {
+                    is_live[*from_state_idx] = true;
+                    worklist.push(*from_state_idx);
+                }
+            }
+        }
+        is_live
     }
 
-    pub fn reset_simulation(&mut self) {
-        self.current_state = self.dfa.start.clone();
+    // Returns the pairs of NFA indices `(i, j)`, with `i < j`, whose match sets overlap: either a
+    // string is accepted by both NFAs, or a string accepted by one is a proper prefix of a string
+    // accepted by the other, which only the longest-match rule resolves.
+    pub fn get_overlapping_nfa_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = BTreeSet::new();
+        for state in &self.states {
+            let accepted_nfa_ids = self.get_accepted_nfa_ids(state.clone());
+            if accepted_nfa_ids.is_empty() {
+                continue;
+            }
+            let mut visited = vec![false; self.states.len()];
+            let mut stack = vec![state.0];
+            visited[state.0] = true;
+            while let Some(state_idx) = stack.pop() {
+                for other_nfa_id in self.get_accepted_nfa_ids(State(state_idx)) {
+                    for nfa_id in accepted_nfa_ids {
+                        if nfa_id != other_nfa_id {
+                            pairs.insert((*nfa_id.min(other_nfa_id), *nfa_id.max(other_nfa_id)));
+                        }
+                    }
+                }
+                for transition in &self.transitions[state_idx] {
+                    if false == visited[transition.to_state.0] {
+                        visited[transition.to_state.0] = true;
+                        stack.push(transition.to_state.0);
+                    }
+                }
+            }
+        }
+        pairs.into_iter().collect()
     }
 
-    // Simulate the DFA with a single character
-    // Returns the next state and whether the current state is a valid state
-    // invalid state means that the DFA has reached a dead end
-    pub fn simulate_single_char(&mut self, input: char) -> (Option<usize>, bool) {
-        let transitions = self.dfa.transitions.get(self.current_state.0);
+    // Returns the pairs of NFA indices `(i, j)`, with `i < j`, accepting some same string
+    pub fn get_co_accepted_nfa_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = BTreeSet::new();
+        for accepted_nfa_ids in &self.dfa_to_accepted_nfa_ids {
+            for (idx, first) in accepted_nfa_ids.iter().enumerate() {
+                for second in &accepted_nfa_ids[idx + 1..] {
+                    pairs.insert((*first, *second));
+                }
+            }
+        }
+        pairs.into_iter().collect()
+    }
 
-        if transitions.is_none() {
-            // not matched, nor is tracked by DFA, so invalid state
-            return (None, false);
+    // Returns the one-hot encoding of the symbols of all the transitions. Once dead states are
+    // pruned, these are exactly the symbols appearing in some accepted string.
+    pub fn get_transition_symbols(&self) -> u128 {
+        let mut symbols = 0u128;
+        for transition in self.transitions.iter().flatten() {
+            let (begin, end) = transition.symbol_range;
+            for c in begin..=end {
+                symbols |= 1 << c;
+            }
         }
+        symbols
+    }
 
-        let transitions = transitions.unwrap();
-        let transition = DFA::get_transition(transitions, input);
+    #[cfg(any(test, feature = "regex-engine"))]
+    pub fn get_num_dead_states(&self) -> usize {
+        self.get_live_states()
+            .iter()
+            .filter(|is_live| false == **is_live)
+            .count()
+    }
 
-        if transition.is_none() {
-            // not matched, nor is tracked by DFA, so invalid state
-            return (None, false);
+    // Removes the dead states and all the transitions into them. The start state is always kept
+    // (even if it is dead) so that the DFA has a root. The remaining states are re-indexed in
+    // their original order, so the start state stays 0.
+    fn prune_dead_states(&mut self) {
+        let mut is_live = self.get_live_states();
+        is_live[self.start.0] = true;
+
+        let mut state_mapping: Vec<Option<State>> = vec![None; self.states.len()];
+        let mut num_live_states = 0usize;
+        for (state_idx, state_is_live) in is_live.iter().enumerate() {
+            if *state_is_live {
+                state_mapping[state_idx] = Some(State(num_live_states));
+                num_live_states += 1;
+            }
+        }
+        if num_live_states == self.states.len() {
+            return;
+        }
+
+        let mut transitions = Vec::with_capacity(num_live_states);
+        let mut dfa_to_accepted_nfa_state_mapping = Vec::with_capacity(num_live_states);
+        let mut dfa_to_accepted_nfa_ids = Vec::with_capacity(num_live_states);
+        for (state_idx, ((state_transitions, accepted_nfa_state), accepted_nfa_ids)) in
+            core::mem::take(&mut self.transitions)
+                .into_iter()
+                .zip(core::mem::take(&mut self.dfa_to_accepted_nfa_state_mapping))
+                .zip(core::mem::take(&mut self.dfa_to_accepted_nfa_ids))
+                .enumerate()
+        {
+            let Some(from_state) = state_mapping[state_idx].clone() else {
+                continue;
+            };
+            let state_transitions: Vec<Transition> = state_transitions
+                .into_iter()
+                .filter_map(|transition| {
+                    let to_state = state_mapping[transition.to_state.0].clone()?;
+                    Some(Transition {
+                        from_state: from_state.clone(),
+                        to_state,
+                        ..transition
+                    })
+                })
+                .collect();
+            transitions.push(state_transitions);
+            dfa_to_accepted_nfa_state_mapping.push(accepted_nfa_state);
+            dfa_to_accepted_nfa_ids.push(accepted_nfa_ids);
         }
 
-        let next_state = transition.unwrap().to_state.clone();
+        self.start = state_mapping[self.start.0].clone().unwrap();
+        self.accept = self
+            .accept
+            .iter()
+            .filter_map(|state| state_mapping[state.0].clone())
+            .collect();
+        self.states = (0..num_live_states).map(State).collect();
+        self.transitions = transitions;
+        self.dfa_to_accepted_nfa_state_mapping = dfa_to_accepted_nfa_state_mapping;
+        self.dfa_to_accepted_nfa_ids = dfa_to_accepted_nfa_ids;
+    }
+}
 
-        let potential_accept_state = self.dfa.get_accept_nfa_state(next_state.0);
-        self.current_state = next_state;
+impl DfaSimulator {
+    // The simulator holds the DFA through an `Arc`, so the DFA is deep-copied unless the given
+    // `Rc` is its only owner. Use `new_arc` to share a DFA between simulators instead.
+    #[deprecated(note = "deep-copies a shared DFA, use `DfaSimulator::new_arc` instead")]
+    pub fn new(dfa: Rc<DFA>) -> Self {
+        Self::new_arc(Arc::new(Rc::unwrap_or_clone(dfa)))
+    }
 
-        if potential_accept_state.is_some() {
-            // we have a match
-            return (potential_accept_state, true);
+    pub fn new_arc(dfa: Arc<DFA>) -> Self {
+        let tracker = LongestMatchTracker::new(&dfa);
+        DfaSimulator { dfa, tracker }
+    }
+
+    pub fn reset_simulation(&mut self) {
+        self.tracker = LongestMatchTracker::new(&self.dfa);
+    }
+
+    // Simulate the DFA with a single character
+    // Returns the next state and whether the current state is a valid state
+    // invalid state means that the DFA has reached a dead end
+    pub fn simulate_single_char(&mut self, input: char) -> (Option<usize>, bool) {
+        // A non-ASCII char is rejected like any byte outside of the DFA alphabet
+        let c = u8::try_from(input).unwrap_or(u8::MAX);
+        if false == self.tracker.consume(&self.dfa, c) {
+            return (None, false);
         }
+        (self.dfa.is_accept_state(self.tracker.state.clone()), true)
+    }
+
+    // Returns the indices of all the NFAs accepted in the current state, in ascending order, e.g.
+    // to classify the input against every pattern instead of only the highest-priority one. The
+    // current state is left unchanged by a character leading to a dead end.
+    pub fn current_accepts(&self) -> &[usize] {
+        self.dfa.get_accepted_nfa_ids(self.tracker.state.clone())
+    }
 
-        // not matched, but still valid
-        (None, true)
+    // Returns the longest match since the last reset as the number of chars it spans and the
+    // accepted NFA, or `None` if no prefix of the input has been accepted yet. Unlike the result
+    // of `simulate_single_char`, it survives the rejecting chars following the match, e.g. `c+ab`
+    // on `cccabx` still reports `(5, 0)` after the `x`. The longest match is final once a char
+    // leads to a dead end.
+    pub fn get_longest_match(&self) -> Option<(usize, usize)> {
+        self.tracker
+            .get_longest_match()
+            .map(|(nfa_id, len)| (len, nfa_id))
     }
 }
 #[cfg(test)]
 mod tests {
-    use crate::dfa::dfa::{State, DFA};
+    use crate::dfa::dfa::{State, Transition, DFA};
     use crate::error_handling::Result;
     use crate::nfa::nfa::NFA;
     use crate::parser::regex_parser::parser::RegexParser;
     use crate::{dfa, nfa};
-    use std::collections::HashMap;
-    use std::rc::Rc;
+    use std::collections::{HashMap, HashSet};
+    use std::mem::size_of;
+    use std::sync::Arc;
 
     #[test]
     fn test_dfa() {
@@ -474,25 +806,11 @@ mod tests {
         let mut dfa = DFA::new();
 
         dfa.states.push(accept.clone());
-        let mut accept_transition_vec = Vec::new();
-        for _ in 0..128 {
-            accept_transition_vec.push(None);
-        }
-        dfa.transitions.push(accept_transition_vec);
+        dfa.transitions.push(Vec::new());
         dfa.accept.push(accept.clone());
 
-        dfa.add_transition(
-            start.clone(),
-            nfa::nfa::Transition::convert_char_to_symbol_onehot_encoding('a'),
-            accept.clone(),
-            None,
-        );
-        dfa.add_transition(
-            accept.clone(),
-            nfa::nfa::Transition::convert_char_to_symbol_onehot_encoding('b'),
-            start.clone(),
-            None,
-        );
+        dfa.add_transition(start.clone(), &[(b'a', b'a')], accept.clone(), None);
+        dfa.add_transition(accept.clone(), &[(b'b', b'b')], start.clone(), None);
 
         assert_eq!(dfa.simulate("ab"), (None, false));
         assert_eq!(dfa.simulate("a"), (None, true));
@@ -500,6 +818,64 @@ mod tests {
         assert_eq!(dfa.simulate("ba"), (None, false));
     }
 
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_simulate_logs_warning() -> Result<()> {
+        use std::cell::RefCell;
+
+        thread_local! {
+            static RECORDS: RefCell<Vec<(log::Level, String)>> = const { RefCell::new(Vec::new()) };
+        }
+
+        // Captures the records of the current thread only, as the tests run in parallel
+        struct CaptureLogger;
+
+        impl log::Log for CaptureLogger {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                RECORDS.with(|records| {
+                    records
+                        .borrow_mut()
+                        .push((record.level(), record.args().to_string()))
+                });
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: CaptureLogger = CaptureLogger;
+        // The logger may already be installed by another run of this test in the same process
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Trace);
+        let take_records = || RECORDS.with(|records| records.take());
+
+        // A DFA created from NFAs knows the NFA of every accept state
+        let dfa = DFA::from_multiple_nfas(vec![create_nfa1()?, create_nfa2()?]);
+        assert_eq!(dfa.simulate("ab"), (Some(0usize), true));
+        assert_eq!(dfa.simulate("cc"), (Some(1usize), true));
+        assert_eq!(dfa.simulate("abc"), (None, false));
+        assert!(take_records().is_empty());
+
+        let start = State(0);
+        let accept = State(1);
+        let mut scratch_dfa = DFA::new();
+        scratch_dfa.states.push(accept.clone());
+        scratch_dfa.transitions.push(Vec::new());
+        scratch_dfa.accept.push(accept.clone());
+        scratch_dfa.add_transition(start, &[(b'a', b'a')], accept, None);
+        assert_eq!(scratch_dfa.simulate("b"), (None, false));
+        assert!(take_records().is_empty());
+        assert_eq!(scratch_dfa.simulate("a"), (None, true));
+        let records = take_records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0, log::Level::Warn);
+
+        Ok(())
+    }
+
     fn create_nfa1() -> Result<NFA> {
         // Should only match "a" or "ab"
         let mut parser = RegexParser::new();
@@ -574,32 +950,25 @@ mod tests {
         //
         assert_eq!(dfa.transitions.len(), 3);
         let transitions_from_start = dfa.transitions.get(0).unwrap();
-        let mut valid_transitions_count = 0;
-        for transition in transitions_from_start.iter() {
-            if transition.is_some() {
-                valid_transitions_count += 1;
-            }
-        }
-        assert_eq!(valid_transitions_count, 1);
-        let transitions_from_start_given_a = transitions_from_start.get('a' as usize).unwrap();
-        assert_eq!(
-            transitions_from_start_given_a.as_ref().unwrap().to_state,
-            State(1)
-        );
+        assert_eq!(transitions_from_start.len(), 1);
+        assert_eq!(transitions_from_start[0].symbol_range, (b'a', b'a'));
+        let transitions_from_start_given_a =
+            DFA::get_transition(transitions_from_start, 'a').unwrap();
+        assert_eq!(transitions_from_start_given_a.to_state, State(1));
 
         let transitions_to_accept = dfa.transitions.get(1).unwrap();
-        let mut valid_transitions_count = 0;
-        for transition in transitions_to_accept.iter() {
-            if transition.is_some() {
-                valid_transitions_count += 1;
-            }
-        }
-        assert_eq!(valid_transitions_count, 1);
-        let transitions_to_accept_given_b = transitions_to_accept.get('b' as usize).unwrap();
-        assert_eq!(
-            transitions_to_accept_given_b.as_ref().unwrap().to_state,
-            State(2)
-        );
+        assert_eq!(transitions_to_accept.len(), 1);
+        assert_eq!(transitions_to_accept[0].symbol_range, (b'b', b'b'));
+        let transitions_to_accept_given_b =
+            DFA::get_transition(transitions_to_accept, 'b').unwrap();
+        assert_eq!(transitions_to_accept_given_b.to_state, State(2));
+
+        assert_eq!(dfa.num_states(), 3);
+        assert_eq!(dfa.num_transitions(), 2);
+        // A single range covering 3 symbols, then `x`
+        let range_dfa = DFA::from_multiple_nfas(vec![NFA::from_regex("[a-c]x").unwrap()]);
+        assert_eq!(range_dfa.num_states(), 3);
+        assert_eq!(range_dfa.num_transitions(), 4);
 
         // Check correctness given some examples
         assert_eq!(dfa.simulate("a"), (Some(0usize), true));
@@ -726,7 +1095,7 @@ mod tests {
         // "a" or "ab"
         // "c*"
         // "c+ab"
-        let mut dfa_simulator = dfa::dfa::DfaSimulator::new(Rc::new(dfa));
+        let mut dfa_simulator = dfa::dfa::DfaSimulator::new_arc(Arc::new(dfa));
         assert_eq!(
             dfa_simulator.simulate_single_char('a'),
             (Some(0usize), true)
@@ -766,6 +1135,92 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_dfa_clone() -> Result<()> {
+        let dfa = DFA::from_multiple_nfas(vec![
+            NFA::from_regex(r"(error)|(warn)")?,
+            NFA::from_regex(r"[a-z]+")?,
+        ]);
+        let cloned_dfa = dfa.clone();
+        drop(dfa);
+        assert_eq!(cloned_dfa.simulate("warn"), (Some(0usize), true));
+        assert_eq!(cloned_dfa.simulate("warning"), (Some(1usize), true));
+        assert_eq!(cloned_dfa.simulate("42"), (None, false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulator_current_accepts() -> Result<()> {
+        let dfa = DFA::from_multiple_nfas(vec![
+            NFA::from_regex(r"(error)|(warn)")?,
+            NFA::from_regex(r"[a-z]+")?,
+            NFA::from_regex(r"err(or)?")?,
+        ]);
+        let mut dfa_simulator = dfa::dfa::DfaSimulator::new_arc(Arc::new(dfa));
+        assert!(dfa_simulator.current_accepts().is_empty());
+        for c in "err".chars() {
+            dfa_simulator.simulate_single_char(c);
+        }
+        assert_eq!(dfa_simulator.current_accepts(), &[1, 2]);
+        for c in "or".chars() {
+            dfa_simulator.simulate_single_char(c);
+        }
+        assert_eq!(dfa_simulator.current_accepts(), &[0, 1, 2]);
+        assert_eq!(
+            dfa_simulator.simulate_single_char('s'),
+            (Some(1usize), true)
+        );
+        assert_eq!(dfa_simulator.current_accepts(), &[1]);
+
+        dfa_simulator.reset_simulation();
+        assert!(dfa_simulator.current_accepts().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulator_longest_match() -> Result<()> {
+        let dfa = Arc::new(DFA::from_multiple_nfas(vec![NFA::from_regex(r"c+ab")?]));
+        let mut dfa_simulator = dfa::dfa::DfaSimulator::new_arc(dfa.clone());
+        assert_eq!(dfa_simulator.get_longest_match(), None);
+        for c in "ccca".chars() {
+            assert!(dfa_simulator.simulate_single_char(c).1);
+            assert_eq!(dfa_simulator.get_longest_match(), None);
+        }
+        assert_eq!(
+            dfa_simulator.simulate_single_char('b'),
+            (Some(0usize), true)
+        );
+        assert_eq!(dfa_simulator.get_longest_match(), Some((5, 0)));
+        assert_eq!(dfa_simulator.simulate_single_char('x'), (None, false));
+        assert_eq!(dfa_simulator.get_longest_match(), Some((5, 0)));
+        // The match is final after the dead end, even if later chars would be accepted again
+        dfa_simulator.simulate_single_char('c');
+        assert_eq!(dfa_simulator.get_longest_match(), Some((5, 0)));
+
+        dfa_simulator.reset_simulation();
+        assert_eq!(dfa_simulator.get_longest_match(), None);
+        for c in "cab".chars() {
+            dfa_simulator.simulate_single_char(c);
+        }
+        assert_eq!(dfa_simulator.get_longest_match(), Some((3, 0)));
+
+        let dfa = DFA::from_multiple_nfas(vec![NFA::from_regex(r"a")?, NFA::from_regex(r"abc")?]);
+        let mut dfa_simulator = dfa::dfa::DfaSimulator::new_arc(Arc::new(dfa));
+        for (c, expected) in [
+            ('a', Some((1, 0))),
+            ('b', Some((1, 0))),
+            ('c', Some((3, 1))),
+        ] {
+            dfa_simulator.simulate_single_char(c);
+            assert_eq!(dfa_simulator.get_longest_match(), expected);
+        }
+        assert_eq!(dfa_simulator.simulate_single_char('d'), (None, false));
+        assert_eq!(dfa_simulator.get_longest_match(), Some((3, 1)));
+
+        Ok(())
+    }
+
     #[test]
     fn test_int() -> Result<()> {
         let mut parser = RegexParser::new();
@@ -883,6 +1338,170 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_sparse_transitions() -> Result<()> {
+        let mut parser = RegexParser::new();
+        let parsed_ast = parser.parse_into_ast(r"\w{500}")?;
+
+        let mut nfa = NFA::new();
+        nfa.add_ast_to_nfa(&parsed_ast, NFA::START_STATE, NFA::ACCEPT_STATE)?;
+        let dfa = DFA::from_multiple_nfas(vec![nfa]);
+        assert!(dfa.states.len() > 500);
+
+        // `\w` is stored as 4 symbol ranges rather than 63 single-symbol transitions
+        assert_eq!(dfa.transitions[0].len(), 4);
+
+        let sparse_size: usize = dfa
+            .transitions
+            .iter()
+            .map(|transitions| {
+                size_of::<Vec<Transition>>() + transitions.capacity() * size_of::<Transition>()
+            })
+            .sum();
+        let dense_size = dfa.states.len()
+            * (size_of::<Vec<Option<Transition>>>() + 128 * size_of::<Option<Transition>>());
+        assert!(sparse_size * 10 < dense_size);
+
+        let word = "aZ_09".repeat(100);
+        assert_eq!(dfa.simulate(word.as_str()), (Some(0usize), true));
+        assert_eq!(dfa.simulate(&word[1..]), (None, false));
+        assert_eq!(dfa.simulate((word.clone() + "a").as_str()), (None, false));
+        assert_eq!(
+            dfa.simulate((word[1..].to_string() + "-").as_str()),
+            (None, false)
+        );
+        assert_eq!(dfa.simulate(""), (None, false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_symbol_interval_construction_work() -> Result<()> {
+        let create_nfas = || -> Result<Vec<NFA>> {
+            let mut nfas = Vec::new();
+            for pattern in [r".*error.*", r"id=.{8}", r"(.)+:\d+", r".{2,10}\.log"] {
+                nfas.push(NFA::from_regex(pattern)?);
+            }
+            Ok(nfas)
+        };
+
+        // Replays the subset construction, counting the symbol intervals moved on versus the
+        // symbols moved on one at a time
+        let nfas = create_nfas()?;
+        let nfa_starts: Vec<_> = (0..nfas.len())
+            .map(|idx| (idx, nfas[idx].get_start()))
+            .collect();
+        let mut worklist = vec![DFA::epsilon_closure(&nfas, &nfa_starts)];
+        let mut visited = HashSet::new();
+        let mut num_intervals = 0;
+        while let Some(nfa_states) = worklist.pop() {
+            if false == visited.insert(nfa_states.clone()) {
+                continue;
+            }
+            for (_, destination_nfa_states) in DFA::move_on_symbol_intervals(&nfas, &nfa_states) {
+                num_intervals += 1;
+                worklist.push(DFA::epsilon_closure(&nfas, &destination_nfa_states));
+            }
+        }
+        let num_symbol_slots = visited.len() * 128;
+        assert!(num_intervals * 10 < num_symbol_slots);
+
+        let dfa = DFA::from_multiple_nfas(create_nfas()?);
+        assert_eq!(dfa.simulate("an error occurred"), (Some(0usize), true));
+        assert_eq!(dfa.simulate("id=0123abcd"), (Some(1usize), true));
+        assert_eq!(dfa.simulate("id=0123abc"), (None, false));
+        assert_eq!(dfa.simulate("host:8080"), (Some(2usize), true));
+        assert_eq!(dfa.simulate("app.log"), (Some(3usize), true));
+        assert_eq!(dfa.simulate("a.log"), (None, false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_dead_states() -> Result<()> {
+        {
+            let mut parser = RegexParser::new();
+            let parsed_ast = parser.parse_into_ast(r"a+")?;
+
+            let mut nfa = NFA::new();
+            nfa.add_ast_to_nfa(&parsed_ast, NFA::START_STATE, NFA::ACCEPT_STATE)?;
+
+            let dfa = DFA::from_multiple_nfas(vec![nfa]);
+            assert_eq!(dfa.get_num_dead_states(), 0);
+            assert_eq!(dfa.simulate("aaa"), (Some(0usize), true));
+        }
+
+        {
+            // Removing `c` leaves the `ab` branch unable to reach the accept state
+            let create_nfa = || -> Result<NFA> {
+                let mut parser = RegexParser::new();
+                let parsed_ast = parser.parse_into_ast(r"a(bc|d)+")?;
+
+                let mut nfa = NFA::new();
+                nfa.add_ast_to_nfa(&parsed_ast, NFA::START_STATE, NFA::ACCEPT_STATE)?;
+                nfa.remove_symbols(
+                    nfa::nfa::Transition::convert_char_to_symbol_onehot_encoding('c'),
+                );
+                Ok(nfa)
+            };
+
+            let unpruned_dfa = DFA::from_multiple_nfas_without_pruning(vec![create_nfa()?]);
+            assert!(unpruned_dfa.get_num_dead_states() > 0);
+
+            let pruned_dfa = DFA::from_multiple_nfas(vec![create_nfa()?]);
+            assert_eq!(pruned_dfa.get_num_dead_states(), 0);
+            assert!(pruned_dfa.states.len() < unpruned_dfa.states.len());
+
+            for input in ["", "a", "ab", "abc", "ad", "add", "adab", "adbd", "b", "d"] {
+                assert_eq!(pruned_dfa.simulate(input), unpruned_dfa.simulate(input));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_longest_match() -> Result<()> {
+        let dfa = DFA::from_multiple_nfas(vec![NFA::from_regex(r"c+ab")?]);
+        assert_eq!(dfa.longest_match(b"cccababab"), Some((0, 5)));
+        assert_eq!(dfa.longest_match(b"cab"), Some((0, 3)));
+        assert_eq!(dfa.longest_match(b"cca"), None);
+        assert_eq!(dfa.longest_match(b""), None);
+
+        let dfa =
+            DFA::from_multiple_nfas(vec![NFA::from_regex(r"(ab)*")?, NFA::from_regex(r"a+")?]);
+        assert_eq!(dfa.longest_match(b"ababa"), Some((0, 4)));
+        assert_eq!(dfa.longest_match(b"aaab"), Some((1, 3)));
+        assert_eq!(dfa.longest_match(b"b"), Some((0, 0)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_groups() -> Result<()> {
+        let dfa = DFA::from_multiple_nfas(vec![NFA::from_regex(r"(?:ab)+")?]);
+        assert_eq!(dfa.simulate("ab"), (Some(0usize), true));
+        assert_eq!(dfa.simulate("abab"), (Some(0usize), true));
+        assert_eq!(dfa.simulate("aba"), (None, false));
+        assert_eq!(dfa.simulate(""), (None, false));
+
+        let dfa = DFA::from_multiple_nfas(vec![NFA::from_regex(r"(ab)|c")?]);
+        assert_eq!(dfa.simulate("ab"), (Some(0usize), true));
+        assert_eq!(dfa.simulate("c"), (Some(0usize), true));
+        assert_eq!(dfa.simulate("abc"), (None, false));
+
+        let dfa = DFA::from_multiple_nfas(vec![NFA::from_regex(r"(?P<id>\d+)\-(?:x|y)")?]);
+        assert_eq!(dfa.simulate("42-x"), (Some(0usize), true));
+        assert_eq!(dfa.simulate("42-z"), (None, false));
+
+        assert!(matches!(
+            NFA::from_regex(r"(?i:ab)"),
+            Err(crate::error_handling::Error::UnsupportedGroupKindType)
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_repetition() -> Result<()> {
         {
@@ -1,11 +1,47 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt::{Display, Formatter};
 use regex_syntax::ast;
 
+// The payloads of the larger variants are boxed, to keep `Result` small on the hot paths
+
+#[derive(Debug)]
+pub struct VariableRegexErrorInfo {
+    pub name: String,
+    pub regex: String,
+    pub source: ast::Error,
+}
+
+#[derive(Debug)]
+pub struct TimestampRegexErrorInfo {
+    pub index: usize,
+    pub regex: String,
+    pub source: ast::Error,
+}
+
+#[derive(Debug)]
+pub struct UnsupportedRegexConstructInfo {
+    pub construct: &'static str,
+    pub offset: usize,
+}
+
+#[derive(Debug)]
+pub struct UnmatchedContentInfo {
+    pub line: usize,
+    pub text: String,
+}
+
 #[derive(Debug)]
 pub enum Error {
-    RegexParsingError(ast::Error),
+    RegexParsingError(Box<ast::Error>),
+    VariableRegexError(Box<VariableRegexErrorInfo>),
+    TimestampRegexError(Box<TimestampRegexErrorInfo>),
+    #[cfg(feature = "std")]
     YamlParsingError(serde_yaml::Error),
+    #[cfg(feature = "std")]
     IOError(std::io::Error),
     UnsupportedAstNodeType(&'static str),
+    UnsupportedRegexConstruct(Box<UnsupportedRegexConstructInfo>),
     NoneASCIICharacters,
     NegationNotSupported(&'static str),
     NonGreedyRepetitionNotSupported,
@@ -17,7 +53,221 @@ pub enum Error {
     LexerStateUnknown,
     LexerInternalErr(&'static str),
     LogParserInternalErr(&'static str),
+    LogParserInputNotSet,
     InvalidSchema,
+    DuplicateVariableName(String),
+    UnknownVariableName(String),
+    EmptyMatchingVariable(String),
+    ReconstructionMismatch,
+    VariableByteViolation {
+        name: String,
+        byte: u8,
+    },
+    UnmatchedContent(Box<UnmatchedContentInfo>),
+    LineTooLong {
+        line_num: usize,
+        limit: usize,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::RegexParsingError(e) => write!(f, "failed to parse regex: {}", e),
+            Error::VariableRegexError(info) => write!(
+                f,
+                "failed to parse the regex of variable `{}` (`{}`): {}",
+                info.name, info.regex, info.source
+            ),
+            Error::TimestampRegexError(info) => write!(
+                f,
+                "failed to parse the regex of timestamp #{} (`{}`): {}",
+                info.index, info.regex, info.source
+            ),
+            #[cfg(feature = "std")]
+            Error::YamlParsingError(e) => write!(f, "failed to parse YAML: {}", e),
+            #[cfg(feature = "std")]
+            Error::IOError(e) => write!(f, "I/O error: {}", e),
+            Error::UnsupportedAstNodeType(node_type) => {
+                write!(f, "unsupported regex AST node type: {}", node_type)
+            }
+            Error::UnsupportedRegexConstruct(info) => write!(
+                f,
+                "unsupported regex construct at offset {}: {}",
+                info.offset, info.construct
+            ),
+            Error::NoneASCIICharacters => write!(f, "only ASCII characters are supported"),
+            Error::NegationNotSupported(msg) => write!(f, "negation is not supported: {}", msg),
+            Error::NonGreedyRepetitionNotSupported => {
+                write!(f, "non-greedy repetition is not supported")
+            }
+            Error::UnsupportedAstBracketedKind => {
+                write!(f, "unsupported regex bracketed class kind")
+            }
+            Error::UnsupportedClassSetType => write!(f, "unsupported regex class set type"),
+            Error::UnsupportedGroupKindType => write!(f, "unsupported regex group kind"),
+            Error::MissingSchemaKey(key) => write!(f, "missing schema key: `{}`", key),
+            Error::LexerInputStreamNotSet => write!(f, "lexer input stream is not set"),
+            Error::LexerStateUnknown => write!(f, "lexer is in an unknown state"),
+            Error::LexerInternalErr(msg) => write!(f, "lexer internal error: {}", msg),
+            Error::LogParserInternalErr(msg) => write!(f, "log parser internal error: {}", msg),
+            Error::LogParserInputNotSet => write!(f, "log parser input is not set"),
+            Error::InvalidSchema => write!(f, "invalid schema"),
+            Error::DuplicateVariableName(name) => {
+                write!(f, "duplicate variable name in schema: `{}`", name)
+            }
+            Error::UnknownVariableName(name) => {
+                write!(f, "unknown variable name in schema: `{}`", name)
+            }
+            Error::EmptyMatchingVariable(name) => {
+                write!(f, "variable `{}` matches the empty string", name)
+            }
+            Error::ReconstructionMismatch => {
+                write!(f, "emitted tokens don't reconstruct the consumed input")
+            }
+            Error::VariableByteViolation { name, byte } => write!(
+                f,
+                "variable `{}` matched a byte outside of its allowlist: {}",
+                name,
+                core::ascii::escape_default(*byte)
+            ),
+            Error::UnmatchedContent(info) => write!(
+                f,
+                "unmatched content at line {}: \"{}\"",
+                info.line,
+                info.text.escape_default()
+            ),
+            Error::LineTooLong { line_num, limit } => write!(
+                f,
+                "line {} is longer than the limit of {} bytes",
+                line_num, limit
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::RegexParsingError(e) => Some(e.as_ref()),
+            Error::VariableRegexError(info) => Some(&info.source),
+            Error::TimestampRegexError(info) => Some(&info.source),
+            Error::YamlParsingError(e) => Some(e),
+            Error::IOError(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+impl From<ast::Error> for Error {
+    fn from(e: ast::Error) -> Self {
+        Error::RegexParsingError(Box::new(e))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Error::YamlParsingError(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::IOError(e)
+    }
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::error::Error as StdError;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Error::MissingSchemaKey("timestamp").to_string(),
+            "missing schema key: `timestamp`"
+        );
+        assert_eq!(
+            Error::LexerInternalErr("Delimiter not set").to_string(),
+            "lexer internal error: Delimiter not set"
+        );
+        assert_eq!(
+            Error::DuplicateVariableName("int".to_string()).to_string(),
+            "duplicate variable name in schema: `int`"
+        );
+        assert_eq!(
+            Error::EmptyMatchingVariable("int".to_string()).to_string(),
+            "variable `int` matches the empty string"
+        );
+        assert_eq!(
+            Error::VariableByteViolation {
+                name: "int".to_string(),
+                byte: b'\t',
+            }
+            .to_string(),
+            "variable `int` matched a byte outside of its allowlist: \\t"
+        );
+        assert_eq!(
+            Error::LineTooLong {
+                line_num: 2,
+                limit: 16,
+            }
+            .to_string(),
+            "line 2 is longer than the limit of 16 bytes"
+        );
+        assert_eq!(Error::InvalidSchema.to_string(), "invalid schema");
+        assert!(Error::InvalidSchema.source().is_none());
+
+        let io_error = Error::IOError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no such file",
+        ));
+        assert_eq!(io_error.to_string(), "I/O error: no such file");
+        assert_eq!(io_error.source().unwrap().to_string(), "no such file");
+    }
+
+    #[test]
+    fn test_source() {
+        let yaml_error = serde_yaml::from_str::<serde_yaml::Value>("[").unwrap_err();
+        let error = Error::YamlParsingError(yaml_error);
+        assert!(error.to_string().starts_with("failed to parse YAML: "));
+        assert!(error.source().is_some());
+
+        let regex_error = regex_syntax::ast::parse::Parser::new()
+            .parse("(")
+            .unwrap_err();
+        let error = Error::RegexParsingError(Box::new(regex_error));
+        assert!(error.to_string().starts_with("failed to parse regex: "));
+        assert!(error.source().is_some());
+
+        // Converts into a boxed error through `?`
+        let boxed = || -> std::result::Result<(), Box<dyn StdError>> {
+            Err(Error::InvalidSchema)?;
+            Ok(())
+        };
+        assert_eq!(boxed().unwrap_err().to_string(), "invalid schema");
+    }
+
+    #[test]
+    fn test_from() {
+        let read_missing_file = || -> Result<String> {
+            Ok(std::fs::read_to_string(
+                "/nonexistent/log_surgeon_schema.yaml",
+            )?)
+        };
+        assert!(matches!(read_missing_file(), Err(Error::IOError(_))));
+
+        let parse_yaml = || -> Result<serde_yaml::Value> { Ok(serde_yaml::from_str("[")?) };
+        assert!(matches!(parse_yaml(), Err(Error::YamlParsingError(_))));
+
+        let parse_regex =
+            || -> Result<ast::Ast> { Ok(regex_syntax::ast::parse::Parser::new().parse("(")?) };
+        assert!(matches!(parse_regex(), Err(Error::RegexParsingError(_))));
+    }
+}
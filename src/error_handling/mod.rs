@@ -1,3 +1,9 @@
 mod error;
+mod warning;
 pub use error::Error;
 pub use error::Result;
+pub use error::{
+    TimestampRegexErrorInfo, UnmatchedContentInfo, UnsupportedRegexConstructInfo,
+    VariableRegexErrorInfo,
+};
+pub use warning::Warning;
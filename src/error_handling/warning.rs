@@ -0,0 +1,19 @@
+use core::fmt::{Display, Formatter};
+
+// Non-fatal issues found in the input. Unlike an `Error`, a warning doesn't stop the parsing; it's
+// reported through a diagnostics channel set by the caller.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    // The line ending (`\n` or `\r\n`) of the given line differs from the one of the first line
+    MixedLineEndings { line: usize },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Warning::MixedLineEndings { line } => {
+                write!(f, "line {} changes the line ending used so far", line)
+            }
+        }
+    }
+}
@@ -1,12 +1,16 @@
-use crate::dfa::dfa::{State, DFA};
-use crate::error_handling::Error::{LexerInputStreamNotSet, LexerInternalErr, LexerStateUnknown};
-use crate::error_handling::Result;
-use crate::lexer::LexerStream;
+use crate::dfa::dfa::{LongestMatchTracker, State, DFA};
+use crate::error_handling::Error::{
+    LexerInputStreamNotSet, LexerInternalErr, LexerStateUnknown, LineTooLong,
+    ReconstructionMismatch, UnmatchedContent,
+};
+use crate::error_handling::{Error, Result, UnmatchedContentInfo};
+use crate::lexer::{FedInputStream, LexerStream, StringStream, TokenSink, VarMatcher};
 use crate::nfa::nfa::NFA;
-use crate::parser::SchemaConfig;
+use crate::parser::{SchemaConfig, VarSchema};
 use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::rc::Rc;
+use std::sync::Arc;
 
 enum LexerState {
     SeekingToTheNextDelimiter,
@@ -15,18 +19,35 @@ enum LexerState {
     DFAAccepted,
     VarExtract,
     ParsingTimestamp,
+    SeekingToTheEndOfLine,
     EndOfStream,
 }
 
+// The schema config and the DFAs compiled from it by `Lexer::new`. Unlike a `Lexer`, whose input
+// stream may not be `Send`, it can be shared across threads to build lexers without compiling the
+// schema again.
+#[derive(Clone)]
+pub(crate) struct CompiledSchema {
+    schema_config: Arc<SchemaConfig>,
+    ts_dfa: Arc<DFA>,
+    var_dfas: Arc<Vec<DFA>>,
+    var_switch_delimiters: Arc<Vec<Option<usize>>>,
+}
+
 pub struct Lexer {
-    schema_config: Rc<SchemaConfig>,
-    ts_dfa: DFA,
-    var_dfa: DFA,
+    schema_config: Arc<SchemaConfig>,
+    // The compiled DFAs are immutable, and shared by the clones of `try_clone`
+    ts_dfa: Arc<DFA>,
+    var_dfas: Arc<Vec<DFA>>, // one var DFA per delimiter set
+    var_switch_delimiters: Arc<Vec<Option<usize>>>,
+    active_delimiter_set: usize,
 
     state: LexerState,
     dfa_state: State,
 
     input_stream: Option<Box<dyn LexerStream>>,
+    // Whether the input is pushed with `feed` instead of pulled from the input stream
+    is_fed: bool,
     buf: Vec<u8>,
     buf_cursor_pos: usize,
     token_queue: VecDeque<Token>,
@@ -35,15 +56,89 @@ pub struct Lexer {
     last_tokenized_pos: usize,
     match_start_pos: usize,
     match_end_pos: usize,
+    match_at_line_start: bool,
     line_num: usize,
+    // The (line number, start) of every line from the one of the next token to the one of the
+    // tokenized input, with the start being `None` once compacted away from the buffer
+    line_start_positions: VecDeque<(usize, Option<usize>)>,
+    // The start of the line of the last returned token (see `current_line`)
+    token_line_start_pos: Option<usize>,
+
+    // Only tracked to rewind a failed match that has consumed delimiters
+    match_first_delimiter_pos: Option<usize>,
+    last_accepted_match: Option<(usize, State)>,
+
+    verify_reconstruction: bool,
+    verified_pos: usize,
+
+    raw_line_mode: bool,
+    mid_line_timestamps: bool,
+    embedded_timestamps: bool,
+    // Whether a timestamp has been matched on the line of the cursor
+    is_line_timestamped: bool,
+    substring_matching: bool,
+    gc_threshold: usize,
+    crlf_mode: CrlfMode,
+    emit_static_text: bool,
+    emit_delimiter_tokens: bool,
+    emit_end_token: bool,
+    has_emitted_end_token: bool,
+    unmatched_bytes_mode: UnmatchedBytesMode,
+    max_lookahead: Option<usize>,
+
+    // Tracked on the bytes read from the input stream, regardless of any rewind
+    max_line_len: Option<usize>,
+    input_line_num: usize,
+    input_line_len: usize,
+}
+
+// How the `\r` of a `\r\n` line ending is tokenized
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CrlfMode {
+    // The `\r` is kept in the end-of-line token (e.g. ` ms\r\n`)
+    #[default]
+    Preserve,
+    // The `\r` is dropped from the end-of-line token (e.g. ` ms\n`), so the tokens reconstruct
+    // the input with `\n` line endings. Not compatible with `set_verify_reconstruction`.
+    Strip,
+    // The `\r` is a plain delimiter rather than part of the line ending: it ends the static text
+    // before the end-of-line token, which only holds the `\n` (e.g. ` ms\r` then `\n`), and a
+    // match followed by `\r\n` doesn't satisfy a `$` anchor.
+    TreatCrAsDelimiter,
 }
 
-#[derive(Clone, Debug)]
+// How the lexer emits the bytes of static text that are neither delimiters nor matched by any
+// timestamp or variable schema
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum UnmatchedBytesMode {
+    // The bytes are kept in static text tokens, along with the delimiters around them
+    #[default]
+    StaticText,
+    // Every run of such bytes is emitted as its own `Unmatched` token, so the static text tokens
+    // only hold delimiters (e.g. `Took 42 ms` gives `Took`, ` `, `42`, ` `, `ms`)
+    Unmatched,
+    // Every run of such bytes fails `get_next_token` with `UnmatchedContent`. The error is
+    // recoverable: the run is dropped, and the next call continues with the following token.
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum TokenType {
     Timestamp(usize),
+    // Indexes directly into `SchemaConfig::get_var_schemas`
     Variable(usize),
     StaticText,
     StaticTextWithEndLine,
+    // A run of delimiters other than a line ending, only emitted if enabled by
+    // `Lexer::set_emit_delimiter_tokens`
+    Delimiter,
+    // A run of bytes that are neither delimiters nor matched by any schema, only emitted if enabled
+    // by `Lexer::set_unmatched_bytes_mode`
+    Unmatched,
+    // An empty token right after the last token of the input stream, only emitted (once) if
+    // enabled by `Lexer::set_emit_end_token`
+    End,
 }
 
 pub struct Token {
@@ -80,12 +175,90 @@ impl Token {
     pub fn get_line_num(&self) -> usize {
         self.line_num
     }
+
+    pub fn is_timestamp(&self) -> bool {
+        matches!(self.token_type, TokenType::Timestamp(_))
+    }
+
+    pub fn is_variable(&self) -> bool {
+        matches!(self.token_type, TokenType::Variable(_))
+    }
+
+    // `StaticText`, `StaticTextWithEndLine`, and `Delimiter`
+    pub fn is_static_text(&self) -> bool {
+        matches!(
+            self.token_type,
+            TokenType::StaticText | TokenType::StaticTextWithEndLine | TokenType::Delimiter
+        )
+    }
+
+    pub fn is_delimiter(&self) -> bool {
+        matches!(self.token_type, TokenType::Delimiter)
+    }
+
+    pub fn is_unmatched(&self) -> bool {
+        matches!(self.token_type, TokenType::Unmatched)
+    }
+
+    pub fn is_end(&self) -> bool {
+        matches!(self.token_type, TokenType::End)
+    }
+
+    // The timestamp schema index, or `None` if the token isn't a timestamp
+    pub fn timestamp_id(&self) -> Option<usize> {
+        match self.token_type {
+            TokenType::Timestamp(ts_id) => Some(ts_id),
+            _ => None,
+        }
+    }
+
+    // The variable schema index, or `None` if the token isn't a variable
+    pub fn variable_id(&self) -> Option<usize> {
+        match self.token_type {
+            TokenType::Variable(var_id) => Some(var_id),
+            _ => None,
+        }
+    }
+
+    // Returns the number of spaces and tabs right before the line ending (`\n` or `\r\n`) of an
+    // end-of-line token, so that trailing whitespace can be told apart from the line ending itself.
+    // Always 0 for the other tokens.
+    pub fn trailing_whitespace_len(&self) -> usize {
+        if false == matches!(self.token_type, TokenType::StaticTextWithEndLine) {
+            return 0;
+        }
+        let line = self.buf.strip_suffix(b"\n").unwrap_or(&self.buf);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        line.iter()
+            .rev()
+            .take_while(|c| b' ' == **c || b'\t' == **c)
+            .count()
+    }
+
+    // Returns the regex of the variable schema that produced this token, or `None` if the token
+    // isn't a variable.
+    pub fn matched_pattern<'a>(&self, schema_config: &'a SchemaConfig) -> Option<&'a str> {
+        match self.token_type {
+            TokenType::Variable(var_id) => schema_config
+                .get_var_schemas()
+                .get(var_id)
+                .map(|schema| schema.get_regex()),
+            _ => None,
+        }
+    }
 }
 
 impl Lexer {
-    const MIN_BUF_GARBAGE_COLLECTION_SIZE: usize = 4096;
+    const DEFAULT_GC_THRESHOLD: usize = 4096;
 
+    // The compiled DFAs are kept behind an `Arc` so that `parse_files_in_parallel` can share them
+    // across threads, which needs the schema config in an `Arc` too. Moving the schema config out
+    // of a shared `Rc` only clones the handle on its data, which stays shared with the caller.
     pub fn new(schema_mgr: Rc<SchemaConfig>) -> Result<Self> {
+        Self::new_arc(Arc::new(Rc::unwrap_or_clone(schema_mgr)))
+    }
+
+    pub fn new_arc(schema_mgr: Arc<SchemaConfig>) -> Result<Self> {
         let mut ts_nfas: Vec<NFA> = Vec::new();
         for schema in schema_mgr.get_ts_schemas() {
             let mut nfa = NFA::new();
@@ -94,22 +267,62 @@ impl Lexer {
         }
         let ts_dfa = DFA::from_multiple_nfas(ts_nfas);
 
-        let mut var_nfas: Vec<NFA> = Vec::new();
-        for schema in schema_mgr.get_var_schemas() {
-            let mut nfa = NFA::new();
-            nfa.add_ast_to_nfa(schema.get_ast(), nfa.get_start(), nfa.get_accept())?;
-            var_nfas.push(nfa);
+        // The NFAs are built in the schema order: the NFA index reported by the DFA upon acceptance
+        // is then the variable schema index, which is what `TokenType::Variable` carries.
+        let mut var_dfas: Vec<DFA> = Vec::new();
+        for delimiter_set_id in 0..schema_mgr.get_num_delimiter_sets() {
+            let mut var_nfas: Vec<NFA> = Vec::new();
+            for schema in schema_mgr.get_var_schemas() {
+                let mut nfa = NFA::new();
+                nfa.set_case_insensitive(schema.is_case_insensitive());
+                nfa.add_ast_to_nfa(schema.get_ast(), nfa.get_start(), nfa.get_accept())?;
+                // A variable declaring its allowed delimiters can't match across any other delimiter
+                nfa.remove_symbols(Self::get_disallowed_delimiters(
+                    &schema_mgr,
+                    delimiter_set_id,
+                    schema,
+                ));
+                var_nfas.push(nfa);
+            }
+            var_dfas.push(DFA::from_multiple_nfas(var_nfas));
         }
-        let var_dfa = DFA::from_multiple_nfas(var_nfas);
-        let var_dfa_root = var_dfa.get_root();
+        let var_switch_delimiters = schema_mgr
+            .get_var_schemas()
+            .iter()
+            .map(|schema| {
+                schema
+                    .get_switch_delimiters()
+                    .and_then(|name| schema_mgr.get_delimiter_set_id(name))
+            })
+            .collect();
 
-        Ok(Self {
+        Ok(Self::from_compiled_schema(CompiledSchema {
             schema_config: schema_mgr,
+            ts_dfa: Arc::new(ts_dfa),
+            var_dfas: Arc::new(var_dfas),
+            var_switch_delimiters: Arc::new(var_switch_delimiters),
+        }))
+    }
+
+    // Builds a lexer with the default settings from DFAs already compiled by `new`
+    pub(crate) fn from_compiled_schema(compiled_schema: CompiledSchema) -> Self {
+        let CompiledSchema {
+            schema_config,
+            ts_dfa,
+            var_dfas,
+            var_switch_delimiters,
+        } = compiled_schema;
+        let var_dfa_root = var_dfas[SchemaConfig::DEFAULT_DELIMITER_SET_ID].get_root();
+        Self {
+            schema_config,
             ts_dfa,
-            var_dfa,
+            var_dfas,
+            var_switch_delimiters,
+            active_delimiter_set: SchemaConfig::DEFAULT_DELIMITER_SET_ID,
             state: LexerState::ParsingTimestamp,
             dfa_state: var_dfa_root,
             input_stream: None,
+            is_fed: false,
             buf: Vec::new(),
             buf_cursor_pos: 0,
             token_queue: VecDeque::new(),
@@ -117,12 +330,106 @@ impl Lexer {
             last_tokenized_pos: 0,
             match_start_pos: 0,
             match_end_pos: 0,
+            match_at_line_start: false,
             line_num: 1,
-        })
+            line_start_positions: VecDeque::from([(1, Some(0))]),
+            token_line_start_pos: Some(0),
+            match_first_delimiter_pos: None,
+            last_accepted_match: None,
+            verify_reconstruction: false,
+            verified_pos: 0,
+            raw_line_mode: false,
+            mid_line_timestamps: false,
+            embedded_timestamps: false,
+            is_line_timestamped: false,
+            substring_matching: false,
+            gc_threshold: Self::DEFAULT_GC_THRESHOLD,
+            crlf_mode: CrlfMode::default(),
+            emit_static_text: true,
+            emit_delimiter_tokens: false,
+            emit_end_token: false,
+            has_emitted_end_token: false,
+            unmatched_bytes_mode: UnmatchedBytesMode::default(),
+            max_lookahead: None,
+            max_line_len: None,
+            input_line_num: 1,
+            input_line_len: 0,
+        }
+    }
+
+    // Returns a lexer sharing the compiled DFAs and the settings of this lexer, but with a fresh
+    // runtime state and no input stream, as if just reset. This is much cheaper than `new`, e.g.
+    // to lex several inputs in parallel with the same schema.
+    pub fn try_clone(&self) -> Self {
+        let mut lexer = Self::from_compiled_schema(self.get_compiled_schema());
+        lexer.verify_reconstruction = self.verify_reconstruction;
+        lexer.raw_line_mode = self.raw_line_mode;
+        lexer.mid_line_timestamps = self.mid_line_timestamps;
+        lexer.embedded_timestamps = self.embedded_timestamps;
+        lexer.substring_matching = self.substring_matching;
+        lexer.gc_threshold = self.gc_threshold;
+        lexer.crlf_mode = self.crlf_mode;
+        lexer.emit_static_text = self.emit_static_text;
+        lexer.emit_delimiter_tokens = self.emit_delimiter_tokens;
+        lexer.emit_end_token = self.emit_end_token;
+        lexer.unmatched_bytes_mode = self.unmatched_bytes_mode;
+        lexer.max_lookahead = self.max_lookahead;
+        lexer.max_line_len = self.max_line_len;
+        lexer
+    }
+
+    pub(crate) fn get_compiled_schema(&self) -> CompiledSchema {
+        CompiledSchema {
+            schema_config: self.schema_config.clone(),
+            ts_dfa: self.ts_dfa.clone(),
+            var_dfas: self.var_dfas.clone(),
+            var_switch_delimiters: self.var_switch_delimiters.clone(),
+        }
     }
 
-    fn reset(&mut self) {
+    pub(crate) fn get_schema_config(&self) -> &Arc<SchemaConfig> {
+        &self.schema_config
+    }
+
+    // Reverts the settings that drop input bytes from the returned tokens: `LogParser` finds the
+    // line ends and the byte offsets of the log events from the token bytes. The unmatched bytes
+    // are still reported, as `Unmatched` tokens.
+    pub(crate) fn keep_all_input_bytes(&mut self) {
+        self.emit_static_text = true;
+        self.crlf_mode = CrlfMode::Preserve;
+        if UnmatchedBytesMode::Error == self.unmatched_bytes_mode {
+            self.unmatched_bytes_mode = UnmatchedBytesMode::Unmatched;
+        }
+    }
+
+    fn get_disallowed_delimiters(
+        schema_config: &SchemaConfig,
+        delimiter_set_id: usize,
+        var_schema: &VarSchema,
+    ) -> u128 {
+        let mut disallowed_delimiters = 0u128;
+        for c in 0..128u8 {
+            if false == schema_config.has_delimiter_in_set(delimiter_set_id, c)
+                || var_schema.is_delimiter_allowed(c)
+            {
+                continue;
+            }
+            // `\n` always ends the line unless the variable is multiline
+            if b'\n' == c || var_schema.has_allowed_delimiters() {
+                disallowed_delimiters |= 1 << c;
+            }
+        }
+        disallowed_delimiters
+    }
+
+    // Clears the runtime state and detaches the input stream, but keeps the compiled DFAs and the
+    // settings. Compiling the DFAs is the costly part of `new`, so a single lexer should be reset
+    // and reused for many inputs, e.g. to lex the same in-memory data again. Until a new input
+    // stream is set, `get_next_token` fails with `LexerInputStreamNotSet`. `set_input_stream`
+    // resets the lexer on its own.
+    pub fn reset(&mut self) {
         self.input_stream = None;
+        self.is_fed = false;
         self.buf.clear();
         self.buf_cursor_pos = 0;
         self.token_queue.clear();
@@ -130,34 +437,349 @@ impl Lexer {
         self.last_tokenized_pos = 0;
         self.match_start_pos = 0;
         self.match_end_pos = 0;
+        self.match_at_line_start = false;
         self.line_num = 1;
+        self.line_start_positions = VecDeque::from([(1, Some(0))]);
+        self.token_line_start_pos = Some(0);
+        self.match_first_delimiter_pos = None;
+        self.last_accepted_match = None;
+        self.verified_pos = 0;
+        self.input_line_num = 1;
+        self.input_line_len = 0;
+        self.has_emitted_end_token = false;
+        self.active_delimiter_set = SchemaConfig::DEFAULT_DELIMITER_SET_ID;
         self.state = LexerState::ParsingTimestamp;
     }
 
+    fn get_var_dfa(&self) -> &DFA {
+        &self.var_dfas[self.active_delimiter_set]
+    }
+
+    // Delimiters are looked up in the active delimiter set
+    fn has_delimiter(&self, c: u8) -> bool {
+        self.schema_config
+            .has_delimiter_in_set(self.active_delimiter_set, c)
+    }
+
+    // Enables a debug mode that checks, every time the token queue is filled, that the emitted
+    // tokens exactly cover the consumed input: the concatenated token bytes must equal the input
+    // bytes since the last check, and no consumed byte may be left untokenized at the end of the
+    // stream. Any tokenization bug that skips, duplicates, or rewinds bytes (for example, a wrong
+    // `last_tokenized_pos` after a timestamp backtrack) fails with `ReconstructionMismatch`
+    // instead of silently producing corrupted tokens.
+    pub fn set_verify_reconstruction(&mut self, enabled: bool) {
+        self.verify_reconstruction = enabled;
+        self.verified_pos = self.last_tokenized_pos;
+    }
+
+    // In raw line mode, every line is emitted as a single static text token, skipping the
+    // timestamp and variable matching entirely. Suits inputs where each line is already a
+    // structured record (e.g. JSON lines).
+    pub fn set_raw_line_mode(&mut self, enabled: bool) {
+        self.raw_line_mode = enabled;
+    }
+
+    // By default, only a timestamp starting a line is matched, and a timestamp-looking substring
+    // later in the line (e.g. a response time after the request time) is lexed like any other
+    // text. When enabled, the timestamp schemas are also matched right after every delimiter, and
+    // such a match is emitted as a `Timestamp` token too. `LogParser` still starts a log event only
+    // at a timestamp starting a line, so the first timestamp remains the event timestamp.
+    pub fn set_mid_line_timestamps(&mut self, enabled: bool) {
+        self.mid_line_timestamps = enabled;
+    }
+
+    // When enabled, a line that doesn't start with a timestamp is matched for a timestamp right
+    // after each of its delimiters, like with `set_mid_line_timestamps`, until the first timestamp
+    // of the line, e.g. `INFO pid=123 2024-01-01 00:00:00 msg`. The text leading the timestamp is
+    // lexed as usual, and the timestamp acts like a timestamp starting the line (e.g. it switches
+    // the delimiter set if the schema says so).
+    pub fn set_embedded_timestamps(&mut self, enabled: bool) {
+        self.embedded_timestamps = enabled;
+    }
+
+    // By default, a variable is only extracted if its match is bounded by delimiters (or line
+    // boundaries) on both sides. When enabled, the variables are matched as substrings instead:
+    // the var DFA is attempted at every position, and the longest match is extracted even if it's
+    // surrounded by other bytes, e.g. the IP of `host=10.0.0.1:8080`. The matching then resumes
+    // right after the extracted variable, or at the byte following the attempted position if
+    // nothing matches.
+    pub fn set_substring_matching(&mut self, enabled: bool) {
+        self.substring_matching = enabled;
+    }
+
+    // The internal buffer is compacted once more than `bytes` (4096 by default) of it, and more
+    // than half of it, have been emitted as tokens. A lower threshold bounds the memory retained
+    // after long lines at the cost of more frequent compactions.
+    pub fn set_gc_threshold(&mut self, bytes: usize) {
+        self.gc_threshold = bytes;
+    }
+
+    // Caps the number of consumed bytes that aren't emitted as tokens yet (see `pending_bytes`), so
+    // that the lexer finalizes its tokens within a bounded amount of input, e.g. when a paused
+    // stream would otherwise be held back indefinitely. Once the cap is reached, the pending static
+    // text is emitted, and a variable match still in progress is abandoned: it's rewound to its
+    // last accepted match if it has consumed delimiters, or emitted as static text otherwise. A
+    // variable or static text token is then never longer than the cap, except a timestamp. `None`
+    // (the default) means no cap.
+    pub fn set_max_lookahead(&mut self, max_lookahead: Option<usize>) {
+        self.max_lookahead = max_lookahead.map(|bytes| bytes.max(1));
+    }
+
+    // Caps the number of bytes of a line, excluding its `\n`. Since a line can't be tokenized
+    // before its end is known, the internal buffer may otherwise grow with an arbitrarily long
+    // line, e.g. a malformed input without any line break. Once a line exceeds the cap, the lexer
+    // fails with `LineTooLong`, and the rest of the input stream can't be tokenized. `None` (the
+    // default) means no cap.
+    pub fn set_max_line_len(&mut self, max_line_len: Option<usize>) {
+        self.max_line_len = max_line_len;
+    }
+
+    // The number of states and transitions of the compiled timestamp DFA, to spot schemas whose
+    // DFA gets unexpectedly large
+    pub fn ts_dfa_stats(&self) -> (usize, usize) {
+        (self.ts_dfa.num_states(), self.ts_dfa.num_transitions())
+    }
+
+    // Same as `ts_dfa_stats` for the variable DFAs, indexed by delimiter set ID (see
+    // `SchemaConfig::get_delimiter_set_id`)
+    pub fn var_dfa_stats(&self) -> Vec<(usize, usize)> {
+        self.var_dfas
+            .iter()
+            .map(|var_dfa| (var_dfa.num_states(), var_dfa.num_transitions()))
+            .collect()
+    }
+
+    // A handle on the variable DFA of the default delimiter set, to run the lexer's longest-match
+    // scan over arbitrary buffers
+    pub fn var_matcher(&self) -> VarMatcher<'_> {
+        VarMatcher::new(&self.var_dfas[SchemaConfig::DEFAULT_DELIMITER_SET_ID])
+    }
+
+    // The number of input bytes currently held by the internal buffer, including the bytes already
+    // emitted as tokens but not compacted yet.
+    pub fn get_buf_size(&self) -> usize {
+        self.buf.len()
+    }
+
+    // When disabled, only the timestamp and variable tokens are returned: the static text tokens
+    // are still generated internally, so the line numbers of the returned tokens are unaffected,
+    // but are dropped from the output. `LogParser` needs the static text, since line-based log
+    // event boundaries rely on the `StaticTextWithEndLine` tokens: `LogParser::from_lexer`
+    // re-enables it.
+    pub fn set_emit_static_text(&mut self, enabled: bool) {
+        self.emit_static_text = enabled;
+    }
+
+    // When enabled, every run of delimiters within static text is emitted as its own `Delimiter`
+    // token, so that the remaining static text tokens hold no delimiter. The run ending a line
+    // (e.g. ` \n` or `\r\n`) is still emitted as `StaticTextWithEndLine`.
+    pub fn set_emit_delimiter_tokens(&mut self, enabled: bool) {
+        self.emit_delimiter_tokens = enabled;
+    }
+
+    // When enabled, the end of the input stream is signaled by an `End` token before
+    // `get_next_token` returns `None`, e.g. for state machines that expect an explicit terminal
+    // token. The `End` token is emitted exactly once per input stream.
+    pub fn set_emit_end_token(&mut self, enabled: bool) {
+        self.emit_end_token = enabled;
+    }
+
+    // Defaults to `UnmatchedBytesMode::StaticText`. The other modes let strict pipelines flag the
+    // lines with content that the schema doesn't model.
+    pub fn set_unmatched_bytes_mode(&mut self, unmatched_bytes_mode: UnmatchedBytesMode) {
+        self.unmatched_bytes_mode = unmatched_bytes_mode;
+    }
+
+    pub fn set_crlf_mode(&mut self, crlf_mode: CrlfMode) {
+        self.crlf_mode = crlf_mode;
+    }
+
     pub fn set_input_stream(&mut self, input_stream: Box<dyn LexerStream>) {
         self.reset();
         self.input_stream = Some(input_stream);
         self.state = LexerState::ParsingTimestamp;
     }
 
+    // Same as `set_input_stream`, numbering the lines from `start_line` instead of 1, e.g. to map
+    // the tokens of a chunk of a larger file back to the lines of the file
+    pub fn set_input_stream_with_line_offset(
+        &mut self,
+        input_stream: Box<dyn LexerStream>,
+        start_line: usize,
+    ) {
+        self.set_input_stream(input_stream);
+        self.line_num = start_line;
+        self.line_start_positions = VecDeque::from([(start_line, Some(0))]);
+        self.input_line_num = start_line;
+    }
+
+    // Tokenizes a single line in isolation, numbering its tokens from `base_line_num`. Since the
+    // lexer restarts from the timestamp state at every line boundary, the tokens are the same as
+    // the ones the line gets when lexed as part of the whole input. However, the caller is
+    // responsible for re-grouping the line into its log event: a line without a timestamp
+    // belongs to the multi-line log event started by a previous line. Any previously set input
+    // stream is discarded.
+    pub fn retokenize_line(&mut self, line: &str, base_line_num: usize) -> Result<Vec<Token>> {
+        self.set_input_stream_with_line_offset(Box::new(StringStream::new(line)), base_line_num);
+        let mut tokens = Vec::new();
+        while let Some(token) = self.get_next_token()? {
+            tokens.push(token);
+        }
+        self.input_stream = None;
+        Ok(tokens)
+    }
+
+    // Pushes the given bytes as the next chunk of the input, and returns the tokens that can be
+    // completed so far. This inverts the control of `get_next_token`, e.g. for an async task
+    // feeding the chunks as they arrive. The first call starts a new input, discarding any
+    // previously set input stream, and `finish` ends it. The tokens are the same as if the whole
+    // input was lexed at once, regardless of how it's split into chunks.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Vec<Token>> {
+        if false == self.is_fed {
+            self.set_input_stream(Box::new(FedInputStream::new(false)));
+            self.is_fed = true;
+        }
+        // The input stream only pauses, the fed bytes are read straight from the buffer
+        for c in bytes {
+            self.buf.push(*c);
+            self.check_input_line_len(*c)?;
+        }
+        self.collect_available_tokens()
+    }
+
+    // Ends the input started by `feed`, and returns its remaining tokens. Fails with
+    // `LexerInputStreamNotSet` if no input has been fed since the last reset.
+    pub fn finish(&mut self) -> Result<Vec<Token>> {
+        if false == self.is_fed {
+            return Err(LexerInputStreamNotSet);
+        }
+        self.input_stream = Some(Box::new(FedInputStream::new(true)));
+        let tokens = self.collect_available_tokens()?;
+        self.reset();
+        Ok(tokens)
+    }
+
+    fn collect_available_tokens(&mut self) -> Result<Vec<Token>> {
+        let mut tokens = Vec::new();
+        loop {
+            match self.get_next_token() {
+                Ok(Some(token)) => tokens.push(token),
+                Ok(None) => return Ok(tokens),
+                Err(Error::IOError(e)) if std::io::ErrorKind::WouldBlock == e.kind() => {
+                    return Ok(tokens)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Returns the bytes of the line of the last returned token read so far, excluding its line
+    // ending, e.g. to show the context of the token. A line stays current until a token of the
+    // next line is returned, so the line of a line ending token is complete, while the line of
+    // another token is only read up to the delimiter following it. Before any token is returned,
+    // this is the line of the cursor. `None` if the start of the line has already been compacted
+    // away from the internal buffer, i.e. for a line longer than the GC threshold (see
+    // `set_gc_threshold`).
+    pub fn current_line(&self) -> Option<&[u8]> {
+        let line = &self.buf[self.token_line_start_pos?..];
+        let line = match line.iter().position(|c| b'\n' == *c) {
+            Some(line_end_pos) => &line[..line_end_pos],
+            None => line,
+        };
+        Some(line.strip_suffix(b"\r").unwrap_or(line))
+    }
+
+    // Returns the bytes consumed from the input stream that aren't emitted as tokens yet, such as
+    // a variable still being matched. Useful for showing in-progress input when the input stream
+    // is paused (see `LexerStream`).
+    pub fn pending_bytes(&self) -> &[u8] {
+        &self.buf[self.last_tokenized_pos..self.buf_cursor_pos]
+    }
+
     pub fn get_next_token(&mut self) -> Result<Option<Token>> {
+        self.prepare_next_token()?;
+        let token = self.pop_token();
+        if let Some(token) = token.as_ref() {
+            self.token_line_start_pos = match self.line_start_positions.front() {
+                Some((line_num, line_start_pos)) if token.get_line_num() == *line_num => {
+                    *line_start_pos
+                }
+                _ => None,
+            };
+        }
+        Ok(token)
+    }
+
+    // Pops the front token of the queue, and forgets the starts of the lines before its line
+    fn pop_token(&mut self) -> Option<Token> {
+        let token = self.token_queue.pop_front()?;
+        while 1 < self.line_start_positions.len()
+            && self.line_start_positions[0].0 < token.get_line_num()
+        {
+            self.line_start_positions.pop_front();
+        }
+        Some(token)
+    }
+
+    // Feeds all the remaining tokens of the input stream to the given sink, in order
+    pub fn run_to_sink(&mut self, sink: &mut impl TokenSink) -> Result<()> {
+        while let Some(token) = self.get_next_token()? {
+            sink.accept(&token)?;
+        }
+        Ok(())
+    }
+
+    // Returns the next token without consuming it: the following `get_next_token` returns the
+    // same token. The token stays in the token queue, so the lexer position isn't affected.
+    pub fn peek_next_token(&mut self) -> Result<Option<&Token>> {
+        self.prepare_next_token()?;
+        Ok(self.token_queue.front())
+    }
+
+    // Fills the token queue if it's empty, and drops the static text tokens at its front if they
+    // aren't emitted, so that the front token is the next token to return (if any)
+    fn prepare_next_token(&mut self) -> Result<()> {
         if self.input_stream.is_none() {
             return Err(LexerInputStreamNotSet);
         }
-        if self.token_queue.is_empty() {
-            self.fill_token_queue()?;
+        loop {
+            if self.token_queue.is_empty() {
+                self.fill_token_queue()?;
+            }
+            match self.token_queue.front() {
+                Some(token) if false == self.emit_static_text && token.is_static_text() => {
+                    self.pop_token();
+                }
+                Some(token)
+                    if UnmatchedBytesMode::Error == self.unmatched_bytes_mode
+                        && token.is_unmatched() =>
+                {
+                    let token = self.pop_token().unwrap();
+                    return Err(UnmatchedContent(Box::new(UnmatchedContentInfo {
+                        line: token.get_line_num(),
+                        text: token.get_buf_as_string(),
+                    })));
+                }
+                _ => return Ok(()),
+            }
         }
-        Ok(self.token_queue.pop_front())
     }
 
     fn fill_token_queue(&mut self) -> Result<()> {
         loop {
+            self.enforce_max_lookahead()?;
+            if false == self.token_queue.is_empty() {
+                break;
+            }
             match self.state {
                 LexerState::SeekingToTheNextDelimiter => match self.get_next_char_from_buffer()? {
                     Some(c) => {
-                        if self.schema_config.has_delimiter(c) {
+                        if self.has_delimiter(c) || self.is_line_ending_cr(c)? {
                             self.last_delimiter = Some(c);
                             self.state = LexerState::HandleDelimiter;
+                        } else if self.substring_matching {
+                            self.set_buf_cursor_pos(self.buf_cursor_pos - 1);
+                            self.proceed_to_var_dfa_simulation();
                         }
                     }
                     None => {
@@ -174,30 +796,68 @@ impl Lexer {
                     self.last_delimiter = None;
                     match delimiter {
                         b'\n' => {
-                            self.generate_token(
-                                self.buf_cursor_pos,
-                                TokenType::StaticTextWithEndLine,
-                            )?;
-                            self.line_num += 1;
+                            self.generate_end_of_line_token()?;
+                            self.active_delimiter_set = SchemaConfig::DEFAULT_DELIMITER_SET_ID;
                             self.state = LexerState::ParsingTimestamp;
                         }
+                        _ if self.mid_line_timestamps
+                            || (self.embedded_timestamps && false == self.is_line_timestamped) =>
+                        {
+                            match self.try_parse_timestamp_at_cursor() {
+                                Ok(true) => {
+                                    if false == self.is_line_timestamped {
+                                        self.is_line_timestamped = true;
+                                        if self.embedded_timestamps {
+                                            self.switch_to_ts_delimiter_set();
+                                        }
+                                    }
+                                    self.state = LexerState::SeekingToTheNextDelimiter;
+                                }
+                                Ok(false) => self.proceed_to_var_dfa_simulation(),
+                                Err(e) => {
+                                    // Keeps the lexer resumable if the input stream is only paused
+                                    self.last_delimiter = Some(delimiter);
+                                    return Err(e);
+                                }
+                            }
+                        }
                         _ => self.proceed_to_var_dfa_simulation(),
                     }
                 }
 
                 LexerState::ParsingTimestamp => {
-                    if self.try_parse_timestamp()? {
+                    if self.raw_line_mode {
+                        self.state = LexerState::SeekingToTheEndOfLine;
+                    } else if self.try_parse_timestamp_at_cursor()? {
+                        self.is_line_timestamped = true;
+                        self.switch_to_ts_delimiter_set();
                         self.state = LexerState::SeekingToTheNextDelimiter;
                     } else {
+                        self.is_line_timestamped = false;
                         self.proceed_to_var_dfa_simulation();
+                        self.match_at_line_start = true;
                     }
                 }
 
+                LexerState::SeekingToTheEndOfLine => match self.get_next_char_from_buffer()? {
+                    Some(b'\n') => {
+                        self.generate_end_of_line_token()?;
+                        self.state = LexerState::ParsingTimestamp;
+                    }
+                    Some(_) => {}
+                    None => self.state = LexerState::EndOfStream,
+                },
+
                 LexerState::DFANotAccepted => match self.get_next_char_from_buffer()? {
                     Some(c) => {
-                        self.simulate_var_dfa_and_set_lexer_state(c, LexerState::HandleDelimiter)
+                        self.simulate_var_dfa_and_set_lexer_state(c, LexerState::HandleDelimiter)?
+                    }
+                    None if self.substring_matching => self.end_substring_match(),
+                    None => {
+                        if false == self.rewind_failed_match() {
+                            self.state = LexerState::EndOfStream;
+                        }
                     }
-                    None => self.state = LexerState::EndOfStream,
                 },
 
                 LexerState::DFAAccepted => {
@@ -205,7 +865,7 @@ impl Lexer {
                     self.match_end_pos = self.buf_cursor_pos;
                     match self.get_next_char_from_buffer()? {
                         Some(c) => {
-                            self.simulate_var_dfa_and_set_lexer_state(c, LexerState::VarExtract)
+                            self.simulate_var_dfa_and_set_lexer_state(c, LexerState::VarExtract)?
                         }
                         None => self.state = LexerState::VarExtract,
                     }
@@ -224,24 +884,35 @@ impl Lexer {
                         self.generate_token(self.match_start_pos, TokenType::StaticText)?;
                     }
 
-                    // Extract variable
-                    match self.var_dfa.is_accept_state(self.dfa_state.clone()) {
-                        Some(schema_id) => {
-                            assert_eq!(self.match_start_pos, self.last_tokenized_pos);
-                            self.generate_token(
-                                self.match_end_pos,
-                                TokenType::Variable(schema_id),
-                            )?;
+                    // Extract variable. If no accepted variable has its anchors satisfied, the
+                    // match is left untokenized to become part of the following static text.
+                    if let Some(schema_id) = self.get_matched_var_id()? {
+                        assert_eq!(self.match_start_pos, self.last_tokenized_pos);
+                        let var_schema = &self.schema_config.get_var_schemas()[schema_id];
+                        let matched = &self.buf[self.match_start_pos..self.match_end_pos];
+                        let (var_start, var_end) = var_schema.get_stripped_range(matched);
+                        var_schema.check_allowed_bytes(&matched[var_start..var_end])?;
+                        let var_start_pos = self.match_start_pos + var_start;
+                        let var_end_pos = self.match_start_pos + var_end;
+                        // The stripped enclosing pair, if any, is static text
+                        if var_start_pos != self.match_start_pos {
+                            self.generate_token(var_start_pos, TokenType::StaticText)?;
+                        }
+                        self.generate_token(var_end_pos, TokenType::Variable(schema_id))?;
+                        if var_end_pos != self.match_end_pos {
+                            self.generate_token(self.match_end_pos, TokenType::StaticText)?;
                         }
-                        None => {
-                            return Err(LexerInternalErr(
-                                "DFA state doesn't stop in an accepted state",
-                            ))
+                        if let Some(delimiter_set_id) = self.var_switch_delimiters[schema_id] {
+                            self.active_delimiter_set = delimiter_set_id;
                         }
                     }
 
                     match self.last_delimiter {
                         Some(_) => self.state = LexerState::HandleDelimiter,
+                        // Only in substring matching mode, with more input after the match
+                        None if self.buf_cursor_pos < self.buf.len() => {
+                            self.state = LexerState::SeekingToTheNextDelimiter
+                        }
                         None => self.state = LexerState::EndOfStream,
                     }
                 }
@@ -257,6 +928,13 @@ impl Lexer {
                             TokenType::StaticText
                         };
                         self.generate_token(self.buf_cursor_pos, token_type)?;
+                    } else if self.emit_end_token && false == self.has_emitted_end_token {
+                        self.has_emitted_end_token = true;
+                        self.token_queue.push_back(Token {
+                            buf: Vec::new(),
+                            line_num: self.line_num,
+                            token_type: TokenType::End,
+                        });
                     }
                     break;
                 }
@@ -267,54 +945,92 @@ impl Lexer {
             }
         }
 
+        if self.verify_reconstruction {
+            self.verify_token_queue_reconstruction()?;
+        }
         self.buffer_garbage_collection();
         Ok(())
     }
 
-    fn try_parse_timestamp(&mut self) -> Result<bool> {
-        let buf_cursor_pos_bookmark = self.buf_cursor_pos;
-        if buf_cursor_pos_bookmark != self.last_tokenized_pos {
-            return Err(LexerInternalErr("Timestamp parsing corrupted"));
-        }
-        let mut curr_dfa_state = self.ts_dfa.get_root();
-
-        // (Timestamp schema ID, position)
-        let mut last_matched: Option<(usize, usize)> = None;
-
-        loop {
-            let optional_c = self.get_next_char_from_buffer()?;
-            if optional_c.is_none() {
-                break;
-            }
-
-            let c = optional_c.unwrap();
-            if false == c.is_ascii() {
-                break;
-            }
-
-            let optional_next_state = self.ts_dfa.get_next_state(curr_dfa_state.clone(), c as u8);
-            if optional_next_state.is_none() {
-                break;
+    fn verify_token_queue_reconstruction(&mut self) -> Result<()> {
+        let mut pos = self.verified_pos;
+        for token in &self.token_queue {
+            let end_pos = pos + token.get_buf().len();
+            if end_pos > self.buf.len() || token.get_buf() != &self.buf[pos..end_pos] {
+                return Err(ReconstructionMismatch);
             }
-            curr_dfa_state = optional_next_state.unwrap();
+            pos = end_pos;
+        }
+        if pos != self.last_tokenized_pos {
+            return Err(ReconstructionMismatch);
+        }
+        if self.token_queue.is_empty() && self.last_tokenized_pos != self.buf.len() {
+            // The stream is exhausted but some consumed bytes were never emitted
+            return Err(ReconstructionMismatch);
+        }
+        self.verified_pos = pos;
+        Ok(())
+    }
 
-            match self.ts_dfa.is_accept_state(curr_dfa_state.clone()) {
-                Some(ts_schema_id) => last_matched = Some((ts_schema_id, self.buf_cursor_pos)),
-                None => {}
-            }
+    // Switches the active delimiter set on the timestamp of the line, if the schema says so
+    fn switch_to_ts_delimiter_set(&mut self) {
+        if let Some(delimiter_set_id) = self.schema_config.get_ts_switch_delimiters() {
+            self.active_delimiter_set = delimiter_set_id;
         }
+    }
 
-        match last_matched {
+    // Tries to match a timestamp at the cursor, which may be anywhere in the line, e.g. after a
+    // delimiter with mid-line timestamps. On a match, the pending static text before the cursor is
+    // tokenized first. Otherwise, no input is consumed. The active delimiter set is left
+    // untouched, since it's only switched by the timestamp of the line.
+    fn try_parse_timestamp_at_cursor(&mut self) -> Result<bool> {
+        if self.buf_cursor_pos < self.last_tokenized_pos {
+            return Err(LexerInternalErr("Timestamp parsing corrupted"));
+        }
+        let ts_start_pos = self.buf_cursor_pos;
+        match self.match_timestamp()? {
             Some((ts_schema_id, pos)) => {
+                if ts_start_pos != self.last_tokenized_pos {
+                    self.generate_token(ts_start_pos, TokenType::StaticText)?;
+                }
                 self.generate_token(pos, TokenType::Timestamp(ts_schema_id))?;
-                self.buf_cursor_pos = pos;
                 Ok(true)
             }
-            None => {
-                self.buf_cursor_pos = buf_cursor_pos_bookmark;
-                Ok(false)
+            None => Ok(false),
+        }
+    }
+
+    // Returns the (timestamp schema ID, end position) of the longest timestamp starting at the
+    // cursor, leaving the cursor at the end of the match. The cursor is left untouched if there's
+    // no match.
+    fn match_timestamp(&mut self) -> Result<Option<(usize, usize)>> {
+        let buf_cursor_pos_bookmark = self.buf_cursor_pos;
+        let mut tracker = LongestMatchTracker::new(&self.ts_dfa);
+        loop {
+            let c = match self.get_next_char_from_buffer() {
+                Ok(Some(c)) => c,
+                Ok(None) => break,
+                Err(e) => {
+                    // Keeps the lexer resumable if the input stream is only paused
+                    self.buf_cursor_pos = buf_cursor_pos_bookmark;
+                    return Err(e);
+                }
+            };
+            if false == tracker.consume(&self.ts_dfa, c) {
+                break;
             }
         }
+
+        // (Timestamp schema ID, position). An empty match can't make a timestamp token.
+        let last_matched = tracker
+            .get_longest_match()
+            .filter(|(_, len)| 0 < *len)
+            .map(|(ts_schema_id, len)| (ts_schema_id, buf_cursor_pos_bookmark + len));
+        self.buf_cursor_pos = match last_matched {
+            Some((_, pos)) => pos,
+            None => buf_cursor_pos_bookmark,
+        };
+        Ok(last_matched)
     }
 
     fn get_next_char_from_buffer(&mut self) -> Result<Option<u8>> {
@@ -327,7 +1043,10 @@ impl Lexer {
                 .as_mut()
                 .get_next_char()?
             {
-                Some(c) => self.buf.push(c),
+                Some(c) => {
+                    self.buf.push(c);
+                    self.check_input_line_len(c)?;
+                }
                 None => return Ok(None),
             }
         }
@@ -335,55 +1054,330 @@ impl Lexer {
         Ok(Some(self.buf[pos]))
     }
 
+    // Counts the given byte just read from the input stream towards the length of its line
+    fn check_input_line_len(&mut self, c: u8) -> Result<()> {
+        if b'\n' == c {
+            self.input_line_num += 1;
+            self.input_line_len = 0;
+            return Ok(());
+        }
+        self.input_line_len += 1;
+        match self.max_line_len {
+            Some(limit) if self.input_line_len > limit => Err(LineTooLong {
+                line_num: self.input_line_num,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn peek_next_char_from_buffer(&mut self) -> Result<Option<u8>> {
+        let c = self.get_next_char_from_buffer()?;
+        if c.is_some() {
+            self.set_buf_cursor_pos(self.buf_cursor_pos - 1);
+        }
+        Ok(c)
+    }
+
+    // Whether the given byte, just consumed, is the `\r` of a `\r\n` line ending while `\r` isn't
+    // a delimiter. Such a `\r` still ends the token before it, like a delimiter, so that the last
+    // variable of a CRLF line is matched whatever the delimiters.
+    fn is_line_ending_cr(&mut self, c: u8) -> Result<bool> {
+        if b'\r' != c || self.has_delimiter(c) {
+            return Ok(false);
+        }
+        match self.peek_next_char_from_buffer() {
+            Ok(next_c) => Ok(Some(b'\n') == next_c),
+            Err(e) => {
+                // Keeps the lexer resumable if the input stream is only paused
+                self.set_buf_cursor_pos(self.buf_cursor_pos - 1);
+                Err(e)
+            }
+        }
+    }
+
+    // Same as `is_line_ending_cr` for a byte in the buffer, without reading the input stream
+    fn is_line_ending_cr_at(&self, pos: usize) -> bool {
+        b'\r' == self.buf[pos]
+            && false == self.has_delimiter(b'\r')
+            && Some(&b'\n') == self.buf.get(pos + 1)
+    }
+
     fn capture_delimiter(&mut self, c: u8) -> bool {
-        if self.schema_config.has_delimiter(c) {
+        if self.has_delimiter(c) {
             self.last_delimiter = Some(c);
             return true;
         }
         false
     }
 
-    fn simulate_var_dfa_and_set_lexer_state(&mut self, c: u8, delimiter_dst_state: LexerState) {
-        match self.var_dfa.get_next_state(self.dfa_state.clone(), c) {
+    fn simulate_var_dfa_and_set_lexer_state(
+        &mut self,
+        c: u8,
+        delimiter_dst_state: LexerState,
+    ) -> Result<()> {
+        let is_delimiter = self.has_delimiter(c) || self.is_line_ending_cr(c)?;
+        match self.get_var_dfa().get_next_state(self.dfa_state.clone(), c) {
             Some(next_dfa_state) => {
                 self.dfa_state = next_dfa_state;
-                match self.var_dfa.is_accept_state(self.dfa_state.clone()) {
-                    Some(_) => self.state = LexerState::DFAAccepted,
+                if is_delimiter && self.match_first_delimiter_pos.is_none() {
+                    self.match_first_delimiter_pos = Some(self.buf_cursor_pos - 1);
+                }
+                match self.get_var_dfa().is_accept_state(self.dfa_state.clone()) {
+                    Some(_) => {
+                        self.last_accepted_match =
+                            Some((self.buf_cursor_pos, self.dfa_state.clone()));
+                        self.state = LexerState::DFAAccepted;
+                    }
                     None => self.state = LexerState::DFANotAccepted,
                 }
             }
+            None if self.substring_matching => self.end_substring_match(),
             None => {
-                self.state = if self.capture_delimiter(c) {
+                if self.rewind_failed_match() {
+                    return Ok(());
+                }
+                self.state = if is_delimiter {
+                    self.last_delimiter = Some(c);
                     delimiter_dst_state
                 } else {
                     LexerState::SeekingToTheNextDelimiter
                 };
             }
         }
+        Ok(())
+    }
+
+    // Called when the var DFA can't extend the current match any further. If the match has
+    // consumed delimiters (only possible for patterns matching delimiters, or multiline ones),
+    // the consumed input can't be skipped as static text up to the next delimiter: a variable
+    // could start right after the first consumed delimiter, and a consumed `\n` would be lost.
+    // Instead, the lexer rewinds to the last accepted match if it's followed by a delimiter, or to
+    // the first consumed delimiter otherwise. Returns whether the lexer has been rewound.
+    fn rewind_failed_match(&mut self) -> bool {
+        let delimiter_pos = match self.match_first_delimiter_pos.take() {
+            Some(delimiter_pos) => delimiter_pos,
+            None => return false,
+        };
+        if let Some((end_pos, dfa_state)) = self.last_accepted_match.take() {
+            let next_c = self.buf[end_pos];
+            if self.has_delimiter(next_c) || self.is_line_ending_cr_at(end_pos) {
+                self.dfa_state = dfa_state;
+                self.match_end_pos = end_pos;
+                self.set_buf_cursor_pos(end_pos + 1);
+                self.last_delimiter = Some(next_c);
+                self.state = LexerState::VarExtract;
+                return true;
+            }
+        }
+        self.set_buf_cursor_pos(delimiter_pos + 1);
+        self.last_delimiter = Some(self.buf[delimiter_pos]);
+        self.state = LexerState::HandleDelimiter;
+        true
+    }
+
+    // Called in substring matching mode when the var DFA can't extend the current match any
+    // further. The last accepted match, if any, is extracted regardless of the byte following it.
+    // Otherwise, the matching restarts at the byte following the start of the failed match, which
+    // may be a delimiter to handle (e.g. a `\n` consumed by a multiline variable).
+    fn end_substring_match(&mut self) {
+        self.match_first_delimiter_pos = None;
+        if let Some((end_pos, dfa_state)) = self.last_accepted_match.take() {
+            self.dfa_state = dfa_state;
+            self.match_end_pos = end_pos;
+            self.last_delimiter = None;
+            match self.buf.get(end_pos) {
+                Some(c) if self.has_delimiter(*c) => {
+                    self.last_delimiter = Some(*c);
+                    self.set_buf_cursor_pos(end_pos + 1);
+                }
+                _ => self.set_buf_cursor_pos(end_pos),
+            }
+            self.state = LexerState::VarExtract;
+            return;
+        }
+        let c = match self.buf.get(self.match_start_pos) {
+            Some(c) => *c,
+            None => {
+                self.state = LexerState::EndOfStream;
+                return;
+            }
+        };
+        self.set_buf_cursor_pos(self.match_start_pos + 1);
+        self.state = if self.capture_delimiter(c) {
+            LexerState::HandleDelimiter
+        } else {
+            LexerState::SeekingToTheNextDelimiter
+        };
+    }
+
+    // Returns the highest-priority variable accepted by the DFA whose anchors are satisfied by the
+    // match boundaries.
+    fn get_matched_var_id(&mut self) -> Result<Option<usize>> {
+        // A match followed by `\r\n` also ends the line
+        let match_at_line_end = match self.last_delimiter {
+            Some(b'\r') if CrlfMode::TreatCrAsDelimiter != self.crlf_mode => {
+                matches!(self.peek_next_char_from_buffer()?, Some(b'\n') | None)
+            }
+            Some(delimiter) => b'\n' == delimiter,
+            // Either the end of the stream, or a substring match followed by other bytes
+            None => self.buf_cursor_pos == self.buf.len(),
+        };
+        let var_ids = self
+            .get_var_dfa()
+            .get_accepted_nfa_ids(self.dfa_state.clone());
+        if var_ids.is_empty() {
+            return Err(LexerInternalErr(
+                "DFA state doesn't stop in an accepted state",
+            ));
+        }
+        let var_schemas = self.schema_config.get_var_schemas();
+        Ok(var_ids.iter().copied().find(|var_id| {
+            let var_schema = &var_schemas[*var_id];
+            (self.match_at_line_start || false == var_schema.is_start_anchored())
+                && (match_at_line_end || false == var_schema.is_end_anchored())
+        }))
+    }
+
+    fn enforce_max_lookahead(&mut self) -> Result<()> {
+        let max_lookahead = match self.max_lookahead {
+            Some(max_lookahead) => max_lookahead,
+            None => return Ok(()),
+        };
+        let is_exceeded = |lexer: &Self| lexer.pending_bytes().len() >= max_lookahead;
+        if false == is_exceeded(self) {
+            return Ok(());
+        }
+        match self.state {
+            LexerState::SeekingToTheNextDelimiter | LexerState::SeekingToTheEndOfLine => {}
+            LexerState::DFANotAccepted | LexerState::DFAAccepted => {
+                // The static text before the match is final
+                if self.match_start_pos > self.last_tokenized_pos {
+                    self.generate_token(self.match_start_pos, TokenType::StaticText)?;
+                    if false == is_exceeded(self) {
+                        return Ok(());
+                    }
+                }
+                if self.rewind_failed_match() {
+                    return Ok(());
+                }
+                self.state = LexerState::SeekingToTheNextDelimiter;
+            }
+            _ => return Ok(()),
+        }
+        self.generate_token(self.buf_cursor_pos, TokenType::StaticText)
     }
 
     fn proceed_to_var_dfa_simulation(&mut self) {
+        self.match_at_line_start = false;
+        self.match_first_delimiter_pos = None;
+        self.last_accepted_match = None;
         self.match_start_pos = self.buf_cursor_pos;
-        self.dfa_state = self.var_dfa.get_root();
+        self.dfa_state = self.get_var_dfa().get_root();
         self.state = LexerState::DFANotAccepted;
     }
 
+    // Tokenizes up to the `\n` just consumed, handling a preceding `\r` as per the CRLF mode
+    fn generate_end_of_line_token(&mut self) -> Result<()> {
+        let end_pos = self.buf_cursor_pos;
+        let cr_pos = end_pos.checked_sub(2);
+        let has_cr = cr_pos
+            .is_some_and(|cr_pos| cr_pos >= self.last_tokenized_pos && b'\r' == self.buf[cr_pos]);
+        match self.crlf_mode {
+            CrlfMode::TreatCrAsDelimiter if has_cr => {
+                self.generate_token(end_pos - 1, TokenType::StaticText)?;
+                self.generate_token(end_pos, TokenType::StaticTextWithEndLine)
+            }
+            CrlfMode::Strip if has_cr => {
+                // Dropped before the token may get split into delimiter and unmatched runs
+                let mut token_buf = self.buf[self.last_tokenized_pos..end_pos].to_vec();
+                token_buf.remove(token_buf.len() - 2);
+                self.push_token(token_buf, end_pos, TokenType::StaticTextWithEndLine);
+                Ok(())
+            }
+            _ => self.generate_token(end_pos, TokenType::StaticTextWithEndLine),
+        }
+    }
+
     fn generate_token(&mut self, end_pos: usize, token_type: TokenType) -> Result<()> {
         if end_pos <= self.last_tokenized_pos {
             return Err(LexerInternalErr("Tokenization end position corrupted"));
         }
-        self.token_queue.push_back(Token {
-            buf: self.buf[self.last_tokenized_pos..end_pos]
-                .iter()
-                .map(|c| c.clone())
-                .collect(),
-            line_num: self.line_num,
-            token_type,
-        });
-        self.last_tokenized_pos = end_pos;
+        let token_buf = self.buf[self.last_tokenized_pos..end_pos].to_vec();
+        self.push_token(token_buf, end_pos, token_type);
         Ok(())
     }
 
+    // Queues the given bytes as the token of the input up to `end_pos`. The bytes are the ones of
+    // the input, unless the CRLF mode strips a `\r`.
+    fn push_token(&mut self, token_buf: Vec<u8>, end_pos: usize, token_type: TokenType) {
+        match token_type {
+            TokenType::StaticText | TokenType::StaticTextWithEndLine
+                if self.emit_delimiter_tokens
+                    || UnmatchedBytesMode::StaticText != self.unmatched_bytes_mode =>
+            {
+                self.push_static_text_tokens(token_buf, end_pos, token_type)
+            }
+            _ => self.token_queue.push_back(Token {
+                buf: token_buf,
+                line_num: self.line_num,
+                token_type,
+            }),
+        }
+        // A token carries the line number where it starts, e.g. a multiline variable, and the
+        // line ending token the number of the line it ends
+        for pos in self.last_tokenized_pos..end_pos {
+            if b'\n' == self.buf[pos] {
+                self.line_num += 1;
+                self.line_start_positions
+                    .push_back((self.line_num, Some(pos + 1)));
+            }
+        }
+        self.last_tokenized_pos = end_pos;
+    }
+
+    // Splits static text into runs of delimiters and runs of other bytes. Runs of other bytes are
+    // reported as `Unmatched` unless the unmatched bytes mode is `StaticText`. A line ending is
+    // always the last delimiter run of a `StaticTextWithEndLine` token, which keeps its token type.
+    // The `\r` of a `\r\n` line ending is a delimiter, even if it's not in the delimiter set.
+    fn push_static_text_tokens(&mut self, buf: Vec<u8>, end_pos: usize, token_type: TokenType) {
+        let is_delimiter: Vec<bool> = (0..buf.len())
+            .map(|idx| {
+                // A `\r` ending the token is followed by the byte at `end_pos` in the input
+                let next_c = buf.get(idx + 1).or(self.buf.get(end_pos));
+                self.has_delimiter(buf[idx]) || (b'\r' == buf[idx] && Some(&b'\n') == next_c)
+            })
+            .collect();
+        let mut run_start = 0;
+        while run_start < buf.len() {
+            let is_delimiter_run = is_delimiter[run_start];
+            let run_end = is_delimiter[run_start..]
+                .iter()
+                .position(|is_delimiter| *is_delimiter != is_delimiter_run)
+                .map_or(buf.len(), |run_len| run_start + run_len);
+            let run_token_type = if false == is_delimiter_run {
+                match self.unmatched_bytes_mode {
+                    UnmatchedBytesMode::StaticText => TokenType::StaticText,
+                    UnmatchedBytesMode::Unmatched | UnmatchedBytesMode::Error => {
+                        TokenType::Unmatched
+                    }
+                }
+            } else if buf.len() == run_end && TokenType::StaticTextWithEndLine == token_type {
+                TokenType::StaticTextWithEndLine
+            } else if self.emit_delimiter_tokens {
+                TokenType::Delimiter
+            } else {
+                TokenType::StaticText
+            };
+            self.token_queue.push_back(Token {
+                buf: buf[run_start..run_end].to_vec(),
+                line_num: self.line_num,
+                token_type: run_token_type,
+            });
+            run_start = run_end;
+        }
+    }
+
     fn get_and_increment_buf_cursor_pos(&mut self) -> usize {
         let curr_pos = self.buf_cursor_pos;
         self.buf_cursor_pos += 1;
@@ -396,7 +1390,7 @@ impl Lexer {
 
     fn buffer_garbage_collection(&mut self) {
         if self.last_tokenized_pos <= self.buf.len() / 2
-            || self.last_tokenized_pos <= Self::MIN_BUF_GARBAGE_COLLECTION_SIZE
+            || self.last_tokenized_pos <= self.gc_threshold
         {
             return;
         }
@@ -410,7 +1404,77 @@ impl Lexer {
         }
         self.buf.resize(dst_idx, 0);
         self.buf_cursor_pos -= self.last_tokenized_pos;
+        let compacted_len = self.last_tokenized_pos;
+        for (_, line_start_pos) in self.line_start_positions.iter_mut() {
+            *line_start_pos = line_start_pos.and_then(|pos| pos.checked_sub(compacted_len));
+        }
+        self.token_line_start_pos = self
+            .token_line_start_pos
+            .and_then(|pos| pos.checked_sub(compacted_len));
+        // A match may still be in progress after the static text before it is tokenized to
+        // enforce the max lookahead
+        self.match_start_pos = self.match_start_pos.saturating_sub(compacted_len);
+        self.match_end_pos = self.match_end_pos.saturating_sub(compacted_len);
+        self.match_first_delimiter_pos = self
+            .match_first_delimiter_pos
+            .and_then(|pos| pos.checked_sub(compacted_len));
+        if let Some((end_pos, _)) = self.last_accepted_match.as_mut() {
+            *end_pos = end_pos.saturating_sub(compacted_len);
+        }
+        self.verified_pos = 0;
         self.last_tokenized_pos = 0;
-        // No need to reset match_start/end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_token(token_type: TokenType) -> Token {
+        Token {
+            buf: b"text".to_vec(),
+            token_type,
+            line_num: 1,
+        }
+    }
+
+    #[test]
+    fn test_token_type_predicates() {
+        let timestamp = new_token(TokenType::Timestamp(1));
+        assert!(timestamp.is_timestamp());
+        assert!(false == timestamp.is_variable());
+        assert!(false == timestamp.is_static_text());
+        assert_eq!(timestamp.timestamp_id(), Some(1));
+        assert_eq!(timestamp.variable_id(), None);
+
+        let variable = new_token(TokenType::Variable(2));
+        assert!(false == variable.is_timestamp());
+        assert!(variable.is_variable());
+        assert!(false == variable.is_static_text());
+        assert_eq!(variable.timestamp_id(), None);
+        assert_eq!(variable.variable_id(), Some(2));
+
+        for token_type in [
+            TokenType::StaticText,
+            TokenType::StaticTextWithEndLine,
+            TokenType::Delimiter,
+        ] {
+            let static_text = new_token(token_type.clone());
+            assert_eq!(
+                static_text.is_delimiter(),
+                TokenType::Delimiter == token_type
+            );
+            assert!(false == static_text.is_timestamp());
+            assert!(false == static_text.is_variable());
+            assert!(static_text.is_static_text());
+            assert!(false == static_text.is_end());
+            assert_eq!(static_text.timestamp_id(), None);
+            assert_eq!(static_text.variable_id(), None);
+        }
+
+        let end = new_token(TokenType::End);
+        assert!(end.is_end());
+        assert!(false == end.is_static_text());
+        assert!(false == end.is_variable());
     }
 }
@@ -1,5 +1,9 @@
 use crate::error_handling::Result;
 
+// A byte stream consumed by the lexer. Returning `Ok(None)` ends the stream. An interactive stream
+// can instead return an `IOError` of kind `WouldBlock` when no input is available yet: the lexer
+// returns the error without losing its state, and `Lexer::get_next_token` can be retried once more
+// input is available.
 pub trait LexerStream {
     fn get_next_char(&mut self) -> Result<Option<u8>>;
 }
@@ -1,9 +1,21 @@
 mod lexer;
 mod lexer_stream;
 mod streams;
+mod token_sink;
+mod var_matcher;
 
+pub use lexer::CrlfMode;
 pub use lexer::Lexer;
 pub use lexer::Token;
 pub use lexer::TokenType;
+pub use lexer::UnmatchedBytesMode;
 pub use lexer_stream::LexerStream;
 pub use streams::BufferedFileStream;
+pub(crate) use streams::FedInputStream;
+#[cfg(feature = "flate2")]
+pub use streams::GzipFileStream;
+pub use streams::StdinStream;
+pub(crate) use streams::StringStream;
+pub use streams::TailFileStream;
+pub use token_sink::TokenSink;
+pub use var_matcher::VarMatcher;
@@ -1,47 +1,334 @@
 use super::lexer_stream::LexerStream;
-use crate::error_handling::Error::IOError;
-use crate::error_handling::Result;
-use std::io::{self, BufReader, Read};
+use crate::error_handling::{Result, Warning};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
 
 const BUF_SIZE: usize = 4096 * 8;
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
 
 pub struct BufferedFileStream {
-    buf_reader: BufReader<std::fs::File>,
+    buf_reader: BufReader<Box<dyn Read + Send>>,
     pos: usize,
     end: usize,
     buffer: [u8; BUF_SIZE],
+    skip_bom: bool,
+    is_bom_checked: bool,
+    line_ending_checker: Option<LineEndingChecker>,
+}
+
+// Tracks the line endings of a stream to report the first line whose ending differs from the
+// one of the first line
+struct LineEndingChecker {
+    warning_sender: Sender<Warning>,
+    line_num: usize,
+    last_c: Option<u8>,
+    is_crlf: Option<bool>,
+    has_warned: bool,
+}
+
+impl LineEndingChecker {
+    fn check(&mut self, c: u8) {
+        if b'\n' == c {
+            let is_crlf = Some(b'\r') == self.last_c;
+            match self.is_crlf {
+                None => self.is_crlf = Some(is_crlf),
+                Some(expected) if expected != is_crlf && false == self.has_warned => {
+                    self.has_warned = true;
+                    // The receiver may be gone; the warning is best-effort
+                    let _ = self.warning_sender.send(Warning::MixedLineEndings {
+                        line: self.line_num,
+                    });
+                }
+                Some(_) => {}
+            }
+            self.line_num += 1;
+        }
+        self.last_c = Some(c);
+    }
 }
 
 impl BufferedFileStream {
     pub fn new(path: &str) -> Result<Self> {
-        match std::fs::File::open(path) {
-            Ok(file) => Ok(Self {
-                buf_reader: BufReader::new(file),
-                pos: 0,
-                end: 0,
-                buffer: [0; BUF_SIZE],
-            }),
-            Err(e) => Err(IOError(e)),
+        let file = std::fs::File::open(path)?;
+        Ok(Self::from_reader(Box::new(file)))
+    }
+
+    // Streams the file from the given byte offset, counted after the leading BOM if any (as the
+    // BOM is skipped by default). Useful to resume parsing from `LogParser::last_committed_offset`.
+    pub fn new_from_offset(path: &str, offset: u64) -> Result<Self> {
+        let mut file = std::fs::File::open(path)?;
+        let mut head = [0u8; UTF8_BOM.len()];
+        let mut head_len = 0;
+        while head_len < head.len() {
+            match file.read(&mut head[head_len..])? {
+                0 => break,
+                byte_read => head_len += byte_read,
+            }
+        }
+        let bom_len = match head[..head_len].starts_with(&UTF8_BOM) {
+            true => UTF8_BOM.len() as u64,
+            false => 0,
+        };
+        file.seek(SeekFrom::Start(bom_len + offset))?;
+        let mut buffered_file_stream = Self::from_reader(Box::new(file));
+        buffered_file_stream.is_bom_checked = true;
+        Ok(buffered_file_stream)
+    }
+
+    fn from_reader(reader: Box<dyn Read + Send>) -> Self {
+        Self {
+            buf_reader: BufReader::new(reader),
+            pos: 0,
+            end: 0,
+            buffer: [0; BUF_SIZE],
+            skip_bom: true,
+            is_bom_checked: false,
+            line_ending_checker: None,
         }
     }
+
+    // By default, a leading UTF-8 byte-order mark (`EF BB BF`) is skipped so that it doesn't
+    // pollute the first token. Must be set before reading the first byte to take effect.
+    pub fn set_skip_bom(&mut self, skip_bom: bool) {
+        self.skip_bom = skip_bom;
+    }
+
+    // Sends a `MixedLineEndings` warning to the given channel the first time a line doesn't end
+    // like the first line (`\n` vs. `\r\n`), which usually reveals a corrupted or concatenated
+    // file. Must be set before reading the first byte to take effect.
+    pub fn set_line_ending_warnings(&mut self, warning_sender: Sender<Warning>) {
+        self.line_ending_checker = Some(LineEndingChecker {
+            warning_sender,
+            line_num: 1,
+            last_c: None,
+            is_crlf: None,
+            has_warned: false,
+        });
+    }
+
+    // Fills the buffer with enough bytes to detect a leading BOM, then skips it if needed
+    fn check_bom(&mut self) -> Result<()> {
+        self.is_bom_checked = true;
+        while self.end < UTF8_BOM.len() {
+            match self.buf_reader.read(&mut self.buffer[self.end..])? {
+                0 => break,
+                byte_read => self.end += byte_read,
+            }
+        }
+        if self.skip_bom && self.buffer[..self.end].starts_with(&UTF8_BOM) {
+            self.pos = UTF8_BOM.len();
+        }
+        Ok(())
+    }
 }
 
 impl LexerStream for BufferedFileStream {
     fn get_next_char(&mut self) -> Result<Option<u8>> {
+        if false == self.is_bom_checked {
+            self.check_bom()?;
+        }
         if self.pos == self.end {
-            match self.buf_reader.read(&mut self.buffer) {
-                Ok(byte_read) => {
-                    if 0 == byte_read {
-                        return Ok(None);
-                    }
-                    self.end = byte_read;
-                    self.pos = 0;
-                }
-                Err(e) => return Err(IOError(e)),
+            let byte_read = self.buf_reader.read(&mut self.buffer)?;
+            if 0 == byte_read {
+                return Ok(None);
             }
+            self.end = byte_read;
+            self.pos = 0;
         }
         let c = self.buffer[self.pos];
         self.pos += 1;
+        if let Some(line_ending_checker) = self.line_ending_checker.as_mut() {
+            line_ending_checker.check(c);
+        }
+        Ok(Some(c))
+    }
+}
+
+// Streams the decompressed content of a gzip file, exactly like `BufferedFileStream` streams the
+// content of the uncompressed file
+#[cfg(feature = "flate2")]
+pub struct GzipFileStream {
+    buffered_stream: BufferedFileStream,
+}
+
+#[cfg(feature = "flate2")]
+impl GzipFileStream {
+    pub fn new(path: &str) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Ok(Self {
+            buffered_stream: BufferedFileStream::from_reader(Box::new(
+                flate2::read::GzDecoder::new(file),
+            )),
+        })
+    }
+
+    // See `BufferedFileStream::set_skip_bom`
+    pub fn set_skip_bom(&mut self, skip_bom: bool) {
+        self.buffered_stream.set_skip_bom(skip_bom);
+    }
+
+    // See `BufferedFileStream::set_line_ending_warnings`
+    pub fn set_line_ending_warnings(&mut self, warning_sender: Sender<Warning>) {
+        self.buffered_stream
+            .set_line_ending_warnings(warning_sender);
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl LexerStream for GzipFileStream {
+    fn get_next_char(&mut self) -> Result<Option<u8>> {
+        self.buffered_stream.get_next_char()
+    }
+}
+
+// Streams the standard input (e.g. `cat app.log | my_tool`) lazily, exactly like
+// `BufferedFileStream` streams a file. The standard input is only locked while a chunk is read,
+// so other threads may still use it in between.
+pub struct StdinStream {
+    buffered_stream: BufferedFileStream,
+}
+
+impl StdinStream {
+    pub fn new() -> Self {
+        Self {
+            buffered_stream: BufferedFileStream::from_reader(Box::new(io::stdin())),
+        }
+    }
+
+    // See `BufferedFileStream::set_skip_bom`
+    pub fn set_skip_bom(&mut self, skip_bom: bool) {
+        self.buffered_stream.set_skip_bom(skip_bom);
+    }
+
+    // See `BufferedFileStream::set_line_ending_warnings`
+    pub fn set_line_ending_warnings(&mut self, warning_sender: Sender<Warning>) {
+        self.buffered_stream
+            .set_line_ending_warnings(warning_sender);
+    }
+}
+
+impl Default for StdinStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LexerStream for StdinStream {
+    fn get_next_char(&mut self) -> Result<Option<u8>> {
+        self.buffered_stream.get_next_char()
+    }
+}
+
+// Streams a file that is still being written to, like `tail -f`: upon reaching the end of the
+// file, the stream waits for appended bytes instead of ending, checking the file again every poll
+// interval (see `set_poll_interval`). Without a poll interval, the stream pauses with an `IOError`
+// of kind `WouldBlock` instead (see `LexerStream`), and the caller polls again later. Either way,
+// the stream only ends once the stop signal is raised and no more bytes are available.
+pub struct TailFileStream {
+    buffered_stream: BufferedFileStream,
+    stop_signal: Arc<AtomicBool>,
+    poll_interval: Option<Duration>,
+}
+
+impl TailFileStream {
+    const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    pub fn new(path: &str) -> Result<Self> {
+        Ok(Self {
+            buffered_stream: BufferedFileStream::new(path)?,
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            poll_interval: Some(Self::DEFAULT_POLL_INTERVAL),
+        })
+    }
+
+    // Sets how long to sleep between two reads at the end of the file (100ms by default). `None`
+    // pauses the stream with `WouldBlock` instead, leaving the polling to the caller.
+    pub fn set_poll_interval(&mut self, poll_interval: Option<Duration>) {
+        self.poll_interval = poll_interval;
+    }
+
+    // Storing `true` into the returned flag (e.g. from another thread) ends the stream at the next
+    // end of the file. Must be retrieved before handing the stream over to a lexer.
+    pub fn get_stop_signal(&self) -> Arc<AtomicBool> {
+        self.stop_signal.clone()
+    }
+
+    // See `BufferedFileStream::set_skip_bom`
+    pub fn set_skip_bom(&mut self, skip_bom: bool) {
+        self.buffered_stream.set_skip_bom(skip_bom);
+    }
+
+    // See `BufferedFileStream::set_line_ending_warnings`
+    pub fn set_line_ending_warnings(&mut self, warning_sender: Sender<Warning>) {
+        self.buffered_stream
+            .set_line_ending_warnings(warning_sender);
+    }
+}
+
+impl LexerStream for TailFileStream {
+    fn get_next_char(&mut self) -> Result<Option<u8>> {
+        loop {
+            // A read past the end of a file picks up the bytes appended since the last read
+            if let Some(c) = self.buffered_stream.get_next_char()? {
+                return Ok(Some(c));
+            }
+            if self.stop_signal.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+            match self.poll_interval {
+                Some(poll_interval) => std::thread::sleep(poll_interval),
+                None => return Err(io::Error::from(io::ErrorKind::WouldBlock).into()),
+            }
+        }
+    }
+}
+
+// The input stream of a lexer fed with `Lexer::feed`, which appends the fed bytes to its buffer
+// directly. The stream pauses until `Lexer::finish` replaces it with an ended one.
+pub(crate) struct FedInputStream {
+    is_ended: bool,
+}
+
+impl FedInputStream {
+    pub(crate) fn new(is_ended: bool) -> Self {
+        Self { is_ended }
+    }
+}
+
+impl LexerStream for FedInputStream {
+    fn get_next_char(&mut self) -> Result<Option<u8>> {
+        match self.is_ended {
+            true => Ok(None),
+            false => Err(io::Error::from(io::ErrorKind::WouldBlock).into()),
+        }
+    }
+}
+
+pub(crate) struct StringStream {
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl StringStream {
+    pub(crate) fn new(input: &str) -> Self {
+        Self::from_bytes(input.as_bytes().to_vec())
+    }
+
+    pub(crate) fn from_bytes(buf: Vec<u8>) -> Self {
+        Self { buf, pos: 0 }
+    }
+}
+
+impl LexerStream for StringStream {
+    fn get_next_char(&mut self) -> Result<Option<u8>> {
+        if self.pos == self.buf.len() {
+            return Ok(None);
+        }
+        let c = self.buf[self.pos];
+        self.pos += 1;
         Ok(Some(c))
     }
 }
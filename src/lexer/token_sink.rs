@@ -0,0 +1,8 @@
+use super::lexer::Token;
+use crate::error_handling::Result;
+
+// A consumer of the tokens emitted by `Lexer::run_to_sink`, e.g. to count, write, or forward
+// them. Returning an error stops the lexer, which returns the error as is.
+pub trait TokenSink {
+    fn accept(&mut self, token: &Token) -> Result<()>;
+}
@@ -0,0 +1,22 @@
+use crate::dfa::dfa::DFA;
+
+// A cheap handle on the variable DFA of a `Lexer` (see `Lexer::var_matcher`), to run the same
+// longest-match scan as the lexer over arbitrary buffers. Unlike the lexer, it doesn't check
+// anchors nor delimiters around the match.
+#[derive(Clone, Copy)]
+pub struct VarMatcher<'a> {
+    var_dfa: &'a DFA,
+}
+
+impl<'a> VarMatcher<'a> {
+    pub(crate) fn new(var_dfa: &'a DFA) -> Self {
+        Self { var_dfa }
+    }
+
+    // Returns the ID of the highest-priority variable accepted by the longest match starting at
+    // `pos` (see `SchemaConfig::get_var_schemas`) and the length of the match, or `None` if no
+    // variable matches at `pos`.
+    pub fn longest_match_at(&self, buf: &[u8], pos: usize) -> Option<(usize, usize)> {
+        self.var_dfa.longest_match(buf.get(pos..)?)
+    }
+}
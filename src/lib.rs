@@ -1,5 +1,17 @@
+// Without the `std` feature, only the regex engine is built, on top of `core` and `alloc`. The
+// lexer, the log parser and the schema parser, which rely on I/O, require `std`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+// Library code never prints: diagnostics are returned to the caller instead (e.g. as `Warning`s or
+// lint results), or reported through the `log` facade with the `log` feature, so that embedders
+// control the output. Tests may print for debugging.
+#![cfg_attr(not(test), deny(clippy::print_stdout, clippy::print_stderr))]
+
+extern crate alloc;
+
 pub mod error_handling;
+#[cfg(feature = "std")]
 pub mod lexer;
+#[cfg(feature = "std")]
 pub mod log_parser;
 pub mod parser;
 
@@ -13,18 +25,60 @@ mod dfa;
 #[cfg(not(feature = "regex-engine"))]
 mod nfa;
 
+use crate::error_handling::Result;
+use crate::nfa::nfa::NFA;
+use crate::parser::regex_parser::parser::RegexParser;
+
 const VERSION: &str = "0.0.1";
 
 pub fn version() -> &'static str {
     VERSION
 }
 
+// Checks whether the given regex is supported by the regex engine, without a schema. Returns the
+// error a schema using the regex would fail with, e.g. `UnsupportedRegexConstruct` for a regex
+// feature that the engine doesn't implement.
+pub fn validate_regex(pattern: &str) -> Result<()> {
+    let ast = RegexParser::new().parse_into_ast(pattern)?;
+    RegexParser::check_supported(&ast)?;
+    let mut nfa = NFA::new();
+    nfa.add_ast_to_nfa(&ast, nfa.get_start(), nfa.get_accept())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error_handling::Error::{RegexParsingError, UnsupportedRegexConstruct};
 
     #[test]
     fn test_version() {
         assert_eq!(version(), VERSION);
     }
+
+    #[test]
+    fn test_validate_regex() -> Result<()> {
+        validate_regex(r"^\d{4}\-(?:\d{2}|[A-Z]+)(?<ms>\.\d{3})?$")?;
+
+        for (pattern, expected_construct) in [
+            (r"\d+?", "non-greedy repetition"),
+            (r"(?i)abc", "inline flags"),
+            (r"\bword", "word boundary"),
+            (r"\D+", "negated Perl class"),
+            (r"\pL", "Unicode class"),
+            ("caf\u{e9}", "non-ASCII character"),
+        ] {
+            match validate_regex(pattern) {
+                Err(UnsupportedRegexConstruct(info)) => {
+                    assert_eq!(info.construct, expected_construct, "{}", pattern)
+                }
+                _ => panic!("Unsupported regex not detected: {}", pattern),
+            }
+        }
+        assert!(matches!(
+            validate_regex(r"a(?=b)"),
+            Err(RegexParsingError(_))
+        ));
+        assert!(matches!(validate_regex(r"(a"), Err(RegexParsingError(_))));
+        Ok(())
+    }
 }
@@ -0,0 +1,24 @@
+// The fields most consumers read first from a log event: its timestamp and its severity (e.g.
+// `INFO`), as matched by the schema. See `LogEvent::header`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventHeader {
+    timestamp: Option<String>,
+    severity: Option<String>,
+}
+
+impl EventHeader {
+    pub(crate) fn new(timestamp: Option<String>, severity: Option<String>) -> Self {
+        Self {
+            timestamp,
+            severity,
+        }
+    }
+
+    pub fn get_timestamp(&self) -> Option<&str> {
+        self.timestamp.as_deref()
+    }
+
+    pub fn get_severity(&self) -> Option<&str> {
+        self.severity.as_deref()
+    }
+}
@@ -1,33 +1,232 @@
-use crate::error_handling::Error::LogParserInternalErr;
-use crate::error_handling::Result;
+use crate::error_handling::Error::{
+    LexerInputStreamNotSet, LogParserInputNotSet, LogParserInternalErr, UnknownVariableName,
+    UnmatchedContent,
+};
+use crate::error_handling::{Result, UnmatchedContentInfo};
 use crate::lexer::BufferedFileStream;
 use crate::lexer::LexerStream;
+use crate::lexer::StringStream;
 use crate::lexer::{Lexer, Token, TokenType};
+use crate::log_parser::logfmt::parse_logfmt_pairs;
+use crate::log_parser::EventHeader;
+use crate::log_parser::NumericSummary;
+use crate::log_parser::ParseProgress;
+use crate::log_parser::PositionalSchema;
+use crate::log_parser::VariableRecord;
 use crate::parser::SchemaConfig;
+use indexmap::IndexMap;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
+
+// How `LogParser` decides that a log event repeats the previous one, see
+// `LogParser::set_dedup_mode`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DedupMode {
+    // The log messages (i.e. excluding the timestamp) have the same bytes
+    Exact,
+    // The log messages have the same static text and the same variable types in the same order,
+    // regardless of the variable values (e.g. `Took 42 ms` and `Took 7 ms`)
+    Template,
+}
 
 pub struct LogParser {
     lexer: Lexer,
-    schema_config: Rc<SchemaConfig>,
+    schema_config: Arc<SchemaConfig>,
     tokens: Option<Vec<Token>>,
+    num_buffered_lines: usize,
+    is_at_line_start: bool,
+    // Whether a timestamp token has been seen on the current line
+    is_line_timestamped: bool,
+    embedded_timestamps: bool,
+    logfmt_mode: bool,
+    positional_schema: Option<PositionalSchema>,
+    max_tokens_per_event: Option<usize>,
+    multiline_mode: bool,
+    strict_mode: bool,
+    raw_line_mode: bool,
+    stack_trace_mode: bool,
+    max_lines_per_event: Option<usize>,
+    num_static_only_events: usize,
+    parsed_offset: u64,
+    committed_offset: u64,
+    progress_interval: usize,
+    dedup_mode: Option<DedupMode>,
+    pending_log_event: Option<LogEvent>,
 }
 
 pub struct LogEvent {
     tokens: Vec<Token>,
     line_range: (usize, usize),
-    has_timestamp: bool,
-    schema_config: Rc<SchemaConfig>,
+    // The text of every line of `line_range`, only built by the first `get_line_text`
+    line_texts: OnceLock<Vec<String>>,
+    // `Some(0)` for a log event starting with its timestamp, or the index of an embedded timestamp
+    // (see `LogParser::set_embedded_timestamps`)
+    timestamp_idx: Option<usize>,
+    schema_config: Arc<SchemaConfig>,
+    logfmt_pairs: IndexMap<String, String>,
+    positional_fields: IndexMap<String, String>,
+    repeat_count: usize,
+    start_offset: u64,
 }
 
 impl LogParser {
+    const DEFAULT_PROGRESS_INTERVAL: usize = 1000;
+
     pub fn new(schema_config: Rc<SchemaConfig>) -> Result<Self> {
-        let lexer = Lexer::new(schema_config.clone())?;
-        Ok((Self {
+        Ok(Self::from_lexer(Lexer::new(schema_config)?))
+    }
+
+    pub fn new_arc(schema_config: Arc<SchemaConfig>) -> Result<Self> {
+        Ok(Self::from_lexer(Lexer::new_arc(schema_config)?))
+    }
+
+    // Builds a log parser around the given lexer, keeping its settings, except the ones dropping
+    // input bytes from the tokens, which would break the log event boundaries and offsets: the
+    // static text is always emitted, the `\r` of a CRLF is always preserved, and
+    // `UnmatchedBytesMode::Error` falls back to `UnmatchedBytesMode::Unmatched`. Since
+    // `Lexer::try_clone` shares the compiled DFAs, this is much cheaper than `new` to get several
+    // parsers for the same schema.
+    pub fn from_lexer(mut lexer: Lexer) -> Self {
+        lexer.keep_all_input_bytes();
+        let schema_config = lexer.get_schema_config().clone();
+        Self {
             lexer,
             schema_config,
             tokens: None,
-        }))
+            num_buffered_lines: 0,
+            is_at_line_start: true,
+            is_line_timestamped: false,
+            embedded_timestamps: false,
+            logfmt_mode: false,
+            positional_schema: None,
+            max_tokens_per_event: None,
+            multiline_mode: true,
+            strict_mode: false,
+            raw_line_mode: false,
+            stack_trace_mode: false,
+            max_lines_per_event: None,
+            num_static_only_events: 0,
+            parsed_offset: 0,
+            committed_offset: 0,
+            progress_interval: Self::DEFAULT_PROGRESS_INTERVAL,
+            dedup_mode: None,
+            pending_log_event: None,
+        }
+    }
+
+    // Caps the number of tokens buffered for a single log event. Once the cap is reached, the
+    // buffered tokens are force-emitted as a log event even if no event boundary has been found,
+    // so that boundary-free inputs can't grow a log event without limit. `None` means no cap.
+    pub fn set_max_tokens_per_event(&mut self, max_tokens_per_event: Option<usize>) {
+        self.max_tokens_per_event = max_tokens_per_event;
+    }
+
+    // Caps the number of lines of a single log event. Once the cap is reached, the log event is
+    // emitted at the end of its last line, and the following lines start a new log event without
+    // a timestamp (or, in stack trace mode, standalone single-line log events). `None` means no
+    // cap.
+    pub fn set_max_lines_per_event(&mut self, max_lines_per_event: Option<usize>) {
+        self.max_lines_per_event = max_lines_per_event;
+    }
+
+    // When enabled, variables are also extracted from within other text, e.g. the IP of
+    // `host=10.0.0.1:8080`, instead of only between delimiters. See
+    // `Lexer::set_substring_matching`.
+    pub fn set_substring_matching(&mut self, enabled: bool) {
+        self.lexer.set_substring_matching(enabled);
+    }
+
+    // Caps the number of bytes of an input line, so that a malformed input (e.g. without any line
+    // break) fails with `LineTooLong` instead of being buffered without limit. See
+    // `Lexer::set_max_line_len`.
+    pub fn set_max_line_len(&mut self, max_line_len: Option<usize>) {
+        self.lexer.set_max_line_len(max_line_len);
+    }
+
+    // When enabled, the message of every emitted log event is also split into logfmt key/value
+    // pairs (`key1=val1 key2="val 2"`), independent of the variable schemas.
+    pub fn set_logfmt_mode(&mut self, enabled: bool) {
+        self.logfmt_mode = enabled;
+    }
+
+    // When set, the tokens of every emitted log event are also named by their position as per the
+    // given schema (see `PositionalSchema`), independent of the variable schemas. `None` (the
+    // default) disables the positional naming.
+    pub fn set_positional_schema(&mut self, positional_schema: Option<PositionalSchema>) {
+        self.positional_schema = positional_schema;
+    }
+
+    // In multiline mode (the default), a log event starts at a timestamp and spans all the
+    // following lines until the next timestamp. When disabled, every line is emitted as its own
+    // log event, which suits logs without timestamps.
+    pub fn set_multiline_mode(&mut self, enabled: bool) {
+        self.multiline_mode = enabled;
+    }
+
+    // Stack trace mode refines the multiline mode for logs like Java or Python ones, where only
+    // the lines following a timestamped line (e.g. a stack trace) are continuation lines. Such lines
+    // are always attached to the preceding timestamped log event, while any line without a
+    // timestamp that doesn't follow one (e.g. at the start of the input) is emitted as its own
+    // log event. Has no effect if the multiline mode is disabled.
+    pub fn set_stack_trace_mode(&mut self, enabled: bool) {
+        self.stack_trace_mode = enabled;
+    }
+
+    // In strict mode, a log event containing any static text other than delimiters and whitespace,
+    // or any `Unmatched` token, fails with `UnmatchedContent`, which helps to validate that a
+    // schema models every field of a log format.
+    pub fn set_strict_mode(&mut self, enabled: bool) {
+        self.strict_mode = enabled;
+    }
+
+    // In raw line mode, every line is emitted as its own log event made of a single static text
+    // token holding the raw line, without any timestamp or variable extraction. This is a fast
+    // path for logs where each line is already a complete record, such as JSON lines. Strict mode
+    // doesn't apply to raw lines.
+    pub fn set_raw_line_mode(&mut self, enabled: bool) {
+        self.raw_line_mode = enabled;
+        self.lexer.set_raw_line_mode(enabled);
+    }
+
+    // When enabled, a timestamp-looking substring after the start of a line (e.g. a response time
+    // following the request time) is emitted as a `Timestamp` token within the log message, but
+    // doesn't start a new log event: the timestamp starting the line remains the event timestamp.
+    // See `Lexer::set_mid_line_timestamps`.
+    pub fn set_mid_line_timestamps(&mut self, enabled: bool) {
+        self.lexer.set_mid_line_timestamps(enabled);
+    }
+
+    // When enabled, a line with a timestamp after the start of the line (e.g. `INFO pid=123
+    // 2024-01-01 00:00:00 msg`) starts a new log event at its first timestamp, like a line starting
+    // with a timestamp. The leading text is kept in the log message. See
+    // `Lexer::set_embedded_timestamps`.
+    pub fn set_embedded_timestamps(&mut self, enabled: bool) {
+        self.embedded_timestamps = enabled;
+        self.lexer.set_embedded_timestamps(enabled);
+    }
+
+    // When set, consecutive log events repeating each other as per the given mode are collapsed
+    // into the first one, whose `LogEvent::repeat_count` holds the size of the run, like syslog's
+    // "last message repeated N times". A log event is then only emitted once the next distinct log
+    // event (or the end of the input) is found. `None` (the default) disables the deduplication.
+    pub fn set_dedup_mode(&mut self, dedup_mode: Option<DedupMode>) {
+        self.dedup_mode = dedup_mode;
+    }
+
+    // The number of log events emitted between two progress reports of `parse_all_with_progress`.
+    // Defaults to 1000. Zero is treated as 1.
+    pub fn set_progress_interval(&mut self, num_events: usize) {
+        self.progress_interval = num_events.max(1);
+    }
+
+    // The number of log events emitted so far without any variable (see
+    // `LogEvent::has_only_static_text`). A high ratio of such log events hints that the schema
+    // doesn't model the log format.
+    pub fn get_num_static_only_events(&self) -> usize {
+        self.num_static_only_events
     }
 
     pub fn set_input_file(&mut self, path: &str) -> Result<()> {
@@ -35,33 +234,274 @@ impl LogParser {
         self.set_input_stream(buffered_file_stream)
     }
 
+    // Resumes parsing a file from the given byte offset, typically the `last_committed_offset` of
+    // a previous run over the same file. The line numbers restart from 1.
+    pub fn set_input_file_from_offset(&mut self, path: &str, offset: u64) -> Result<()> {
+        let buffered_file_stream = Box::new(BufferedFileStream::new_from_offset(path, offset)?);
+        self.set_input_stream(buffered_file_stream)?;
+        self.parsed_offset = offset;
+        self.committed_offset = offset;
+        Ok(())
+    }
+
     pub fn set_input_stream(&mut self, input_stream: Box<dyn LexerStream>) -> Result<()> {
         self.tokens = None;
+        self.num_buffered_lines = 0;
+        self.is_at_line_start = true;
+        self.parsed_offset = 0;
+        self.committed_offset = 0;
+        self.pending_log_event = None;
         self.lexer.set_input_stream(input_stream);
         Ok(())
     }
 
+    // The byte offset in the input right after the last emitted log event (excluding a leading
+    // BOM skipped by `BufferedFileStream`). Once a log event is processed, persisting this offset
+    // allows to resume with `set_input_file_from_offset` without processing the log event again.
+    pub fn last_committed_offset(&self) -> u64 {
+        self.committed_offset
+    }
+
+    // Fails with `LogParserInputNotSet` if called before `set_input_file` or `set_input_stream`
     pub fn parse_next_log_event(&mut self) -> Result<Option<LogEvent>> {
+        let Some(dedup_mode) = self.dedup_mode else {
+            let log_event = self.parse_next_undeduped_log_event()?;
+            self.committed_offset = self.parsed_offset;
+            return Ok(log_event);
+        };
+        // The pending log event, if any, is always the last parsed one
+        let mut log_event = match self.pending_log_event.take() {
+            Some(log_event) => log_event,
+            None => match self.parse_next_undeduped_log_event()? {
+                Some(log_event) => log_event,
+                None => return Ok(None),
+            },
+        };
         loop {
-            match self.lexer.get_next_token()? {
+            let log_event_end_offset = self.parsed_offset;
+            let next_log_event = match self.parse_next_undeduped_log_event() {
+                Ok(next_log_event) => next_log_event,
+                Err(e) => {
+                    // Kept for the next call if the input stream only paused (`WouldBlock`)
+                    self.pending_log_event = Some(log_event);
+                    return Err(e);
+                }
+            };
+            match next_log_event {
+                Some(next_log_event) if log_event.is_repeated_by(&next_log_event, dedup_mode) => {
+                    log_event.repeat_count += 1;
+                }
+                next_log_event => {
+                    self.pending_log_event = next_log_event;
+                    self.committed_offset = log_event_end_offset;
+                    return Ok(Some(log_event));
+                }
+            }
+        }
+    }
+
+    fn parse_next_undeduped_log_event(&mut self) -> Result<Option<LogEvent>> {
+        loop {
+            let next_token = self.lexer.get_next_token().map_err(|e| match e {
+                LexerInputStreamNotSet => LogParserInputNotSet,
+                e => e,
+            })?;
+            let is_at_line_start = self.is_at_line_start;
+            let is_line_timestamped = self.is_line_timestamped;
+            if let Some(token) = next_token.as_ref() {
+                self.is_at_line_start = TokenType::StaticTextWithEndLine == token.get_token_type();
+                self.is_line_timestamped = false == self.is_at_line_start
+                    && (self.is_line_timestamped || token.is_timestamp());
+            }
+            match next_token {
                 Some(token) => match token.get_token_type() {
-                    TokenType::Timestamp(_) => {
-                        if self.tokens.is_none() {
-                            self.buffer_token(token);
-                            continue;
+                    // Only a timestamp starting a line (or the first one embedded in it, if
+                    // enabled) starts a new log event
+                    TokenType::Timestamp(_)
+                        if is_at_line_start
+                            || (self.embedded_timestamps && false == is_line_timestamped) =>
+                    {
+                        let line_tokens = self.take_buffered_line_tokens();
+                        let log_event = match self.tokens.is_some() {
+                            true => self.emit_buffered_tokens_as_log_event()?,
+                            false => None,
+                        };
+                        for line_token in line_tokens {
+                            self.buffer_token(line_token);
                         }
-                        let log_event = self.emit_buffered_tokens_as_log_event()?;
                         self.buffer_token(token);
-                        return Ok(log_event);
+                        if log_event.is_some() {
+                            return Ok(log_event);
+                        }
+                    }
+                    TokenType::StaticTextWithEndLine
+                        if false == self.multiline_mode || self.raw_line_mode =>
+                    {
+                        self.buffer_token(token);
+                        return self.emit_buffered_tokens_as_log_event();
+                    }
+                    TokenType::StaticTextWithEndLine => {
+                        self.buffer_token(token);
+                        self.num_buffered_lines += 1;
+                        if self.is_log_event_ended_at_line_end() {
+                            return self.emit_buffered_tokens_as_log_event();
+                        }
                     }
+                    // Only emitted if enabled on the lexer given to `from_lexer`, and followed by the
+                    // end of the input, which flushes the last log event
+                    TokenType::End => {}
                     _ => self.buffer_token(token),
                 },
                 None => break,
             }
+            if self.has_reached_max_tokens_per_event() {
+                return self.emit_buffered_tokens_as_log_event();
+            }
         }
         self.emit_buffered_tokens_as_log_event()
     }
 
+    // Parses the remaining log events, calling `on_progress` every `progress_interval` log events
+    // (see `set_progress_interval`) and once more after the last log event, so that the last
+    // reported progress always matches the totals.
+    pub fn parse_all_with_progress(
+        &mut self,
+        mut on_progress: impl FnMut(ParseProgress),
+    ) -> Result<Vec<LogEvent>> {
+        let mut log_events = Vec::new();
+        let start_offset = self.committed_offset;
+        let mut bytes_processed = 0;
+        let mut current_line = 0;
+        while let Some(log_event) = self.parse_next_log_event()? {
+            bytes_processed = self.committed_offset - start_offset;
+            current_line = log_event.get_line_range().1;
+            log_events.push(log_event);
+            if 0 == log_events.len() % self.progress_interval {
+                on_progress(ParseProgress::new(
+                    bytes_processed,
+                    log_events.len(),
+                    current_line,
+                ));
+            }
+        }
+        if 0 != log_events.len() % self.progress_interval || log_events.is_empty() {
+            on_progress(ParseProgress::new(
+                bytes_processed,
+                log_events.len(),
+                current_line,
+            ));
+        }
+        Ok(log_events)
+    }
+
+    // Parses the remaining log events, and summarizes the values matched by the given variable as
+    // numbers. Fails with `UnknownVariableName` if the schema has no such variable.
+    pub fn numeric_summary(&mut self, var_name: &str) -> Result<NumericSummary> {
+        let var_id = self
+            .schema_config
+            .get_var_id_by_name(var_name)
+            .ok_or_else(|| UnknownVariableName(var_name.to_string()))?;
+        let mut summary = NumericSummary::default();
+        while let Some(log_event) = self.parse_next_log_event()? {
+            for token in log_event.get_variables_by_id(var_id) {
+                summary.add_value(token.get_buf_as_string().as_str());
+            }
+        }
+        Ok(summary)
+    }
+
+    // Parses the remaining log events, and maps the byte length of the values matched by the
+    // given variable to their number of occurrences. A long tail of lengths hints at a variable
+    // pattern that is too greedy. Fails with `UnknownVariableName` if the schema has no such
+    // variable.
+    pub fn variable_length_histogram(&mut self, var_name: &str) -> Result<BTreeMap<usize, usize>> {
+        let var_id = self
+            .schema_config
+            .get_var_id_by_name(var_name)
+            .ok_or_else(|| UnknownVariableName(var_name.to_string()))?;
+        let mut histogram = BTreeMap::new();
+        while let Some(log_event) = self.parse_next_log_event()? {
+            for token in log_event.get_variables_by_id(var_id) {
+                *histogram.entry(token.get_buf().len()).or_insert(0) += 1;
+            }
+        }
+        Ok(histogram)
+    }
+
+    // Parses the remaining log events, and flattens their variables into one record per
+    // occurrence, in input order. Repeats collapsed by the dedup mode don't produce any records.
+    pub fn extract_variables(&mut self) -> Result<Vec<VariableRecord>> {
+        let mut records = Vec::new();
+        while let Some(log_event) = self.parse_next_log_event()? {
+            let mut offset = log_event.get_start_offset();
+            for token in &log_event.tokens {
+                if let TokenType::Variable(var_id) = token.get_token_type() {
+                    let name = self
+                        .schema_config
+                        .get_var_name(var_id)
+                        .ok_or(LogParserInternalErr("Variable ID out of range"))?;
+                    records.push(VariableRecord::new(
+                        name.to_string(),
+                        token.get_buf_as_string(),
+                        token.get_line_num(),
+                        offset,
+                    ));
+                }
+                offset += token.get_buf().len() as u64;
+            }
+        }
+        Ok(records)
+    }
+
+    // Whether the buffered log event must end with its last buffered line, even if the next line
+    // doesn't start with a timestamp
+    fn is_log_event_ended_at_line_end(&self) -> bool {
+        if let Some(max_lines_per_event) = self.max_lines_per_event {
+            if self.num_buffered_lines >= max_lines_per_event {
+                return true;
+            }
+        }
+        let has_timestamp = match self.tokens.as_ref() {
+            Some(tokens) => self.find_timestamp_idx(tokens).is_some(),
+            None => false,
+        };
+        self.stack_trace_mode && false == has_timestamp
+    }
+
+    // Returns the index of the timestamp of the log event made of the given tokens: either its
+    // first token, or with embedded timestamps, the first timestamp of its first line
+    fn find_timestamp_idx(&self, tokens: &[Token]) -> Option<usize> {
+        let first_line_num = tokens.first()?.get_line_num();
+        let idx = tokens
+            .iter()
+            .take_while(|token| token.get_line_num() == first_line_num)
+            .position(|token| token.is_timestamp())?;
+        (0 == idx || self.embedded_timestamps).then_some(idx)
+    }
+
+    // Removes the buffered tokens of the current line, i.e. the ones following the last line end
+    fn take_buffered_line_tokens(&mut self) -> Vec<Token> {
+        let Some(tokens) = self.tokens.as_mut() else {
+            return Vec::new();
+        };
+        let line_start_idx = tokens
+            .iter()
+            .rposition(|token| TokenType::StaticTextWithEndLine == token.get_token_type())
+            .map_or(0, |idx| idx + 1);
+        let line_tokens = tokens.split_off(line_start_idx);
+        if tokens.is_empty() {
+            self.tokens = None;
+        }
+        line_tokens
+    }
+
+    fn has_reached_max_tokens_per_event(&self) -> bool {
+        match (self.max_tokens_per_event, &self.tokens) {
+            (Some(max_tokens_per_event), Some(tokens)) => tokens.len() >= max_tokens_per_event,
+            _ => false,
+        }
+    }
+
     fn buffer_token(&mut self, token: Token) {
         if self.tokens.is_none() {
             self.tokens = Some(Vec::new());
@@ -73,51 +513,182 @@ impl LogParser {
         match &self.tokens {
             Some(_) => {
                 let tokens = self.tokens.take().unwrap();
-                LogEvent::new(self.schema_config.clone(), tokens)
+                self.num_buffered_lines = 0;
+                if self.strict_mode && false == self.raw_line_mode {
+                    self.check_unmatched_content(&tokens)?;
+                }
+                let start_offset = self.parsed_offset;
+                self.parsed_offset += tokens
+                    .iter()
+                    .map(|token| token.get_buf().len() as u64)
+                    .sum::<u64>();
+                let timestamp_idx = self.find_timestamp_idx(&tokens);
+                let log_event = LogEvent::new(
+                    self.schema_config.clone(),
+                    tokens,
+                    start_offset,
+                    timestamp_idx,
+                    self.logfmt_mode,
+                    self.positional_schema.as_ref(),
+                )?;
+                if log_event
+                    .as_ref()
+                    .is_some_and(|log_event| log_event.has_only_static_text())
+                {
+                    self.num_static_only_events += 1;
+                }
+                Ok(log_event)
             }
             None => Ok(None),
         }
     }
+
+    fn check_unmatched_content(&self, tokens: &[Token]) -> Result<()> {
+        for token in tokens {
+            // An `Unmatched` token only holds bytes that aren't delimiters
+            if false == (token.is_static_text() || token.is_unmatched()) || token.is_delimiter() {
+                continue;
+            }
+            let is_unmatched = token.get_buf().iter().any(|c| {
+                let is_delimiter = (0..self.schema_config.get_num_delimiter_sets())
+                    .any(|set_id| self.schema_config.has_delimiter_in_set(set_id, *c));
+                false == is_delimiter && false == c.is_ascii_whitespace()
+            });
+            if is_unmatched {
+                return Err(UnmatchedContent(Box::new(UnmatchedContentInfo {
+                    line: token.get_line_num(),
+                    text: token.get_buf_as_string(),
+                })));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl LogEvent {
-    fn new(schema_config: Rc<SchemaConfig>, tokens: Vec<Token>) -> Result<Option<Self>> {
+    const DEFAULT_SEVERITY_VAR_NAME: &'static str = "loglevel";
+
+    fn new(
+        schema_config: Arc<SchemaConfig>,
+        tokens: Vec<Token>,
+        start_offset: u64,
+        timestamp_idx: Option<usize>,
+        logfmt_mode: bool,
+        positional_schema: Option<&PositionalSchema>,
+    ) -> Result<Option<Self>> {
         if tokens.is_empty() {
             return Err(LogParserInternalErr("The given token vector is empty"));
         }
-        let has_timestamp = match tokens.first().unwrap().get_token_type() {
-            TokenType::Timestamp(_) => true,
-            _ => false,
-        };
         let line_range = (
             tokens.first().unwrap().get_line_num(),
             tokens.last().unwrap().get_line_num(),
         );
-        Ok(Some(
-            (Self {
-                tokens,
-                line_range,
-                has_timestamp,
-                schema_config,
-            }),
-        ))
+        let mut log_event = Self {
+            tokens,
+            line_range,
+            line_texts: OnceLock::new(),
+            timestamp_idx,
+            schema_config,
+            logfmt_pairs: IndexMap::new(),
+            positional_fields: IndexMap::new(),
+            repeat_count: 1,
+            start_offset,
+        };
+        if logfmt_mode {
+            let mut message = String::new();
+            for token in log_event.get_log_message_tokens() {
+                message += &token.get_buf_as_string();
+            }
+            log_event.logfmt_pairs = parse_logfmt_pairs(message.as_str());
+        }
+        if let Some(positional_schema) = positional_schema {
+            log_event.positional_fields = positional_schema.label_fields(&log_event.tokens);
+        }
+        Ok(Some(log_event))
     }
 
     pub fn get_timestamp_token(&self) -> Option<&Token> {
-        match self.has_timestamp {
-            true => Some(&self.tokens[0]),
-            false => None,
+        self.timestamp_idx.map(|idx| &self.tokens[idx])
+    }
+
+    // The exact bytes matched by the timestamp schema, e.g. to re-parse the timestamp
+    pub fn timestamp_bytes(&self) -> Option<&[u8]> {
+        self.get_timestamp_token().map(|token| token.get_buf())
+    }
+
+    // Same as `timestamp_bytes`, as text. `None` if the timestamp isn't valid UTF-8.
+    pub fn timestamp_str(&self) -> Option<&str> {
+        self.timestamp_bytes()
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    // Parses the timestamp with the format of the matched timestamp schema (see
+    // `TimestampSchema::set_format`). Without such a format, the timestamp is parsed as
+    // `YYYY-MM-DD` followed by `T` or a space, `hh:mm:ss`, and optional fractional seconds after
+    // `.` or `,`. `None` if there's no timestamp, or if it doesn't parse.
+    #[cfg(feature = "chrono")]
+    pub fn timestamp_datetime(&self) -> Option<chrono::NaiveDateTime> {
+        const INFERRED_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+        let timestamp = self.timestamp_str()?;
+        let ts_id = self.get_timestamp_token()?.timestamp_id()?;
+        match self.schema_config.get_ts_schemas()[ts_id].get_format() {
+            Some(format) => chrono::NaiveDateTime::parse_from_str(timestamp, format).ok(),
+            None => {
+                let timestamp = timestamp.replace(',', ".");
+                INFERRED_FORMATS.iter().find_map(|format| {
+                    chrono::NaiveDateTime::parse_from_str(timestamp.as_str(), format).ok()
+                })
+            }
         }
     }
 
+    // The byte offset of the first token in the input stream. A deduplicated log event starts at
+    // its first occurrence.
+    pub fn get_start_offset(&self) -> u64 {
+        self.start_offset
+    }
+
     pub fn get_line_range(&self) -> (usize, usize) {
         self.line_range
     }
 
+    // Returns the text of the given line of the log event, excluding its line ending, e.g. the
+    // whole line of a variable token found in the middle of it. Invalid UTF-8 is replaced, like
+    // with `to_string`. `None` if the line isn't within `get_line_range`.
+    pub fn get_line_text(&self, line_num: usize) -> Option<&str> {
+        let start_line = self.line_range.0;
+        let line_texts = self.line_texts.get_or_init(|| self.build_line_texts());
+        let line = line_texts.get(line_num.checked_sub(start_line)?)?;
+        Some(line.strip_suffix('\r').unwrap_or(line))
+    }
+
+    // Groups the bytes of the tokens by the line numbers of the tokens, excluding the line endings.
+    // A token spanning several lines, i.e. a multiline variable, is on one more line after each of
+    // its `\n`.
+    fn build_line_texts(&self) -> Vec<String> {
+        let (start_line, end_line) = self.line_range;
+        let mut lines = vec![Vec::new(); end_line - start_line + 1];
+        for token in &self.tokens {
+            let token_lines = token.get_buf().split(|c| b'\n' == *c);
+            for (line_idx, bytes) in (token.get_line_num() - start_line..).zip(token_lines) {
+                if let Some(line) = lines.get_mut(line_idx) {
+                    line.extend_from_slice(bytes);
+                }
+            }
+        }
+        lines
+            .iter()
+            .map(|line| String::from_utf8_lossy(line).into_owned())
+            .collect()
+    }
+
+    // The tokens following the timestamp starting the log event, if any. With an embedded
+    // timestamp, the log message is made of all the tokens, including the timestamp token, so
+    // that the text leading the timestamp isn't lost.
     pub fn get_log_message_tokens(&self) -> &[Token] {
-        match self.has_timestamp {
-            true => &self.tokens[1..],
-            false => &self.tokens[..],
+        match self.timestamp_idx {
+            Some(0) => &self.tokens[1..],
+            _ => &self.tokens[..],
         }
     }
 
@@ -129,9 +700,222 @@ impl LogEvent {
         result
     }
 
+    // The original bytes of the log event. Unlike `to_string`, invalid UTF-8 isn't replaced.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.tokens
+            .iter()
+            .flat_map(|token| token.get_buf().iter().copied())
+            .collect()
+    }
+
+    // Feeds the bytes of the log event back through the given lexer, numbering the tokens from the
+    // first line of the log event. For a correct tokenization, the tokens are the same as the ones
+    // of the log event (idempotence), which makes this a handy check in tests. The lexer is reset
+    // afterwards, so that a single lexer can relex every log event without compiling the schema
+    // again.
+    pub fn relex(&self, lexer: &mut Lexer) -> Result<Vec<Token>> {
+        let input_stream = Box::new(StringStream::from_bytes(self.to_bytes()));
+        lexer.set_input_stream_with_line_offset(input_stream, self.line_range.0);
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.get_next_token()? {
+            tokens.push(token);
+        }
+        lexer.reset();
+        Ok(tokens)
+    }
+
+    // Consumes the log event into its tokens, including the timestamp token if any
+    pub fn into_tokens(self) -> Vec<Token> {
+        self.tokens
+    }
+
+    // The number of consecutive log events collapsed into this one by `LogParser::set_dedup_mode`,
+    // including itself. Always 1 unless deduplication is enabled.
+    pub fn repeat_count(&self) -> usize {
+        self.repeat_count
+    }
+
+    // Whether the given log event repeats this one as per the given mode
+    fn is_repeated_by(&self, other: &LogEvent, dedup_mode: DedupMode) -> bool {
+        let tokens = self.get_log_message_tokens();
+        let other_tokens = other.get_log_message_tokens();
+        if tokens.len() != other_tokens.len() {
+            return false;
+        }
+        // An embedded timestamp is part of the log message, but never makes a difference
+        let embedded_timestamp_idx = self.timestamp_idx.filter(|idx| 0 != *idx);
+        let other_embedded_timestamp_idx = other.timestamp_idx.filter(|idx| 0 != *idx);
+        if embedded_timestamp_idx != other_embedded_timestamp_idx {
+            return false;
+        }
+        tokens
+            .iter()
+            .zip(other_tokens)
+            .enumerate()
+            .all(|(idx, (token, other_token))| {
+                if token.get_token_type() != other_token.get_token_type() {
+                    return false;
+                }
+                match (dedup_mode, token.get_token_type()) {
+                    (DedupMode::Template, TokenType::Variable(_) | TokenType::Timestamp(_)) => true,
+                    _ if Some(idx) == embedded_timestamp_idx => true,
+                    _ => token.get_buf() == other_token.get_buf(),
+                }
+            })
+    }
+
     pub fn get_num_tokens(&self) -> usize {
         self.tokens.len()
     }
+
+    // Whether the log event ends with its own `\n`. An incomplete log event is either the tail of
+    // an input that doesn't end with `\n`, or a log event force-emitted by
+    // `set_max_tokens_per_event`. Streaming consumers should hold back an incomplete tail, since
+    // the rest of its last line may still arrive.
+    pub fn is_complete(&self) -> bool {
+        matches!(
+            self.tokens.last().unwrap().get_token_type(),
+            TokenType::StaticTextWithEndLine
+        )
+    }
+
+    // Whether the log message has no variable token. Any other token (e.g. `Unmatched` or a
+    // mid-line timestamp) doesn't count as a variable.
+    pub fn has_only_static_text(&self) -> bool {
+        let tokens = self.get_log_message_tokens();
+        false == tokens.iter().any(|token| token.is_variable())
+    }
+
+    // Returns all the variable tokens of the given variable schema ID, in order. Timestamp and
+    // static text tokens are never returned.
+    pub fn get_variables_by_id(&self, id: usize) -> Vec<&Token> {
+        self.get_log_message_tokens()
+            .iter()
+            .filter(|token| match token.get_token_type() {
+                TokenType::Variable(var_id) => var_id == id,
+                _ => false,
+            })
+            .collect()
+    }
+
+    // Returns all the variable tokens of the given variable name, in order. Empty if the schema
+    // has no such variable.
+    pub fn get_variables_by_name(&self, name: &str) -> Vec<&Token> {
+        match self.schema_config.get_var_id_by_name(name) {
+            Some(var_id) => self.get_variables_by_id(var_id),
+            None => Vec::new(),
+        }
+    }
+
+    // Whether the log message has a variable token of the given variable name. Always false if the
+    // schema has no such variable.
+    pub fn contains_variable_named(&self, name: &str) -> bool {
+        match self.schema_config.get_var_id_by_name(name) {
+            Some(var_id) => self
+                .get_log_message_tokens()
+                .iter()
+                .any(|token| token.variable_id() == Some(var_id)),
+            None => false,
+        }
+    }
+
+    // Lists the variables of the log message one per line, each with the regex of its variable
+    // schema, e.g. `int: "1234" matched by '\-{0,1}\d+'`. Meant for debugging a schema.
+    pub fn describe_variables(&self) -> String {
+        let mut description = String::new();
+        for token in self.get_log_message_tokens() {
+            let Some(var_id) = token.variable_id() else {
+                continue;
+            };
+            description += &format!(
+                "{}: \"{}\" matched by '{}'\n",
+                self.schema_config.get_var_name(var_id).unwrap_or_default(),
+                token.get_buf_as_string().escape_default(),
+                self.schema_config.get_var_regex(var_id).unwrap_or_default()
+            );
+        }
+        description
+    }
+
+    pub fn contains_timestamp(&self) -> bool {
+        self.timestamp_idx.is_some()
+    }
+
+    // The timestamp and the first `loglevel` variable of the log event
+    pub fn header(&self) -> EventHeader {
+        self.header_with_severity_var(Self::DEFAULT_SEVERITY_VAR_NAME)
+    }
+
+    // Same as `header`, with the severity taken from the first variable of the given name
+    pub fn header_with_severity_var(&self, severity_var_name: &str) -> EventHeader {
+        EventHeader::new(
+            self.get_timestamp_token()
+                .map(|token| token.get_buf_as_string()),
+            self.get_variables_by_name(severity_var_name)
+                .first()
+                .map(|token| token.get_buf_as_string()),
+        )
+    }
+
+    // Serializes the log event into a JSON object of the form:
+    // `{"timestamp": "...", "line_range": [1, 2], "tokens": [{"type": "variable", "name": "int",
+    // "text": "42"}, ...]}`, where `timestamp` is `null` if the log event has no timestamp, and
+    // `tokens` are the log message tokens. Only variable tokens carry a `name`.
+    pub fn to_json(&self) -> Value {
+        let tokens: Vec<Value> = self
+            .get_log_message_tokens()
+            .iter()
+            .map(|token| match token.get_token_type() {
+                TokenType::Variable(var_id) => json!({
+                    "type": "variable",
+                    "name": self.schema_config.get_var_name(var_id),
+                    "text": token.get_buf_as_string(),
+                }),
+                TokenType::Timestamp(_) => json!({
+                    "type": "timestamp",
+                    "text": token.get_buf_as_string(),
+                }),
+                TokenType::StaticText => json!({
+                    "type": "static_text",
+                    "text": token.get_buf_as_string(),
+                }),
+                TokenType::StaticTextWithEndLine => json!({
+                    "type": "static_text_with_end_line",
+                    "text": token.get_buf_as_string(),
+                }),
+                TokenType::Delimiter => json!({
+                    "type": "delimiter",
+                    "text": token.get_buf_as_string(),
+                }),
+                TokenType::Unmatched => json!({
+                    "type": "unmatched",
+                    "text": token.get_buf_as_string(),
+                }),
+                TokenType::End => json!({
+                    "type": "end",
+                    "text": "",
+                }),
+            })
+            .collect();
+        let (start_line, end_line) = self.get_line_range();
+        json!({
+            "timestamp": self.get_timestamp_token().map(|token| token.get_buf_as_string()),
+            "line_range": [start_line, end_line],
+            "tokens": tokens,
+        })
+    }
+
+    // Returns the logfmt key/value pairs of the log message. Always empty unless the log event is
+    // emitted by a `LogParser` with logfmt mode enabled.
+    pub fn logfmt_pairs(&self) -> &IndexMap<String, String> {
+        &self.logfmt_pairs
+    }
+
+    // Returns the fields named by position, in order. Always empty unless the log event is emitted
+    // by a `LogParser` with a positional schema set.
+    pub fn positional_fields(&self) -> &IndexMap<String, String> {
+        &self.positional_fields
+    }
 }
 
 impl Debug for LogEvent {
@@ -0,0 +1,87 @@
+use indexmap::IndexMap;
+
+// Splits a logfmt-formatted text (`key1=val1 key2="val 2"`) into key/value pairs. Values can be
+// double-quoted to include whitespace, and `\"` / `\\` are unescaped inside quotes. A key without
+// `=` is kept with an empty value. If a key appears multiple times, the last value wins.
+pub(crate) fn parse_logfmt_pairs(text: &str) -> IndexMap<String, String> {
+    let mut pairs = IndexMap::new();
+    let mut chars = text.chars().peekable();
+
+    loop {
+        while let Some(c) = chars.peek() {
+            if false == c.is_whitespace() {
+                break;
+            }
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() || c == '=' {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+
+        let mut value = String::new();
+        if let Some('=') = chars.peek() {
+            chars.next();
+            if let Some('"') = chars.peek() {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(escaped) => value.push(escaped),
+                            None => value.push(c),
+                        },
+                        _ => value.push(c),
+                    }
+                }
+            } else {
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    value.push(c);
+                    chars.next();
+                }
+            }
+        }
+
+        if false == key.is_empty() {
+            pairs.insert(key, value);
+        }
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_logfmt_pairs() {
+        let pairs = parse_logfmt_pairs(
+            "level=info msg=\"user logged in\" user_id=42 empty= flag path=\"C:\\\\tmp\"\n",
+        );
+        let expected: Vec<(&str, &str)> = vec![
+            ("level", "info"),
+            ("msg", "user logged in"),
+            ("user_id", "42"),
+            ("empty", ""),
+            ("flag", ""),
+            ("path", "C:\\tmp"),
+        ];
+        let actual: Vec<(&str, &str)> = pairs
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+}
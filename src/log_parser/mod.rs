@@ -1,4 +1,18 @@
+mod event_header;
 mod log_parser;
+mod logfmt;
+mod numeric_summary;
+mod parallel;
+mod positional;
+mod progress;
+mod variable_record;
 
+pub use event_header::EventHeader;
+pub use log_parser::DedupMode;
 pub use log_parser::LogEvent;
 pub use log_parser::LogParser;
+pub use numeric_summary::NumericSummary;
+pub use parallel::parse_files_in_parallel;
+pub use positional::PositionalSchema;
+pub use progress::ParseProgress;
+pub use variable_record::VariableRecord;
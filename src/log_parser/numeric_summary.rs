@@ -0,0 +1,56 @@
+// Summary statistics of the values matched by a numeric variable. Values that don't parse into
+// `f64` (e.g. a hex value matched by a loose pattern) are only counted as skipped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NumericSummary {
+    count: usize,
+    skipped: usize,
+    min: f64,
+    max: f64,
+    sum: f64,
+}
+
+impl NumericSummary {
+    pub(crate) fn add_value(&mut self, value: &str) {
+        let value = match value.trim().parse::<f64>() {
+            Ok(value) if value.is_finite() => value,
+            _ => {
+                self.skipped += 1;
+                return;
+            }
+        };
+        if 0 == self.count {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    // The number of parsed values
+    pub fn get_count(&self) -> usize {
+        self.count
+    }
+
+    // The number of values that couldn't be parsed
+    pub fn get_skipped(&self) -> usize {
+        self.skipped
+    }
+
+    // `None` if no value has been parsed
+    pub fn get_min(&self) -> Option<f64> {
+        (0 != self.count).then_some(self.min)
+    }
+
+    // `None` if no value has been parsed
+    pub fn get_max(&self) -> Option<f64> {
+        (0 != self.count).then_some(self.max)
+    }
+
+    // `None` if no value has been parsed
+    pub fn get_mean(&self) -> Option<f64> {
+        (0 != self.count).then(|| self.sum / self.count as f64)
+    }
+}
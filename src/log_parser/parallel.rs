@@ -0,0 +1,66 @@
+use crate::error_handling::Error::IOError;
+use crate::error_handling::Result;
+use crate::lexer::{Lexer, Token};
+use crate::log_parser::LogParser;
+use crate::parser::SchemaConfig;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+// The tokens of every log event of a file
+type FileResult = Result<Vec<Vec<Token>>>;
+
+// Parses the given files on up to `num_threads` threads, and returns the result of each file in
+// the order of `paths`: the tokens of every log event of the file, or the first error met while
+// parsing it. The schema is compiled once, and every thread builds its own parser from the shared
+// DFAs, reusing it for all the files it parses. Fails without parsing any file if the schema can't
+// be compiled.
+pub fn parse_files_in_parallel(
+    schema_config: &Arc<SchemaConfig>,
+    paths: &[PathBuf],
+    num_threads: usize,
+) -> Result<Vec<FileResult>> {
+    let compiled_schema = Lexer::new_arc(schema_config.clone())?.get_compiled_schema();
+    let next_path_idx = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<FileResult>>> =
+        Mutex::new((0..paths.len()).map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..num_threads.clamp(1, paths.len().max(1)) {
+            scope.spawn(|| {
+                let mut log_parser =
+                    LogParser::from_lexer(Lexer::from_compiled_schema(compiled_schema.clone()));
+                loop {
+                    let path_idx = next_path_idx.fetch_add(1, Ordering::Relaxed);
+                    if path_idx >= paths.len() {
+                        break;
+                    }
+                    let result = parse_file(&mut log_parser, &paths[path_idx]);
+                    results.lock().unwrap()[path_idx] = Some(result);
+                }
+            });
+        }
+    });
+
+    Ok(results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("Every path is parsed by a thread"))
+        .collect())
+}
+
+fn parse_file(log_parser: &mut LogParser, path: &Path) -> FileResult {
+    let Some(path) = path.to_str() else {
+        return Err(IOError(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Non UTF-8 path",
+        )));
+    };
+    log_parser.set_input_file(path)?;
+    let mut log_events = Vec::new();
+    while let Some(log_event) = log_parser.parse_next_log_event()? {
+        log_events.push(log_event.into_tokens());
+    }
+    Ok(log_events)
+}
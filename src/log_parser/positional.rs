@@ -0,0 +1,39 @@
+use crate::lexer::Token;
+use indexmap::IndexMap;
+
+// Names the fields of strictly positional logs (e.g. `<time> <level> <msg>`) by their position
+// rather than by regex: after tokenization, the Nth token holding any non-whitespace byte
+// (including the timestamp token) is labeled with the Nth field name. See
+// `LogParser::set_positional_schema`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PositionalSchema {
+    field_names: Vec<String>,
+}
+
+impl PositionalSchema {
+    pub fn new(field_names: &[&str]) -> Self {
+        Self {
+            field_names: field_names.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    pub fn get_field_names(&self) -> &[String] {
+        &self.field_names
+    }
+
+    // Maps every field name to its token, trimmed of the surrounding whitespace. The field names
+    // past the last token are left out, and so are the tokens past the last field name.
+    pub(crate) fn label_fields(&self, tokens: &[Token]) -> IndexMap<String, String> {
+        tokens
+            .iter()
+            .filter(|token| {
+                token
+                    .get_buf()
+                    .iter()
+                    .any(|c| false == c.is_ascii_whitespace())
+            })
+            .zip(&self.field_names)
+            .map(|(token, name)| (name.clone(), token.get_buf_as_string().trim().to_string()))
+            .collect()
+    }
+}
@@ -0,0 +1,32 @@
+// A snapshot of the progress of `LogParser::parse_all_with_progress`, as of the last emitted log
+// event
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParseProgress {
+    bytes_processed: u64,
+    events_emitted: usize,
+    current_line: usize,
+}
+
+impl ParseProgress {
+    pub(crate) fn new(bytes_processed: u64, events_emitted: usize, current_line: usize) -> Self {
+        Self {
+            bytes_processed,
+            events_emitted,
+            current_line,
+        }
+    }
+
+    // The number of input bytes of the log events emitted so far
+    pub fn get_bytes_processed(&self) -> u64 {
+        self.bytes_processed
+    }
+
+    pub fn get_events_emitted(&self) -> usize {
+        self.events_emitted
+    }
+
+    // The last line of the last emitted log event, or 0 if no log event has been emitted
+    pub fn get_current_line(&self) -> usize {
+        self.current_line
+    }
+}
@@ -0,0 +1,37 @@
+// A single variable occurrence, as extracted by `LogParser::extract_variables`
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariableRecord {
+    name: String,
+    value: String,
+    line: usize,
+    start_offset: u64,
+}
+
+impl VariableRecord {
+    pub(crate) fn new(name: String, value: String, line: usize, start_offset: u64) -> Self {
+        Self {
+            name,
+            value,
+            line,
+            start_offset,
+        }
+    }
+
+    // The name of the variable schema that matched the value
+    pub fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn get_value(&self) -> &str {
+        self.value.as_str()
+    }
+
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    // The byte offset of the value in the input stream
+    pub fn get_start_offset(&self) -> u64 {
+        self.start_offset
+    }
+}
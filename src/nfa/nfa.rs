@@ -1,8 +1,12 @@
 use crate::error_handling::Result;
+#[cfg(any(test, feature = "regex-engine"))]
 use crate::parser::regex_parser::parser::RegexParser;
-use std::collections::HashMap;
-use std::fmt::Debug;
-use std::hash::Hash;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
 
 use crate::error_handling::Error::{
     NegationNotSupported, NonGreedyRepetitionNotSupported, NoneASCIICharacters,
@@ -10,46 +14,48 @@ use crate::error_handling::Error::{
     UnsupportedGroupKindType,
 };
 use regex_syntax::ast::{
-    Alternation, Ast, ClassBracketed, ClassPerl, ClassPerlKind, ClassSet, ClassSetItem,
-    ClassSetRange, ClassSetUnion, Concat, Group, GroupKind, Literal, Repetition, RepetitionKind,
-    RepetitionRange,
+    Alternation, Assertion, AssertionKind, Ast, ClassBracketed, ClassPerl, ClassPerlKind, ClassSet,
+    ClassSetItem, ClassSetRange, ClassSetUnion, Concat, Group, GroupKind, Literal, Repetition,
+    RepetitionKind, RepetitionRange,
 };
 
-const DIGIT_TRANSITION: u128 = 0x000000000000000003ff000000000000;
-const SPACE_TRANSITION: u128 = 0x00000000000000000000000100003e00;
-const WORD_TRANSITION: u128 = 0x07fffffe87fffffe03ff000000000000;
+// Symbol ranges are inclusive, sorted, and neither overlapping nor adjacent
+const DIGIT_RANGES: &[(u8, u8)] = &[(b'0', b'9')];
+const SPACE_RANGES: &[(u8, u8)] = &[(b'\t', b'\r'), (b' ', b' ')];
+const WORD_RANGES: &[(u8, u8)] = &[(b'0', b'9'), (b'A', b'Z'), (b'_', b'_'), (b'a', b'z')];
 
-const EPSILON_TRANSITION: u128 = 0x0;
+const EPSILON_RANGES: &[(u8, u8)] = &[];
 
-const DOT_TRANSITION: u128 = !EPSILON_TRANSITION;
+const DOT_RANGES: &[(u8, u8)] = &[(0, 127)];
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+const LOWERCASE_RANGE: (u8, u8) = (b'a', b'z');
+const UPPERCASE_RANGE: (u8, u8) = (b'A', b'Z');
+const CASE_OFFSET: u8 = b'a' - b'A';
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct State(pub usize);
 
+// A transition on every symbol within its symbol ranges. A transition without any symbol range is
+// an epsilon transition.
 pub struct Transition {
     from: State,
     to: State,
-    symbol_onehot_encoding: u128,
+    symbol_ranges: Vec<(u8, u8)>,
     tag: i16,
 }
 
 impl Debug for Transition {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        if 0 == self.symbol_onehot_encoding {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if self.symbol_ranges.is_empty() {
             return write!(f, "{:?} -> {:?}, symbol: {}", self.from, self.to, "epsilon");
         }
 
-        let mut char_vec: Vec<char> = Vec::new();
-        for i in 0..128u8 {
-            let mask = 1u128 << i;
-            if mask & self.symbol_onehot_encoding == mask {
-                char_vec.push(i as char);
-            }
-        }
         write!(
             f,
             "{:?} -> {:?}, symbol: {:?}",
-            self.from, self.to, char_vec
+            self.from,
+            self.to,
+            self.get_symbol()
         )
     }
 }
@@ -79,6 +85,70 @@ impl Transition {
         symbol_onehot_encoding
     }
 
+    // Extends the given symbols so that every ASCII letter matches both its lowercase and its
+    // uppercase form. Non-letter symbols are kept unchanged.
+    pub fn convert_to_case_insensitive_symbol_onehot_encoding(
+        symbol_onehot_encoding: u128,
+    ) -> u128 {
+        Self::convert_symbol_ranges_to_onehot_encoding(&Self::convert_to_case_insensitive_ranges(
+            &Self::convert_symbol_onehot_encoding_to_ranges(symbol_onehot_encoding),
+        ))
+    }
+
+    // Same as `convert_to_case_insensitive_symbol_onehot_encoding`, on symbol ranges
+    pub fn convert_to_case_insensitive_ranges(symbol_ranges: &[(u8, u8)]) -> Vec<(u8, u8)> {
+        let mut case_insensitive_ranges = symbol_ranges.to_vec();
+        for &(begin, end) in symbol_ranges {
+            let (lower_begin, lower_end) =
+                (begin.max(LOWERCASE_RANGE.0), end.min(LOWERCASE_RANGE.1));
+            if lower_begin <= lower_end {
+                case_insensitive_ranges.push((lower_begin - CASE_OFFSET, lower_end - CASE_OFFSET));
+            }
+            let (upper_begin, upper_end) =
+                (begin.max(UPPERCASE_RANGE.0), end.min(UPPERCASE_RANGE.1));
+            if upper_begin <= upper_end {
+                case_insensitive_ranges.push((upper_begin + CASE_OFFSET, upper_end + CASE_OFFSET));
+            }
+        }
+        Self::normalize_symbol_ranges(case_insensitive_ranges)
+    }
+
+    // Sorts the given symbol ranges and merges the overlapping or adjacent ones
+    pub fn normalize_symbol_ranges(mut symbol_ranges: Vec<(u8, u8)>) -> Vec<(u8, u8)> {
+        symbol_ranges.sort();
+        let mut normalized_ranges: Vec<(u8, u8)> = Vec::with_capacity(symbol_ranges.len());
+        for (begin, end) in symbol_ranges {
+            match normalized_ranges.last_mut() {
+                Some(last_range) if begin as u16 <= last_range.1 as u16 + 1 => {
+                    last_range.1 = last_range.1.max(end);
+                }
+                _ => normalized_ranges.push((begin, end)),
+            }
+        }
+        normalized_ranges
+    }
+
+    pub fn convert_symbol_onehot_encoding_to_ranges(symbol_onehot_encoding: u128) -> Vec<(u8, u8)> {
+        let mut symbol_ranges: Vec<(u8, u8)> = Vec::new();
+        let mut remaining = symbol_onehot_encoding;
+        while 0 != remaining {
+            let begin = remaining.trailing_zeros();
+            let len = (remaining >> begin).trailing_ones();
+            symbol_ranges.push((begin as u8, (begin + len - 1) as u8));
+            remaining &= !(((1u128 << (len - 1)) << 1).wrapping_sub(1) << begin);
+        }
+        symbol_ranges
+    }
+
+    pub fn convert_symbol_ranges_to_onehot_encoding(symbol_ranges: &[(u8, u8)]) -> u128 {
+        let mut symbol_onehot_encoding: u128 = 0;
+        for &(begin, end) in symbol_ranges {
+            let len = (end - begin) as u32 + 1;
+            symbol_onehot_encoding |= ((1u128 << (len - 1)) << 1).wrapping_sub(1) << begin;
+        }
+        symbol_onehot_encoding
+    }
+
     pub fn convert_char_vec_to_symbol_onehot_encoding(char_vec: Vec<u8>) -> u128 {
         let mut symbol_onehot_encoding: u128 = 0;
         for c in char_vec {
@@ -91,23 +161,29 @@ impl Transition {
         Transition {
             from,
             to,
-            symbol_onehot_encoding,
+            symbol_ranges: Self::convert_symbol_onehot_encoding_to_ranges(symbol_onehot_encoding),
             tag,
         }
     }
 
     pub fn get_symbol_onehot_encoding(&self) -> u128 {
-        self.symbol_onehot_encoding
+        Self::convert_symbol_ranges_to_onehot_encoding(&self.symbol_ranges)
+    }
+
+    // The sorted, disjoint and non-adjacent inclusive ranges of the symbols of the transition
+    pub fn get_symbol_ranges(&self) -> &[(u8, u8)] {
+        &self.symbol_ranges
+    }
+
+    pub fn is_epsilon(&self) -> bool {
+        self.symbol_ranges.is_empty()
     }
 
     pub fn get_symbol(&self) -> Vec<char> {
-        let mut symbol = vec![];
-        for i in 0..=127 {
-            if self.symbol_onehot_encoding & (1 << i) != 0 {
-                symbol.push(i as u8 as char);
-            }
-        }
-        symbol
+        self.symbol_ranges
+            .iter()
+            .flat_map(|&(begin, end)| (begin..=end).map(|c| c as char))
+            .collect()
     }
 
     pub fn get_to_state(&self) -> State {
@@ -119,7 +195,8 @@ pub struct NFA {
     start: State,
     accept: State,
     states: Vec<State>,
-    transitions: HashMap<State, Vec<Transition>>,
+    transitions: BTreeMap<State, Vec<Transition>>,
+    case_insensitive: bool,
 }
 
 impl NFA {
@@ -135,10 +212,26 @@ impl NFA {
             start: NFA::START_STATE,
             accept: NFA::ACCEPT_STATE,
             states: states_vec,
-            transitions: HashMap::new(),
+            transitions: BTreeMap::new(),
+            case_insensitive: false,
         }
     }
 
+    // When set, ASCII letters in literals and ranges added afterwards match both cases.
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_insensitive = case_insensitive;
+    }
+
+    // Parses the given regex and builds it into a new NFA between its start and accept states.
+    #[cfg(any(test, feature = "regex-engine"))]
+    pub fn from_regex(pattern: &str) -> Result<Self> {
+        let mut parser = RegexParser::new();
+        let ast = parser.parse_into_ast(pattern)?;
+        let mut nfa = NFA::new();
+        nfa.add_ast_to_nfa(&ast, NFA::START_STATE, NFA::ACCEPT_STATE)?;
+        Ok(nfa)
+    }
+
     pub fn add_ast_to_nfa(&mut self, ast: &Ast, start: State, end: State) -> Result<()> {
         match ast {
             Ast::Literal(literal) => self.add_literal(&**literal, start, end)?,
@@ -149,6 +242,7 @@ impl NFA {
             Ast::ClassBracketed(bracketed) => self.add_bracketed(&**bracketed, start, end)?,
             Ast::Alternation(alternation) => self.add_alternation(&**alternation, start, end)?,
             Ast::Group(group) => self.add_group(&**group, start, end)?,
+            Ast::Assertion(assertion) => self.add_assertion(&**assertion, start, end)?,
             _ => {
                 return Err(UnsupportedAstNodeType("Ast Type not supported"));
             }
@@ -163,7 +257,7 @@ impl NFA {
     }
 
     fn add_dot(&mut self, start: State, end: State) -> Result<()> {
-        self.add_transition(start, end, DOT_TRANSITION);
+        self.add_transition(start, end, DOT_RANGES.to_vec());
         Ok(())
     }
 
@@ -172,9 +266,9 @@ impl NFA {
             return Err(NegationNotSupported("Negation in perl not yet supported."));
         }
         match perl.kind {
-            ClassPerlKind::Digit => self.add_transition(start, end, DIGIT_TRANSITION),
-            ClassPerlKind::Space => self.add_transition(start, end, SPACE_TRANSITION),
-            ClassPerlKind::Word => self.add_transition(start, end, WORD_TRANSITION),
+            ClassPerlKind::Digit => self.add_transition(start, end, DIGIT_RANGES.to_vec()),
+            ClassPerlKind::Space => self.add_transition(start, end, SPACE_RANGES.to_vec()),
+            ClassPerlKind::Word => self.add_transition(start, end, WORD_RANGES.to_vec()),
         }
         Ok(())
     }
@@ -193,9 +287,29 @@ impl NFA {
         Ok(())
     }
 
+    // Line anchors are zero-width: they are checked by the lexer against the match boundaries
+    // instead of consuming any symbol.
+    fn add_assertion(&mut self, assertion: &Assertion, start: State, end: State) -> Result<()> {
+        match assertion.kind {
+            AssertionKind::StartLine
+            | AssertionKind::StartText
+            | AssertionKind::EndLine
+            | AssertionKind::EndText => self.add_epsilon_transition(start, end),
+            _ => return Err(UnsupportedAstNodeType("Assertion type not supported")),
+        }
+        Ok(())
+    }
+
+    // Capture semantics are ignored: any group only delimits its inner pattern. Flags set by a
+    // non-capturing group (e.g. `(?i:...)`) aren't supported.
     fn add_group(&mut self, group: &Group, start: State, end: State) -> Result<()> {
         match &group.kind {
-            GroupKind::CaptureIndex(_) => self.add_ast_to_nfa(&group.ast, start, end)?,
+            GroupKind::CaptureIndex(_) | GroupKind::CaptureName { .. } => {
+                self.add_ast_to_nfa(&group.ast, start, end)?
+            }
+            GroupKind::NonCapturing(flags) if flags.items.is_empty() => {
+                self.add_ast_to_nfa(&group.ast, start, end)?
+            }
             _ => return Err(UnsupportedGroupKindType),
         }
         Ok(())
@@ -350,23 +464,18 @@ impl NFA {
     }
 
     fn add_transition_from_range(&mut self, from: State, to: State, range: Option<(u8, u8)>) {
-        let transition = Transition {
-            from: from.clone(),
-            to: to.clone(),
-            symbol_onehot_encoding: Transition::convert_char_range_to_symbol_onehot_encoding(range),
-            tag: -1,
-        };
-        self.transitions
-            .entry(from)
-            .or_insert(vec![])
-            .push(transition);
+        let mut symbol_ranges: Vec<(u8, u8)> = range.into_iter().collect();
+        if self.case_insensitive {
+            symbol_ranges = Transition::convert_to_case_insensitive_ranges(&symbol_ranges);
+        }
+        self.add_transition(from, to, symbol_ranges);
     }
 
-    fn add_transition(&mut self, from: State, to: State, onehot: u128) {
+    fn add_transition(&mut self, from: State, to: State, symbol_ranges: Vec<(u8, u8)>) {
         let transition = Transition {
             from: from.clone(),
             to: to.clone(),
-            symbol_onehot_encoding: onehot,
+            symbol_ranges,
             tag: -1,
         };
         self.transitions
@@ -376,12 +485,29 @@ impl NFA {
     }
 
     fn add_epsilon_transition(&mut self, from: State, to: State) {
-        self.add_transition(from, to, EPSILON_TRANSITION);
+        self.add_transition(from, to, EPSILON_RANGES.to_vec());
+    }
+
+    // Removes the given symbols from all non-epsilon transitions. A transition left without any
+    // symbol is dropped instead of becoming an epsilon transition.
+    pub fn remove_symbols(&mut self, symbol_onehot_encoding: u128) {
+        let removed_ranges =
+            Transition::convert_symbol_onehot_encoding_to_ranges(symbol_onehot_encoding);
+        for transitions in self.transitions.values_mut() {
+            transitions.retain_mut(|transition| {
+                if transition.is_epsilon() {
+                    return true;
+                }
+                transition.symbol_ranges =
+                    subtract_symbol_ranges(&transition.symbol_ranges, &removed_ranges);
+                false == transition.is_epsilon()
+            });
+        }
     }
 }
 
 impl Debug for NFA {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(
             f,
             "NFA( start: {:?}, accept: {:?}, states: {:?}, transitions: {{\n",
@@ -404,9 +530,10 @@ impl Debug for NFA {
 
 // NFA implementation for NFA to dfa conversion helper functions
 impl NFA {
-    pub fn epsilon_closure(&self, states: &Vec<State>) -> Vec<State> {
-        let mut closure = states.clone();
-        let mut stack = states.clone();
+    // Returns the given states and all the states reachable from them through epsilon transitions.
+    pub fn epsilon_closure(&self, states: &[State]) -> Vec<State> {
+        let mut closure = states.to_vec();
+        let mut stack = states.to_vec();
 
         while let Some(state) = stack.pop() {
             let transitions = self.transitions.get(&state);
@@ -415,7 +542,7 @@ impl NFA {
             }
 
             for transition in transitions.unwrap() {
-                if transition.symbol_onehot_encoding == 0 {
+                if transition.is_epsilon() {
                     let to_state = transition.to.clone();
                     if !closure.contains(&to_state) {
                         closure.push(to_state.clone());
@@ -449,7 +576,7 @@ impl NFA {
         self.accept.clone()
     }
 
-    pub fn get_transitions(&self) -> &HashMap<State, Vec<Transition>> {
+    pub fn get_transitions(&self) -> &BTreeMap<State, Vec<Transition>> {
         &self.transitions
     }
 
@@ -459,6 +586,33 @@ impl NFA {
 }
 
 // Helper functions
+// Returns the symbols of `symbol_ranges` that aren't in `removed_ranges`. Both inputs, and the
+// output, are sorted and disjoint.
+fn subtract_symbol_ranges(
+    symbol_ranges: &[(u8, u8)],
+    removed_ranges: &[(u8, u8)],
+) -> Vec<(u8, u8)> {
+    let mut remaining_ranges: Vec<(u8, u8)> = Vec::new();
+    for &(begin, end) in symbol_ranges {
+        let mut begin = begin as u16;
+        let end = end as u16;
+        for &(removed_begin, removed_end) in removed_ranges {
+            let (removed_begin, removed_end) = (removed_begin as u16, removed_end as u16);
+            if removed_end < begin || removed_begin > end {
+                continue;
+            }
+            if removed_begin > begin {
+                remaining_ranges.push((begin as u8, (removed_begin - 1) as u8));
+            }
+            begin = removed_end + 1;
+        }
+        if begin <= end {
+            remaining_ranges.push((begin as u8, end as u8));
+        }
+    }
+    remaining_ranges
+}
+
 fn get_ascii_char(c: char) -> Result<u8> {
     if false == c.is_ascii() {
         return Err(NoneASCIICharacters);
@@ -470,6 +624,11 @@ fn get_ascii_char(c: char) -> Result<u8> {
 mod tests {
     use super::*;
 
+    const DIGIT_TRANSITION: u128 = 0x000000000000000003ff000000000000;
+    const SPACE_TRANSITION: u128 = 0x00000000000000000000000100003e00;
+
+    const EPSILON_TRANSITION: u128 = 0x0;
+
     #[test]
     fn test_single_char() -> Result<()> {
         let mut parser = RegexParser::new();
@@ -486,6 +645,32 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_hex_escape() -> Result<()> {
+        for regex in [r"\x09", r"\x{9}", r"\t", r"\u0009"] {
+            let mut parser = RegexParser::new();
+            let parsed_ast = parser.parse_into_ast(regex)?;
+            let mut nfa = NFA::new();
+            nfa.add_ast_to_nfa(&parsed_ast, NFA::START_STATE, NFA::ACCEPT_STATE)?;
+
+            assert!(has_transition(
+                &nfa,
+                NFA::START_STATE,
+                NFA::ACCEPT_STATE,
+                Transition::convert_char_to_symbol_onehot_encoding('\t')
+            ));
+        }
+
+        let mut parser = RegexParser::new();
+        let parsed_ast = parser.parse_into_ast(r"\x{e9}")?;
+        let mut nfa = NFA::new();
+        assert!(matches!(
+            nfa.add_ast_to_nfa(&parsed_ast, NFA::START_STATE, NFA::ACCEPT_STATE),
+            Err(NoneASCIICharacters)
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_dot() -> Result<()> {
         {
@@ -1153,6 +1338,95 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_case_insensitive() -> Result<()> {
+        let mut parser = RegexParser::new();
+        let parsed_ast = parser.parse_into_ast(r"e[a-c]:")?;
+
+        let mut nfa = NFA::new();
+        nfa.set_case_insensitive(true);
+        nfa.add_ast_to_nfa(&parsed_ast, NFA::START_STATE, NFA::ACCEPT_STATE)?;
+
+        assert!(has_transition(
+            &nfa,
+            NFA::START_STATE,
+            State(2),
+            Transition::convert_char_vec_to_symbol_onehot_encoding(vec![b'e', b'E'])
+        ));
+        assert!(has_transition(
+            &nfa,
+            State(2),
+            State(3),
+            Transition::convert_char_vec_to_symbol_onehot_encoding(vec![
+                b'a', b'b', b'c', b'A', b'B', b'C'
+            ])
+        ));
+        assert!(has_transition(
+            &nfa,
+            State(3),
+            NFA::ACCEPT_STATE,
+            Transition::convert_char_to_symbol_onehot_encoding(':')
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_symbols() -> Result<()> {
+        let mut parser = RegexParser::new();
+        let parsed_ast = parser.parse_into_ast(r"(:|\d)+")?;
+
+        let mut nfa = NFA::new();
+        nfa.add_ast_to_nfa(&parsed_ast, NFA::START_STATE, NFA::ACCEPT_STATE)?;
+        nfa.remove_symbols(Transition::convert_char_vec_to_symbol_onehot_encoding(
+            vec![b':', b'0'],
+        ));
+
+        // `:` transitions are dropped entirely rather than turned into epsilon transitions
+        let colon_transition = Transition::convert_char_to_symbol_onehot_encoding(':');
+        assert!(has_no_transition(
+            &nfa,
+            State(3),
+            State(4),
+            colon_transition
+        ));
+        assert!(has_no_transition(
+            &nfa,
+            State(3),
+            State(4),
+            EPSILON_TRANSITION
+        ));
+        assert!(has_no_transition(
+            &nfa,
+            State(7),
+            State(8),
+            colon_transition
+        ));
+        assert!(has_no_transition(
+            &nfa,
+            State(7),
+            State(8),
+            EPSILON_TRANSITION
+        ));
+
+        // `0` is removed from the digit transitions
+        let digit_transition =
+            Transition::convert_char_range_to_symbol_onehot_encoding(Some((b'1', b'9')));
+        assert!(has_transition(&nfa, State(5), State(6), digit_transition));
+        assert!(has_transition(&nfa, State(9), State(10), digit_transition));
+
+        // Epsilon transitions are kept
+        assert!(has_transition(&nfa, State(4), State(2), EPSILON_TRANSITION));
+        assert!(has_transition(
+            &nfa,
+            State(2),
+            NFA::ACCEPT_STATE,
+            EPSILON_TRANSITION
+        ));
+
+        Ok(())
+    }
+
     fn has_transition(nfa: &NFA, from: State, to: State, onehot_trans: u128) -> bool {
         if from.0 >= nfa.states.len() || to.0 >= nfa.states.len() {
             return false;
@@ -1164,7 +1438,7 @@ mod tests {
             if to != trans.to {
                 continue;
             }
-            if trans.symbol_onehot_encoding == onehot_trans {
+            if trans.get_symbol_onehot_encoding() == onehot_trans {
                 return true;
             }
         }
@@ -1184,23 +1458,19 @@ mod tests {
         nfa.add_epsilon_transition(NFA::START_STATE, NFA::ACCEPT_STATE);
         nfa.add_epsilon_transition(NFA::ACCEPT_STATE, State(2));
         nfa.add_epsilon_transition(NFA::START_STATE, State(2));
-        nfa.add_transition(
-            State(2),
-            State(3),
-            Transition::convert_char_to_symbol_onehot_encoding('a'),
-        );
+        nfa.add_transition(State(2), State(3), vec![(b'a', b'a')]);
         nfa.add_epsilon_transition(State(3), State(5));
         nfa.add_epsilon_transition(State(3), State(4));
         nfa.add_epsilon_transition(State(4), State(6));
         nfa.add_epsilon_transition(State(6), State(3));
 
-        let closure = nfa.epsilon_closure(&vec![NFA::START_STATE]);
+        let closure = nfa.epsilon_closure(&[NFA::START_STATE]);
         assert_eq!(closure.len(), 3);
         assert_eq!(closure.contains(&NFA::START_STATE), true);
         assert_eq!(closure.contains(&NFA::ACCEPT_STATE), true);
         assert_eq!(closure.contains(&State(2)), true);
 
-        let closure = nfa.epsilon_closure(&vec![State(3)]);
+        let closure = nfa.epsilon_closure(&[State(3)]);
         assert_eq!(closure.len(), 4);
         assert_eq!(closure.contains(&State(3)), true);
         assert_eq!(closure.contains(&State(4)), true);
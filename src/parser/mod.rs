@@ -1,7 +1,17 @@
 pub(crate) mod regex_parser;
 
+#[cfg(feature = "std")]
 mod schema_parser;
 
+#[cfg(feature = "std")]
+pub use schema_parser::builder::SchemaConfigBuilder;
+#[cfg(feature = "std")]
+pub use schema_parser::builder::TimestampFormat;
+#[cfg(feature = "std")]
+pub use schema_parser::lint::LintWarning;
+#[cfg(feature = "std")]
 pub use schema_parser::parser::SchemaConfig;
+#[cfg(feature = "std")]
 pub use schema_parser::parser::TimestampSchema;
+#[cfg(feature = "std")]
 pub use schema_parser::parser::VarSchema;
@@ -1,5 +1,11 @@
-use crate::error_handling::{Error, Error::RegexParsingError, Result};
-use regex_syntax::ast::{parse::Parser, Ast};
+use crate::error_handling::{
+    Error, Error::UnsupportedRegexConstruct, Result, UnsupportedRegexConstructInfo,
+};
+use alloc::boxed::Box;
+use regex_syntax::ast::{
+    parse::Parser, AssertionKind, Ast, ClassBracketed, ClassSet, ClassSetItem, GroupKind, Literal,
+    LiteralKind, Span,
+};
 
 // This is a wrapper of `regex_syntax::ast::parse::Parser`, which can be extended to hold
 // program-specific data members.
@@ -15,11 +21,108 @@ impl RegexParser {
     }
 
     pub fn parse_into_ast(&mut self, pattern: &str) -> Result<Ast> {
-        match self.m_parser.parse(pattern) {
-            Ok(ast) => Ok(ast),
-            Err(e) => Err(RegexParsingError(e)),
+        Ok(self.m_parser.parse(pattern)?)
+    }
+
+    // Walks the AST and reports the first construct the NFA can't be built from, with its offset
+    // in the pattern. Lookarounds and backreferences are already rejected by `parse_into_ast`.
+    pub fn check_supported(ast: &Ast) -> Result<()> {
+        match ast {
+            Ast::Empty(span) => Err(Self::unsupported("empty pattern", span)),
+            Ast::Flags(flags) => Err(Self::unsupported("inline flags", &flags.span)),
+            Ast::Literal(literal) if false == literal.c.is_ascii() => {
+                Err(Self::non_ascii_literal(literal))
+            }
+            Ast::Literal(_) | Ast::Dot(_) => Ok(()),
+            Ast::Assertion(assertion) => match assertion.kind {
+                AssertionKind::StartLine
+                | AssertionKind::StartText
+                | AssertionKind::EndLine
+                | AssertionKind::EndText => Ok(()),
+                _ => Err(Self::unsupported("word boundary", &assertion.span)),
+            },
+            Ast::ClassUnicode(class) => Err(Self::unsupported("Unicode class", &class.span)),
+            Ast::ClassPerl(perl) if perl.negated => {
+                Err(Self::unsupported("negated Perl class", &perl.span))
+            }
+            Ast::ClassPerl(_) => Ok(()),
+            Ast::ClassBracketed(bracketed) => Self::check_bracketed_supported(bracketed),
+            Ast::Repetition(repetition) => {
+                if false == repetition.greedy {
+                    return Err(Self::unsupported("non-greedy repetition", &repetition.span));
+                }
+                Self::check_supported(&repetition.ast)
+            }
+            Ast::Group(group) => match &group.kind {
+                GroupKind::NonCapturing(flags) if false == flags.items.is_empty() => {
+                    Err(Self::unsupported("group flags", &flags.span))
+                }
+                _ => Self::check_supported(&group.ast),
+            },
+            Ast::Alternation(alternation) => {
+                alternation.asts.iter().try_for_each(Self::check_supported)
+            }
+            Ast::Concat(concat) => concat.asts.iter().try_for_each(Self::check_supported),
+        }
+    }
+
+    fn check_bracketed_supported(bracketed: &ClassBracketed) -> Result<()> {
+        if bracketed.negated {
+            return Err(Self::unsupported("negated bracket class", &bracketed.span));
+        }
+        match &bracketed.kind {
+            ClassSet::Item(item) => Self::check_class_set_item_supported(item),
+            ClassSet::BinaryOp(op) => {
+                Err(Self::unsupported("bracket class set operation", &op.span))
+            }
+        }
+    }
+
+    fn check_class_set_item_supported(item: &ClassSetItem) -> Result<()> {
+        match item {
+            ClassSetItem::Literal(literal) if false == literal.c.is_ascii() => {
+                Err(Self::non_ascii_literal(literal))
+            }
+            ClassSetItem::Range(range) if false == range.start.c.is_ascii() => {
+                Err(Self::non_ascii_literal(&range.start))
+            }
+            ClassSetItem::Range(range) if false == range.end.c.is_ascii() => {
+                Err(Self::non_ascii_literal(&range.end))
+            }
+            ClassSetItem::Literal(_) | ClassSetItem::Range(_) => Ok(()),
+            ClassSetItem::Perl(perl) if perl.negated => {
+                Err(Self::unsupported("negated Perl class", &perl.span))
+            }
+            ClassSetItem::Perl(_) => Ok(()),
+            ClassSetItem::Bracketed(bracketed) => Self::check_bracketed_supported(bracketed),
+            ClassSetItem::Union(union) => union
+                .items
+                .iter()
+                .try_for_each(Self::check_class_set_item_supported),
+            ClassSetItem::Empty(span) => Err(Self::unsupported("empty bracket class", span)),
+            ClassSetItem::Ascii(ascii) => Err(Self::unsupported("ASCII class", &ascii.span)),
+            ClassSetItem::Unicode(unicode) => {
+                Err(Self::unsupported("Unicode class", &unicode.span))
+            }
         }
     }
+
+    // Escapes like `\x{e9}` are reported separately since the pattern itself is plain ASCII.
+    fn non_ascii_literal(literal: &Literal) -> Error {
+        match literal.kind {
+            LiteralKind::HexFixed(_) | LiteralKind::HexBrace(_) => {
+                Self::unsupported("non-ASCII escape", &literal.span)
+            }
+            _ => Self::unsupported("non-ASCII character", &literal.span),
+        }
+    }
+
+    fn unsupported(construct: &'static str, span: &Span) -> Error {
+        UnsupportedRegexConstruct(Box::new(UnsupportedRegexConstructInfo {
+            construct,
+            offset: span.start.offset,
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,220 @@
+use crate::error_handling::Result;
+use crate::parser::schema_parser::parser::{SchemaConfig, TimestampSchema, VarSchema};
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Commonly used timestamp formats, each mapped to a tested timestamp regex
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimestampFormat {
+    // E.g. 2015-01-31T15:50:45, 2015-01-31T15:50:45.392, or 2015-01-31T15:50:45,392
+    Iso8601,
+    // E.g. 2015-01-31T15:50:45Z or 2015-01-31 15:50:45.392+08:00
+    Rfc3339,
+    // E.g. Jan 31 15:50:45 or Jan  1 15:50:45
+    Syslog,
+    // E.g. [31/Jan/2015:15:50:45 +0800]
+    Apache,
+}
+
+impl TimestampFormat {
+    pub fn get_regex(&self) -> &'static str {
+        match self {
+            TimestampFormat::Iso8601 => r"\d{4}\-\d{2}\-\d{2}T\d{2}:\d{2}:\d{2}((\.|,)\d{1,9})?",
+            TimestampFormat::Rfc3339 => {
+                r"\d{4}\-\d{2}\-\d{2}(T|t| )\d{2}:\d{2}:\d{2}(\.\d{1,9})?(Z|z|((\+|\-)\d{2}:\d{2}))"
+            }
+            TimestampFormat::Syslog => {
+                r"(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec) ( |\d)\d \d{2}:\d{2}:\d{2}"
+            }
+            TimestampFormat::Apache => {
+                r"\[\d{2}/(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)/\d{4}:\d{2}:\d{2}:\d{2} (\+|\-)\d{4}\]"
+            }
+        }
+    }
+}
+
+// Builds a `SchemaConfig` programmatically, as an alternative to a YAML schema file. All the
+// regexes are validated by `build`.
+pub struct SchemaConfigBuilder {
+    timestamps: Vec<(String, Option<String>)>,
+    variables: Vec<(String, String)>,
+    delimiters: String,
+}
+
+impl Default for SchemaConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchemaConfigBuilder {
+    pub fn new() -> Self {
+        Self {
+            timestamps: Vec::new(),
+            variables: Vec::new(),
+            delimiters: String::new(),
+        }
+    }
+
+    pub fn add_timestamp(mut self, regex: &str) -> Self {
+        self.timestamps.push((regex.to_string(), None));
+        self
+    }
+
+    // Same as `add_timestamp`, with the strftime-style format of the matched timestamps (see
+    // `TimestampSchema::set_format`)
+    pub fn add_timestamp_with_format(mut self, regex: &str, format: &str) -> Self {
+        self.timestamps
+            .push((regex.to_string(), Some(format.to_string())));
+        self
+    }
+
+    pub fn add_timestamp_format(self, format: TimestampFormat) -> Self {
+        self.add_timestamp(format.get_regex())
+    }
+
+    pub fn add_variable(mut self, name: &str, regex: &str) -> Self {
+        self.variables.push((name.to_string(), regex.to_string()));
+        self
+    }
+
+    // `\n` is always a delimiter, even if not given. The string supports the same backslash escapes
+    // as the `delimiters` of a YAML schema (e.g. `\\x1f`).
+    pub fn set_delimiters(mut self, delimiters: &str) -> Self {
+        self.delimiters = delimiters.to_string();
+        self
+    }
+
+    pub fn build(self) -> Result<Rc<SchemaConfig>> {
+        Ok(Rc::new(self.build_schema_config()?))
+    }
+
+    // Same as `build`, for a schema shared across threads
+    pub fn build_arc(self) -> Result<Arc<SchemaConfig>> {
+        Ok(Arc::new(self.build_schema_config()?))
+    }
+
+    fn build_schema_config(self) -> Result<SchemaConfig> {
+        let mut ts_schemas: Vec<TimestampSchema> = Vec::new();
+        for (index, (regex, format)) in self.timestamps.into_iter().enumerate() {
+            let mut ts_schema = SchemaConfig::load_ts_schema(index, regex.as_str())?;
+            ts_schema.set_format(format);
+            ts_schemas.push(ts_schema);
+        }
+
+        let mut var_schemas: Vec<VarSchema> = Vec::new();
+        for (name, regex) in self.variables {
+            let var_schema = VarSchema::new(name.clone(), regex)
+                .map_err(|e| SchemaConfig::wrap_var_regex_error(&name, e))?;
+            var_schemas.push(var_schema);
+        }
+
+        let delimiters = SchemaConfig::unescape_delimiter_str(self.delimiters.as_str())?;
+        SchemaConfig::new(ts_schemas, var_schemas, delimiters.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::{Lexer, StringStream, TokenType};
+
+    // Checks whether the format matches exactly the given text, when at the start of a log line
+    fn is_timestamp_matched(format: TimestampFormat, timestamp: &str) -> Result<bool> {
+        let schema_config = SchemaConfigBuilder::new()
+            .add_timestamp_format(format)
+            .build()?;
+        let mut lexer = Lexer::new(schema_config)?;
+        lexer.set_input_stream(Box::new(StringStream::new(
+            format!("{}\n", timestamp).as_str(),
+        )));
+        let token = lexer.get_next_token()?.unwrap();
+        Ok(matches!(token.get_token_type(), TokenType::Timestamp(0))
+            && token.get_buf_as_string() == timestamp)
+    }
+
+    fn assert_timestamp_format_matches(
+        format: TimestampFormat,
+        matched: &[&str],
+        unmatched: &[&str],
+    ) -> Result<()> {
+        for timestamp in matched {
+            assert!(is_timestamp_matched(format, timestamp)?, "{}", timestamp);
+        }
+        for timestamp in unmatched {
+            assert!(
+                false == is_timestamp_matched(format, timestamp)?,
+                "{}",
+                timestamp
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_timestamp_formats() -> Result<()> {
+        assert_timestamp_format_matches(
+            TimestampFormat::Iso8601,
+            &[
+                "2015-01-31T15:50:45",
+                "2015-01-31T15:50:45.39",
+                "2015-01-31T15:50:45,392",
+                "2015-01-31T15:50:45.392000000",
+            ],
+            &[
+                "2015-01-31 15:50:45",
+                "2015-01-31T15:50",
+                "2015-01-31T15:50:45.",
+            ],
+        )?;
+        assert_timestamp_format_matches(
+            TimestampFormat::Rfc3339,
+            &[
+                "2015-01-31T15:50:45Z",
+                "2015-01-31t15:50:45.392z",
+                "2015-01-31 15:50:45.392+08:00",
+                "2015-01-31T15:50:45-05:30",
+            ],
+            &["2015-01-31T15:50:45", "2015-01-31T15:50:45+0800"],
+        )?;
+        assert_timestamp_format_matches(
+            TimestampFormat::Syslog,
+            &["Jan 31 15:50:45", "Feb  1 00:00:00", "Dec 01 23:59:59"],
+            &["Foo 31 15:50:45", "Jan 31 15:50", "jan 31 15:50:45"],
+        )?;
+        assert_timestamp_format_matches(
+            TimestampFormat::Apache,
+            &[
+                "[31/Jan/2015:15:50:45 +0800]",
+                "[01/Dec/1999:00:00:00 -0700]",
+            ],
+            &["31/Jan/2015:15:50:45 +0800", "[31/Jan/2015:15:50:45]"],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder() -> Result<()> {
+        let schema_config = SchemaConfigBuilder::new()
+            .add_timestamp_format(TimestampFormat::Iso8601)
+            .add_timestamp_format(TimestampFormat::Syslog)
+            .add_timestamp(r"\d{10}")
+            .add_variable("int", r"\d+")
+            .add_variable("hex", r"0x[0-9a-f]+")
+            .set_delimiters(r" :\x1f")
+            .build()?;
+
+        assert_eq!(schema_config.get_ts_schemas().len(), 3);
+        assert_eq!(
+            schema_config.get_ts_schemas()[1].get_regex(),
+            TimestampFormat::Syslog.get_regex()
+        );
+        assert_eq!(schema_config.get_var_id_by_name("hex"), Some(1));
+        assert!(schema_config.has_delimiter(b':'));
+        assert!(schema_config.has_delimiter(b'\x1f'));
+        assert!(schema_config.has_delimiter(b'\n'));
+        assert!(false == schema_config.has_delimiter(b','));
+        assert!(false == schema_config.has_delimiter(b'\\'));
+        Ok(())
+    }
+}
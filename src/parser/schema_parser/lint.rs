@@ -0,0 +1,230 @@
+use crate::dfa::dfa::DFA;
+use crate::error_handling::Result;
+use crate::nfa::nfa::NFA;
+use crate::parser::schema_parser::parser::{SchemaConfig, VarSchema};
+use std::fmt::{Display, Formatter};
+
+// A problem of a schema that doesn't prevent it from being used, but likely makes it behave
+// differently than intended. See `SchemaConfig::lint` and `SchemaConfig::lint_str`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LintWarning {
+    // The regex of the variable isn't supported by the regex engine (e.g. `\D` or `a+?`), with the
+    // reason. Only reported by `SchemaConfig::lint_str`, as such a schema can't be loaded.
+    UnsupportedRegex { name: String, reason: String },
+    // The regex of the variable matches the empty string. Only reported by
+    // `SchemaConfig::lint_str`, as such a schema can't be loaded.
+    EmptyMatchingVariable { name: String },
+    // The regex of the variable can match the given delimiters of the default delimiter set, which
+    // it doesn't declare through `allow_delimiters`
+    VariableMatchesDelimiters { name: String, delimiters: String },
+    // Some strings are matched by both variables, and are then always extracted as the first
+    // (higher-priority) one
+    OverlappingVariables { first: String, second: String },
+    // The timestamp schemas of the given indices match overlapping strings, e.g. one matches a
+    // prefix of a match of the other, so only the longest-match rule tells them apart
+    OverlappingTimestamps { first: usize, second: usize },
+    // The regex of the timestamp schema of the given index can't be compiled, with the reason. The
+    // schema is left out of the other timestamp checks.
+    UnsupportedTimestampRegex { index: usize, reason: String },
+}
+
+impl Display for LintWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LintWarning::UnsupportedRegex { name, reason } => {
+                write!(
+                    f,
+                    "variable \"{}\" has an unsupported regex: {}",
+                    name, reason
+                )
+            }
+            LintWarning::EmptyMatchingVariable { name } => {
+                write!(f, "variable \"{}\" matches the empty string", name)
+            }
+            LintWarning::VariableMatchesDelimiters { name, delimiters } => write!(
+                f,
+                "variable \"{}\" can match the undeclared delimiters {:?}: declare them in the \
+                variable (`allow_delimiters`) or remove them from the delimiters",
+                name, delimiters
+            ),
+            LintWarning::OverlappingVariables { first, second } => write!(
+                f,
+                "variables \"{}\" and \"{}\" match some same strings, always extracted as \"{}\"",
+                first, second, first
+            ),
+            LintWarning::OverlappingTimestamps { first, second } => write!(
+                f,
+                "timestamp schemas #{} and #{} match overlapping strings",
+                first, second
+            ),
+            LintWarning::UnsupportedTimestampRegex { index, reason } => write!(
+                f,
+                "timestamp schema #{} has an unsupported regex: {}",
+                index, reason
+            ),
+        }
+    }
+}
+
+impl SchemaConfig {
+    // Analyzes the loaded schema without modifying it. A variable or timestamp schema whose regex
+    // can't be compiled is reported as `UnsupportedRegex` or `UnsupportedTimestampRegex`, and the
+    // analysis goes on without it.
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut lint_warnings = Vec::new();
+        let mut ts_nfas = Vec::new();
+        let mut ts_indices = Vec::new();
+        for (index, ts_schema) in self.get_ts_schemas().iter().enumerate() {
+            let mut nfa = NFA::new();
+            match nfa.add_ast_to_nfa(ts_schema.get_ast(), nfa.get_start(), nfa.get_accept()) {
+                Ok(()) => {
+                    ts_nfas.push(nfa);
+                    ts_indices.push(index);
+                }
+                Err(e) => {
+                    let reason = e.to_string();
+                    lint_warnings.push(LintWarning::UnsupportedTimestampRegex { index, reason });
+                }
+            }
+        }
+        for (first, second) in DFA::from_multiple_nfas(ts_nfas).get_overlapping_nfa_pairs() {
+            lint_warnings.push(LintWarning::OverlappingTimestamps {
+                first: ts_indices[first],
+                second: ts_indices[second],
+            });
+        }
+
+        let mut var_nfas = Vec::new();
+        let mut var_ids = Vec::new();
+        for (var_id, var_schema) in self.get_var_schemas().iter().enumerate() {
+            let name = var_schema.get_name().trim().to_string();
+            let (nfa, delimiters) = match Self::compile_var_nfa(var_schema)
+                .and_then(|nfa| Ok((nfa, self.get_matched_delimiters(var_schema)?)))
+            {
+                Ok(compiled) => compiled,
+                Err(e) => {
+                    let reason = e.to_string();
+                    lint_warnings.push(LintWarning::UnsupportedRegex { name, reason });
+                    continue;
+                }
+            };
+            if false == delimiters.is_empty() {
+                lint_warnings.push(LintWarning::VariableMatchesDelimiters { name, delimiters });
+            }
+            var_nfas.push(nfa);
+            var_ids.push(var_id);
+        }
+        let var_schemas = self.get_var_schemas();
+        for (first, second) in DFA::from_multiple_nfas(var_nfas).get_co_accepted_nfa_pairs() {
+            lint_warnings.push(LintWarning::OverlappingVariables {
+                first: var_schemas[var_ids[first]].get_name().trim().to_string(),
+                second: var_schemas[var_ids[second]].get_name().trim().to_string(),
+            });
+        }
+        lint_warnings
+    }
+
+    // Compiles the regex of the variable as is, i.e. without removing the delimiters like the
+    // lexer does
+    pub(crate) fn compile_var_nfa(var_schema: &VarSchema) -> Result<NFA> {
+        let mut nfa = NFA::new();
+        nfa.set_case_insensitive(var_schema.is_case_insensitive());
+        nfa.add_ast_to_nfa(var_schema.get_ast(), nfa.get_start(), nfa.get_accept())?;
+        Ok(nfa)
+    }
+
+    // Returns the delimiters of the default delimiter set that the regex of the variable can match,
+    // and that the variable doesn't allow
+    pub(crate) fn get_matched_delimiters(&self, var_schema: &VarSchema) -> Result<String> {
+        let nfa = Self::compile_var_nfa(var_schema)?;
+        let symbols = DFA::from_multiple_nfas(vec![nfa]).get_transition_symbols();
+        Ok((0..128u8)
+            .filter(|c| 0 != symbols & (1 << c))
+            .filter(|c| self.has_delimiter(*c) && false == var_schema.is_delimiter_allowed(*c))
+            .map(|c| c as char)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint() -> Result<()> {
+        let schema = r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2},\d{3}'
+delimiters: " :"
+variables:
+  int: '\d+'
+  port: '\d{2,5}'
+  time: '\d{2}:\d{2}'
+  hex: '0x(\d|[a-f])+'
+"#;
+        let lint_warnings = SchemaConfig::parse_from_str(schema)?.lint();
+        assert_eq!(
+            lint_warnings,
+            vec![
+                LintWarning::OverlappingTimestamps {
+                    first: 0,
+                    second: 1
+                },
+                LintWarning::VariableMatchesDelimiters {
+                    name: "time".to_string(),
+                    delimiters: ":".to_string(),
+                },
+                LintWarning::OverlappingVariables {
+                    first: "int".to_string(),
+                    second: "port".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            lint_warnings[2].to_string(),
+            "variables \"int\" and \"port\" match some same strings, always extracted as \"int\""
+        );
+        assert_eq!(SchemaConfig::lint_str(schema)?, lint_warnings);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint_str() -> Result<()> {
+        let schema = r#"
+timestamp: []
+delimiters: " "
+variables:
+  maybe_int: '\d*'
+  not_digits: '\D+'
+  lazy: 'a+?'
+  int: '\-{0,1}\d+'
+  negative_int: '\-\d+'
+"#;
+        assert!(SchemaConfig::parse_from_str(schema).is_err());
+        let lint_warnings = SchemaConfig::lint_str(schema)?;
+        assert_eq!(
+            lint_warnings[0],
+            LintWarning::EmptyMatchingVariable {
+                name: "maybe_int".to_string()
+            }
+        );
+        for (lint_warning, expected_name) in lint_warnings[1..3].iter().zip(["not_digits", "lazy"])
+        {
+            match lint_warning {
+                LintWarning::UnsupportedRegex { name, .. } => assert_eq!(name, expected_name),
+                _ => panic!("Unexpected lint warning: {:?}", lint_warning),
+            }
+        }
+        assert_eq!(
+            lint_warnings[3..],
+            [LintWarning::OverlappingVariables {
+                first: "int".to_string(),
+                second: "negative_int".to_string(),
+            }]
+        );
+
+        assert!(SchemaConfig::lint_str("timestamp: [").is_err());
+        Ok(())
+    }
+}
@@ -1 +1,3 @@
+pub mod builder;
+pub mod lint;
 pub mod parser;
@@ -1,24 +1,43 @@
 use crate::error_handling::Error::{
-    IOError, InvalidSchema, MissingSchemaKey, NoneASCIICharacters, YamlParsingError,
+    DuplicateVariableName, EmptyMatchingVariable, InvalidSchema, MissingSchemaKey,
+    NegationNotSupported, NonGreedyRepetitionNotSupported, NoneASCIICharacters, RegexParsingError,
+    TimestampRegexError, UnsupportedAstBracketedKind, UnsupportedAstNodeType,
+    UnsupportedClassSetType, UnsupportedGroupKindType, UnsupportedRegexConstruct,
+    VariableByteViolation, VariableRegexError,
 };
-use crate::error_handling::Result;
+use crate::error_handling::{Error, Result, TimestampRegexErrorInfo, VariableRegexErrorInfo};
+use crate::nfa::nfa::NFA;
 use crate::parser::regex_parser::parser::RegexParser;
+use crate::parser::schema_parser::builder::SchemaConfigBuilder;
+use crate::parser::schema_parser::lint::LintWarning;
 use indexmap::IndexMap;
-use regex_syntax::ast::Ast;
+use regex_syntax::ast::{AssertionKind, Ast};
+use serde::de::{IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
 use serde_yaml::Value;
-use std::io::Read;
+use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct TimestampSchema {
     regex: String,
     ast: Ast,
+    name: Option<String>,
+    format: Option<String>,
 }
 
 impl TimestampSchema {
     pub fn new(regex: String) -> Result<TimestampSchema> {
         let mut regex_parser = RegexParser::new();
         let ast = regex_parser.parse_into_ast(regex.as_str())?;
-        Ok(Self { regex, ast })
+        RegexParser::check_supported(&ast)?;
+        Ok(Self {
+            regex,
+            ast,
+            name: None,
+            format: None,
+        })
     }
 
     pub fn get_regex(&self) -> &str {
@@ -28,19 +47,271 @@ impl TimestampSchema {
     pub fn get_ast(&self) -> &Ast {
         &self.ast
     }
+
+    // A human-readable label of the timestamp format, e.g. `iso8601`, to tell which schema
+    // matched a timestamp token (see `Token::timestamp_id`)
+    pub fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    // Associates a strftime-style format (e.g. `%Y-%m-%dT%H:%M:%S%.f`) with the timestamps matched
+    // by this schema, used by `LogEvent::timestamp_datetime`. `None` (the default) lets the format
+    // be inferred from common ones.
+    pub fn set_format(&mut self, format: Option<String>) {
+        self.format = format;
+    }
+
+    pub fn get_format(&self) -> Option<&str> {
+        self.format.as_deref()
+    }
 }
 
+#[derive(Clone)]
 pub struct VarSchema {
     pub name: String,
     pub regex: String,
     pub ast: Ast,
+    allowed_delimiters: Option<[bool; 128]>,
+    case_insensitive: bool,
+    multiline: bool,
+    switch_delimiters: Option<String>,
+    allowed_bytes: Option<[bool; 128]>,
+    strip_enclosing: Vec<(u8, u8)>,
+    start_anchored: bool,
+    end_anchored: bool,
 }
 
 impl VarSchema {
     pub fn new(name: String, regex: String) -> Result<VarSchema> {
         let mut regex_parser = RegexParser::new();
         let ast = regex_parser.parse_into_ast(regex.as_str())?;
-        Ok(Self { name, regex, ast })
+        RegexParser::check_supported(&ast)?;
+        let (start_anchored, end_anchored) = Self::get_anchors(&ast)?;
+        Ok(Self {
+            name,
+            regex,
+            ast,
+            allowed_delimiters: None,
+            case_insensitive: false,
+            multiline: false,
+            switch_delimiters: None,
+            allowed_bytes: None,
+            strip_enclosing: Vec::new(),
+            start_anchored,
+            end_anchored,
+        })
+    }
+
+    // Anchors are only supported as the first (`^`) and the last (`$`) item of the pattern. They
+    // aren't matched by the DFA; instead, the lexer checks them against the match boundaries.
+    fn get_anchors(ast: &Ast) -> Result<(bool, bool)> {
+        let items: &[Ast] = match ast {
+            Ast::Concat(concat) => &concat.asts,
+            _ => std::slice::from_ref(ast),
+        };
+        let mut start_anchored = false;
+        let mut end_anchored = false;
+        for (idx, item) in items.iter().enumerate() {
+            match item {
+                Ast::Assertion(assertion) => match assertion.kind {
+                    AssertionKind::StartLine | AssertionKind::StartText if 0 == idx => {
+                        start_anchored = true
+                    }
+                    AssertionKind::EndLine | AssertionKind::EndText if items.len() == idx + 1 => {
+                        end_anchored = true
+                    }
+                    _ => return Err(Self::unsupported_anchor_err()),
+                },
+                _ if Self::has_assertion(item) => return Err(Self::unsupported_anchor_err()),
+                _ => {}
+            }
+        }
+        Ok((start_anchored, end_anchored))
+    }
+
+    fn has_assertion(ast: &Ast) -> bool {
+        match ast {
+            Ast::Assertion(_) => true,
+            Ast::Repetition(repetition) => Self::has_assertion(&repetition.ast),
+            Ast::Group(group) => Self::has_assertion(&group.ast),
+            Ast::Concat(concat) => concat.asts.iter().any(Self::has_assertion),
+            Ast::Alternation(alternation) => alternation.asts.iter().any(Self::has_assertion),
+            _ => false,
+        }
+    }
+
+    fn unsupported_anchor_err() -> Error {
+        UnsupportedAstNodeType("Anchors are only supported at the start or the end of a pattern")
+    }
+
+    // Whether a match must start at the beginning of a line (`^`)
+    pub fn is_start_anchored(&self) -> bool {
+        self.start_anchored
+    }
+
+    // Whether a match must end at the end of a line (`$`)
+    pub fn is_end_anchored(&self) -> bool {
+        self.end_anchored
+    }
+
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_insensitive = case_insensitive;
+    }
+
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    // Whether the regex matches the empty string, i.e. the NFA accept state is reachable from the
+    // start state through epsilon transitions only
+    pub fn matches_empty(&self) -> Result<bool> {
+        let mut nfa = NFA::new();
+        nfa.add_ast_to_nfa(&self.ast, nfa.get_start(), nfa.get_accept())?;
+        Ok(nfa
+            .epsilon_closure(&[nfa.get_start()])
+            .contains(&nfa.get_accept()))
+    }
+
+    // Lets the variable match `\n` (and `\r` for CRLF line endings), so a single variable can
+    // capture a block spanning multiple lines (e.g. a stack trace). `\n` can't be allowed through
+    // `set_allowed_delimiters`.
+    pub fn set_multiline(&mut self, multiline: bool) {
+        self.multiline = multiline;
+    }
+
+    pub fn is_multiline(&self) -> bool {
+        self.multiline
+    }
+
+    // Switches the lexer to the given named delimiter set after this variable is matched, until the
+    // end of the line.
+    pub fn set_switch_delimiters(&mut self, delimiter_set_name: Option<String>) {
+        self.switch_delimiters = delimiter_set_name;
+    }
+
+    pub fn get_switch_delimiters(&self) -> Option<&str> {
+        self.switch_delimiters.as_deref()
+    }
+
+    // Declares the delimiters that can appear inside a match of this variable. By default, a
+    // variable pattern is matched regardless of the delimiters; once the allowed delimiters are
+    // set, the variable can't match any other delimiter.
+    pub fn set_allowed_delimiters(&mut self, delimiters: &str) -> Result<()> {
+        let mut allowed_delimiters = [false; 128];
+        for c in delimiters.chars() {
+            if false == c.is_ascii() {
+                return Err(NoneASCIICharacters);
+            }
+            allowed_delimiters[c as usize] = true;
+        }
+        self.allowed_delimiters = Some(allowed_delimiters);
+        Ok(())
+    }
+
+    pub fn has_allowed_delimiters(&self) -> bool {
+        self.allowed_delimiters.is_some()
+    }
+
+    pub fn is_delimiter_allowed(&self, delimiter: u8) -> bool {
+        if 128 <= delimiter {
+            return false;
+        }
+        if b'\n' == delimiter {
+            return self.multiline;
+        }
+        if b'\r' == delimiter && self.multiline {
+            return true;
+        }
+        match &self.allowed_delimiters {
+            Some(allowed_delimiters) => allowed_delimiters[delimiter as usize],
+            None => false,
+        }
+    }
+
+    // Restricts the bytes a match of this variable may contain. Unlike the regex, the allowlist is
+    // checked after the match is accepted, to catch patterns that are more permissive than
+    // intended.
+    pub fn set_allowed_bytes(&mut self, bytes: &str) -> Result<()> {
+        let mut allowed_bytes = [false; 128];
+        for c in bytes.chars() {
+            if false == c.is_ascii() {
+                return Err(NoneASCIICharacters);
+            }
+            allowed_bytes[c as usize] = true;
+        }
+        self.allowed_bytes = Some(allowed_bytes);
+        Ok(())
+    }
+
+    // Fails with `VariableByteViolation` on the first byte of the match outside of the allowlist
+    pub fn check_allowed_bytes(&self, buf: &[u8]) -> Result<()> {
+        let Some(allowed_bytes) = &self.allowed_bytes else {
+            return Ok(());
+        };
+        match buf
+            .iter()
+            .find(|&&byte| 128 <= byte || false == allowed_bytes[byte as usize])
+        {
+            Some(&byte) => Err(VariableByteViolation {
+                name: self.name.clone(),
+                byte,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // Sets the pairs of enclosing characters, such as `()` or `""`, to strip from a match of this
+    // variable: the enclosing pair is emitted as static text around the variable token. Each pair
+    // is given as a string of its opening and closing ASCII characters.
+    pub fn set_strip_enclosing(&mut self, pairs: &[&str]) -> Result<()> {
+        let mut strip_enclosing = Vec::new();
+        for pair in pairs {
+            if false == pair.is_ascii() {
+                return Err(NoneASCIICharacters);
+            }
+            match pair.as_bytes() {
+                [open, close] => strip_enclosing.push((*open, *close)),
+                _ => return Err(InvalidSchema),
+            }
+        }
+        self.strip_enclosing = strip_enclosing;
+        Ok(())
+    }
+
+    // Returns the range of the given match to emit as the variable: the content within the
+    // enclosing pair if the match is enclosed by a balanced pair to strip, or the whole match
+    // otherwise. An enclosing pair is never stripped if it would leave the variable empty.
+    pub fn get_stripped_range(&self, buf: &[u8]) -> (usize, usize) {
+        let is_enclosed_by = |(open, close): &(u8, u8)| -> bool {
+            if buf.len() < 3 || buf[0] != *open || buf[buf.len() - 1] != *close {
+                return false;
+            }
+            let inner = &buf[1..buf.len() - 1];
+            if open == close {
+                return false == inner.contains(open);
+            }
+            // The first opening character must be closed by the last character only
+            let mut depth = 1usize;
+            for c in inner {
+                if c == open {
+                    depth += 1;
+                } else if c == close {
+                    depth -= 1;
+                    if 0 == depth {
+                        return false;
+                    }
+                }
+            }
+            1 == depth
+        };
+        match self.strip_enclosing.iter().any(is_enclosed_by) {
+            true => (1, buf.len() - 1),
+            false => (0, buf.len()),
+        }
     }
 
     pub fn get_name(&self) -> &str {
@@ -56,26 +327,308 @@ impl VarSchema {
     }
 }
 
+// The schema data is shared by the clones of a schema config, e.g. so that `Lexer::new` moves it
+// from an `Rc` into an `Arc` without copying it. Modifying a clone copies the data first.
+#[derive(Clone)]
 pub struct SchemaConfig {
+    data: Arc<SchemaConfigData>,
+}
+
+#[derive(Clone)]
+struct SchemaConfigData {
     ts_schemas: Vec<TimestampSchema>,
     var_schemas: Vec<VarSchema>,
-    delimiters: [bool; 128],
+    var_name_to_id: HashMap<String, usize>,
+    delimiter_sets: Vec<[bool; 128]>, // the default set is always at index 0
+    delimiter_set_name_to_id: HashMap<String, usize>,
+    ts_switch_delimiters: Option<usize>,
 }
 
 impl SchemaConfig {
     pub fn get_ts_schemas(&self) -> &Vec<TimestampSchema> {
-        &self.ts_schemas
+        &self.data.ts_schemas
     }
 
     pub fn get_var_schemas(&self) -> &Vec<VarSchema> {
-        &self.var_schemas
+        &self.data.var_schemas
     }
 
+    pub fn get_var_id_by_name(&self, name: &str) -> Option<usize> {
+        self.data.var_name_to_id.get(name.trim()).copied()
+    }
+
+    pub fn get_var_name(&self, var_id: usize) -> Option<&str> {
+        self.data
+            .var_schemas
+            .get(var_id)
+            .map(|schema| schema.get_name())
+    }
+
+    // The regex of the variable schema as written in the schema, e.g. to debug why a value has
+    // been matched
+    pub fn get_var_regex(&self, var_id: usize) -> Option<&str> {
+        self.data
+            .var_schemas
+            .get(var_id)
+            .map(|schema| schema.get_regex())
+    }
+
+    // `None` if there's no such timestamp schema, or if it has no name
+    pub fn get_ts_name(&self, ts_id: usize) -> Option<&str> {
+        self.data
+            .ts_schemas
+            .get(ts_id)
+            .and_then(|schema| schema.get_name())
+    }
+
+    // Checks the default delimiter set
     pub fn has_delimiter(&self, delimiter: u8) -> bool {
+        self.has_delimiter_in_set(Self::DEFAULT_DELIMITER_SET_ID, delimiter)
+    }
+
+    pub fn has_delimiter_in_set(&self, delimiter_set_id: usize, delimiter: u8) -> bool {
         if 128 <= delimiter {
             return false;
         }
-        self.delimiters[delimiter as usize]
+        self.data.delimiter_sets[delimiter_set_id][delimiter as usize]
+    }
+
+    pub fn get_num_delimiter_sets(&self) -> usize {
+        self.data.delimiter_sets.len()
+    }
+
+    pub fn get_delimiter_set_id(&self, name: &str) -> Option<usize> {
+        self.data.delimiter_set_name_to_id.get(name).copied()
+    }
+
+    // The delimiter set the lexer switches to after a timestamp, if any
+    pub fn get_ts_switch_delimiters(&self) -> Option<usize> {
+        self.data.ts_switch_delimiters
+    }
+
+    // Appends the timestamp and variable schemas of the given schema config (e.g. a team-specific
+    // overlay) after the ones of this schema config, which keep their IDs and thus their
+    // priority. The default delimiter sets are unioned, and so are the named delimiter sets of the
+    // same name. The timestamp switch delimiters of this schema config, if any, win. Fails with
+    // `DuplicateVariableName`, without modifying this schema config, if both have a variable of
+    // the same name.
+    pub fn merge(&mut self, other: &SchemaConfig) -> Result<()> {
+        let other = other.data.as_ref();
+        if let Some(var_schema) = other.var_schemas.iter().find(|var_schema| {
+            self.data
+                .var_name_to_id
+                .contains_key(var_schema.get_name().trim())
+        }) {
+            return Err(DuplicateVariableName(
+                var_schema.get_name().trim().to_string(),
+            ));
+        }
+
+        let data = Arc::make_mut(&mut self.data);
+        data.ts_schemas.extend(other.ts_schemas.iter().cloned());
+        for var_schema in &other.var_schemas {
+            data.var_name_to_id.insert(
+                var_schema.get_name().trim().to_string(),
+                data.var_schemas.len(),
+            );
+            data.var_schemas.push(var_schema.clone());
+        }
+
+        let mut other_delimiter_set_ids: Vec<(&String, &usize)> =
+            other.delimiter_set_name_to_id.iter().collect();
+        other_delimiter_set_ids.sort_by_key(|(_, id)| **id);
+        for (name, other_id) in other_delimiter_set_ids {
+            let id = match data.delimiter_set_name_to_id.get(name) {
+                Some(id) => *id,
+                None => {
+                    data.delimiter_set_name_to_id
+                        .insert(name.clone(), data.delimiter_sets.len());
+                    data.delimiter_sets.push([false; 128]);
+                    data.delimiter_sets.len() - 1
+                }
+            };
+            for (is_set, is_other_set) in data.delimiter_sets[id]
+                .iter_mut()
+                .zip(other.delimiter_sets[*other_id].iter())
+            {
+                *is_set |= *is_other_set;
+            }
+        }
+        if data.ts_switch_delimiters.is_none() {
+            data.ts_switch_delimiters = other.ts_switch_delimiters.and_then(|other_id| {
+                other
+                    .delimiter_set_name_to_id
+                    .iter()
+                    .find(|(_, id)| **id == other_id)
+                    .and_then(|(name, _)| data.delimiter_set_name_to_id.get(name).copied())
+            });
+        }
+        Ok(())
+    }
+
+    // Returns a 64-bit FNV-1a hash of everything that affects lexing: the ordered timestamp
+    // regexes, the ordered variables (names, regexes, and options), and the delimiter set. It
+    // doesn't depend on the YAML formatting or the delimiter order, and it's stable across runs
+    // and platforms, so it can be persisted as a cache key.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = FingerprintHasher::new();
+        hasher.write_usize(self.data.ts_schemas.len());
+        for ts_schema in &self.data.ts_schemas {
+            hasher.write_str(ts_schema.get_regex());
+        }
+        hasher.write_usize(self.data.var_schemas.len());
+        for var_schema in &self.data.var_schemas {
+            hasher.write_str(var_schema.get_name().trim());
+            hasher.write_str(var_schema.get_regex());
+            match &var_schema.allowed_delimiters {
+                Some(allowed_delimiters) => {
+                    hasher.write_bool(true);
+                    hasher.write_bool_set(allowed_delimiters);
+                }
+                None => hasher.write_bool(false),
+            }
+            hasher.write_bool(var_schema.case_insensitive);
+            hasher.write_bool(var_schema.multiline);
+            match &var_schema.allowed_bytes {
+                Some(allowed_bytes) => {
+                    hasher.write_bool(true);
+                    hasher.write_bool_set(allowed_bytes);
+                }
+                None => hasher.write_bool(false),
+            }
+            hasher.write_usize(var_schema.strip_enclosing.len());
+            for (open, close) in &var_schema.strip_enclosing {
+                hasher.write_usize(*open as usize);
+                hasher.write_usize(*close as usize);
+            }
+        }
+        for var_schema in &self.data.var_schemas {
+            let switch_delimiters = var_schema
+                .get_switch_delimiters()
+                .and_then(|name| self.get_delimiter_set_id(name));
+            hasher.write_usize(switch_delimiters.map_or(0, |id| id + 1));
+        }
+        hasher.write_usize(self.data.ts_switch_delimiters.map_or(0, |id| id + 1));
+        hasher.write_usize(self.data.delimiter_sets.len());
+        for delimiter_set in &self.data.delimiter_sets {
+            hasher.write_bool_set(delimiter_set);
+        }
+        hasher.finish()
+    }
+}
+
+struct FingerprintHasher {
+    hash: u64,
+}
+
+impl FingerprintHasher {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    fn new() -> Self {
+        Self {
+            hash: Self::FNV_OFFSET_BASIS,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(Self::FNV_PRIME);
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write(&(value as u64).to_le_bytes());
+    }
+
+    fn write_bool(&mut self, value: bool) {
+        self.write(&[value as u8]);
+    }
+
+    // Strings are length-prefixed so that the concatenation of consecutive strings is unambiguous
+    fn write_str(&mut self, value: &str) {
+        self.write_usize(value.len());
+        self.write(value.as_bytes());
+    }
+
+    fn write_bool_set(&mut self, set: &[bool; 128]) {
+        let mut bits = 0u128;
+        for (idx, is_set) in set.iter().enumerate() {
+            if *is_set {
+                bits |= 1 << idx;
+            }
+        }
+        self.write(&bits.to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+// The keys of a YAML mapping in declaration order, duplicates included. Unlike `Value`, which
+// rejects duplicated mapping keys, the keys are visited one by one and the values are skipped.
+struct MappingKeys(Vec<String>);
+
+struct MappingKeysVisitor;
+
+impl<'de> Visitor<'de> for MappingKeysVisitor {
+    type Value = MappingKeys;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a mapping")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> core::result::Result<MappingKeys, A::Error> {
+        let mut keys = Vec::new();
+        while let Some((key, IgnoredAny)) = map.next_entry::<String, IgnoredAny>()? {
+            keys.push(key);
+        }
+        Ok(MappingKeys(keys))
+    }
+}
+
+impl<'de> Deserialize<'de> for MappingKeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(MappingKeysVisitor)
+    }
+}
+
+// The variable names of a YAML schema, read as the keys of its `variables` mapping
+struct SchemaVarNames(Vec<String>);
+
+struct SchemaVarNamesVisitor;
+
+impl<'de> Visitor<'de> for SchemaVarNamesVisitor {
+    type Value = SchemaVarNames;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a schema mapping")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> core::result::Result<SchemaVarNames, A::Error> {
+        let mut var_names = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if SchemaConfig::VAR_KEY == key {
+                var_names = map.next_value::<MappingKeys>()?.0;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(SchemaVarNames(var_names))
+    }
+}
+
+impl<'de> Deserialize<'de> for SchemaVarNames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(SchemaVarNamesVisitor)
     }
 }
 
@@ -83,25 +636,81 @@ impl SchemaConfig {
     const TIMESTAMP_KEY: &'static str = "timestamp";
     const VAR_KEY: &'static str = "variables";
     const DELIMITER_EKY: &'static str = "delimiters";
+    const VAR_REGEX_KEY: &'static str = "regex";
+    const TIMESTAMP_NAME_KEY: &'static str = "name";
+    const TIMESTAMP_FORMAT_KEY: &'static str = "format";
+    const VAR_ALLOWED_DELIMITERS_KEY: &'static str = "allow_delimiters";
+    const CASE_INSENSITIVE_KEY: &'static str = "case_insensitive";
+    const VAR_MULTILINE_KEY: &'static str = "multiline";
+    const VAR_ALLOWED_BYTES_KEY: &'static str = "allowed_bytes";
+    const VAR_STRIP_ENCLOSING_KEY: &'static str = "strip_enclosing";
+    const DELIMITER_SETS_KEY: &'static str = "delimiter_sets";
+    const SWITCH_DELIMITERS_KEY: &'static str = "switch_delimiters";
+    const TIMESTAMP_SWITCH_DELIMITERS_KEY: &'static str = "timestamp_switch_delimiters";
+    const DEFAULT_DELIMITER_SET_NAME: &'static str = "default";
+    pub const DEFAULT_DELIMITER_SET_ID: usize = 0;
 
     pub fn parse_from_file(yaml_file_path: &str) -> Result<Rc<SchemaConfig>> {
-        match std::fs::File::open(yaml_file_path) {
-            Ok(mut file) => {
-                let mut contents = String::new();
-                if let Err(e) = file.read_to_string(&mut contents) {
-                    return Err(IOError(e));
-                }
-                Ok(Rc::new(Self::parse_from_str(contents.as_str())?))
-            }
-            Err(e) => Err(IOError(e)),
-        }
+        let contents = std::fs::read_to_string(yaml_file_path)?;
+        Self::parse_from_str(contents.as_str())
+    }
+
+    // Same as `parse_from_file`, for a schema shared across threads (e.g. by several lexers
+    // created with `Lexer::new_arc`)
+    pub fn parse_from_file_arc(yaml_file_path: &str) -> Result<Arc<SchemaConfig>> {
+        let contents = std::fs::read_to_string(yaml_file_path)?;
+        Self::parse_from_str_arc(contents.as_str())
     }
 
-    fn parse_from_str(yaml_content: &str) -> Result<SchemaConfig> {
-        match Self::load_kv_pairs_from_yaml_content(yaml_content) {
-            Ok(kv_pairs) => Self::load_from_kv_pairs(kv_pairs),
-            Err(e) => Err(YamlParsingError(e)),
+    // Builds a schema from string slices, so that a known schema can be embedded as constants
+    // without going through YAML at runtime. Equivalent to the `SchemaConfigBuilder` calls.
+    pub fn from_static(
+        timestamps: &[&'static str],
+        variables: &[(&'static str, &'static str)],
+        delimiters: &'static str,
+    ) -> Result<Rc<SchemaConfig>> {
+        let mut builder = SchemaConfigBuilder::new().set_delimiters(delimiters);
+        for timestamp in timestamps {
+            builder = builder.add_timestamp(timestamp);
         }
+        for (name, regex) in variables {
+            builder = builder.add_variable(name, regex);
+        }
+        builder.build()
+    }
+
+    pub fn parse_from_str(yaml_content: &str) -> Result<Rc<SchemaConfig>> {
+        let kv_pairs = Self::load_kv_pairs_from_yaml_content(yaml_content)?;
+        Ok(Rc::new(Self::load_from_kv_pairs(kv_pairs, None)?))
+    }
+
+    // Same as `parse_from_str`, for a schema shared across threads
+    pub fn parse_from_str_arc(yaml_content: &str) -> Result<Arc<SchemaConfig>> {
+        let kv_pairs = Self::load_kv_pairs_from_yaml_content(yaml_content)?;
+        Ok(Arc::new(Self::load_from_kv_pairs(kv_pairs, None)?))
+    }
+
+    // Dry run of `parse_from_str` followed by `lint`: a variable with an unsupported or
+    // empty-matching regex is reported as a `LintWarning` and left out, instead of failing the
+    // whole schema. Other schema errors (e.g. an invalid YAML) still fail.
+    pub fn lint_str(yaml_content: &str) -> Result<Vec<LintWarning>> {
+        let kv_pairs = Self::load_kv_pairs_from_yaml_content(yaml_content)?;
+        let mut lint_warnings = Vec::new();
+        let schema_config = Self::load_from_kv_pairs(kv_pairs, Some(&mut lint_warnings))?;
+        lint_warnings.extend(schema_config.lint());
+        Ok(lint_warnings)
+    }
+
+    // serde_yaml rejects duplicated mapping keys before the schema is loaded. The variable names
+    // are then read again as a plain list of keys, so that a duplicated one can be reported as a
+    // schema error instead.
+    fn get_duplicate_var_name(yaml_content: &str) -> Option<String> {
+        let var_names = serde_yaml::from_str::<SchemaVarNames>(yaml_content).ok()?.0;
+        let mut seen_names: HashMap<&str, ()> = HashMap::new();
+        var_names
+            .iter()
+            .find(|name| seen_names.insert(name.as_str(), ()).is_some())
+            .cloned()
     }
 
     fn get_key_value<'a>(
@@ -111,72 +720,372 @@ impl SchemaConfig {
         kv_map.get(key).ok_or_else(|| MissingSchemaKey(key))
     }
 
-    fn load_kv_pairs_from_yaml_content(
-        yaml_content: &str,
-    ) -> serde_yaml::Result<IndexMap<String, Value>> {
-        let kv_map_result: IndexMap<String, Value> = serde_yaml::from_str(&yaml_content)?;
-        Ok(kv_map_result)
+    fn load_kv_pairs_from_yaml_content(yaml_content: &str) -> Result<IndexMap<String, Value>> {
+        serde_yaml::from_str(yaml_content).map_err(|e| {
+            match Self::get_duplicate_var_name(yaml_content) {
+                Some(name) => DuplicateVariableName(name),
+                None => e.into(),
+            }
+        })
+    }
+
+    fn get_optional_bool(map: &serde_yaml::Mapping, key: &'static str) -> Result<Option<bool>> {
+        match map.get(key) {
+            Some(Value::Bool(value)) => Ok(Some(*value)),
+            Some(_) => Err(InvalidSchema),
+            None => Ok(None),
+        }
+    }
+
+    // A variable is either defined as a regex string, or as a mapping of the regex and its
+    // options: `{regex: "...", allow_delimiters: ":/", case_insensitive: true, multiline: true}`.
+    fn load_var_schema(name: &str, value: &Value, case_insensitive: bool) -> Result<VarSchema> {
+        match value {
+            Value::String(regex) => {
+                let mut var_schema = VarSchema::new(name.to_string(), regex.clone())?;
+                var_schema.set_case_insensitive(case_insensitive);
+                Ok(var_schema)
+            }
+            Value::Mapping(map) => {
+                let regex = match map.get(Self::VAR_REGEX_KEY) {
+                    Some(Value::String(regex)) => regex,
+                    Some(_) => return Err(InvalidSchema),
+                    None => return Err(MissingSchemaKey(Self::VAR_REGEX_KEY)),
+                };
+                let mut var_schema = VarSchema::new(name.to_string(), regex.clone())?;
+                match map.get(Self::VAR_ALLOWED_DELIMITERS_KEY) {
+                    Some(Value::String(delimiters)) => {
+                        var_schema.set_allowed_delimiters(delimiters.as_str())?
+                    }
+                    Some(_) => return Err(InvalidSchema),
+                    None => {}
+                }
+                match map.get(Self::VAR_ALLOWED_BYTES_KEY) {
+                    Some(Value::String(bytes)) => var_schema.set_allowed_bytes(bytes.as_str())?,
+                    Some(_) => return Err(InvalidSchema),
+                    None => {}
+                }
+                match map.get(Self::VAR_STRIP_ENCLOSING_KEY) {
+                    Some(Value::Sequence(sequence)) => {
+                        let pairs = sequence
+                            .iter()
+                            .map(|pair| pair.as_str().ok_or(InvalidSchema))
+                            .collect::<Result<Vec<&str>>>()?;
+                        var_schema.set_strip_enclosing(&pairs)?;
+                    }
+                    Some(_) => return Err(InvalidSchema),
+                    None => {}
+                }
+                var_schema.set_case_insensitive(
+                    Self::get_optional_bool(map, Self::CASE_INSENSITIVE_KEY)?
+                        .unwrap_or(case_insensitive),
+                );
+                var_schema.set_multiline(
+                    Self::get_optional_bool(map, Self::VAR_MULTILINE_KEY)?.unwrap_or(false),
+                );
+                match map.get(Self::SWITCH_DELIMITERS_KEY) {
+                    Some(Value::String(name)) => {
+                        var_schema.set_switch_delimiters(Some(name.clone()))
+                    }
+                    Some(_) => return Err(InvalidSchema),
+                    None => {}
+                }
+                Ok(var_schema)
+            }
+            _ => Err(InvalidSchema),
+        }
+    }
+
+    // Delimiters are either given as a string of characters, or as a sequence of single characters
+    // and byte values, e.g. `[",", ":", '\t', 0]`. Characters and byte values must be ASCII (0 to
+    // 127), and a character entry may use the same escapes as a delimiter string.
+    fn load_delimiter_str(value: &Value) -> Result<String> {
+        match value {
+            Value::String(delimiter_str) => Self::unescape_delimiter_str(delimiter_str),
+            Value::Sequence(sequence) => sequence
+                .iter()
+                .map(|entry| match entry {
+                    Value::String(delimiter_str) => {
+                        let unescaped = Self::unescape_delimiter_str(delimiter_str)?;
+                        let mut chars = unescaped.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) if c.is_ascii() => Ok(c),
+                            (Some(_), None) => Err(NoneASCIICharacters),
+                            _ => Err(InvalidSchema),
+                        }
+                    }
+                    _ => match entry.as_i64() {
+                        Some(byte) if (0..128).contains(&byte) => Ok(byte as u8 as char),
+                        Some(_) => Err(NoneASCIICharacters),
+                        None => Err(InvalidSchema),
+                    },
+                })
+                .collect(),
+            _ => Err(InvalidSchema),
+        }
+    }
+
+    // Resolves the backslash escapes of a delimiter string: `\t`, `\n`, `\r`, `\\`, and `\xNN`
+    // for any ASCII byte. This allows control characters even in single-quoted YAML strings. Any
+    // other backslash is kept as is, along with the character following it.
+    pub(crate) fn unescape_delimiter_str(delimiter_str: &str) -> Result<String> {
+        let mut unescaped = String::new();
+        let mut chars = delimiter_str.chars();
+        while let Some(c) = chars.next() {
+            if '\\' != c {
+                unescaped.push(c);
+                continue;
+            }
+            let rest = chars.as_str();
+            let (escaped, escape_len) = match rest.as_bytes().first() {
+                Some(b't') => ('\t', 1),
+                Some(b'n') => ('\n', 1),
+                Some(b'r') => ('\r', 1),
+                Some(b'\\') => ('\\', 1),
+                Some(b'x') => match rest
+                    .get(1..3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) if byte.is_ascii() => (byte as char, 3),
+                    Some(_) => return Err(NoneASCIICharacters),
+                    None => ('\\', 0),
+                },
+                _ => ('\\', 0),
+            };
+            unescaped.push(escaped);
+            chars = rest[escape_len..].chars();
+        }
+        Ok(unescaped)
     }
 
-    fn load_from_kv_pairs(kv_pairs: IndexMap<String, Value>) -> Result<Self> {
+    // With `lint_warnings`, the variables with an unsupported or empty-matching regex are reported
+    // there and left out, instead of failing
+    fn load_from_kv_pairs(
+        kv_pairs: IndexMap<String, Value>,
+        mut lint_warnings: Option<&mut Vec<LintWarning>>,
+    ) -> Result<Self> {
         // Handle timestamps
         let mut ts_schemas: Vec<TimestampSchema> = Vec::new();
         let timestamps = Self::get_key_value(&kv_pairs, Self::TIMESTAMP_KEY)?;
         if let Value::Sequence(sequence) = timestamps {
-            sequence.iter().try_for_each(|val| {
-                if let Value::String(s) = val {
-                    ts_schemas.push(TimestampSchema::new(s.clone())?);
-                    Ok(())
-                } else {
-                    Err(InvalidSchema)
-                }
-            })?;
+            for val in sequence {
+                ts_schemas.push(Self::load_ts_schema_from_value(ts_schemas.len(), val)?);
+            }
         } else {
             return Err(InvalidSchema);
         }
 
         // Handle variables
+        let case_insensitive = match kv_pairs.get(Self::CASE_INSENSITIVE_KEY) {
+            Some(Value::Bool(case_insensitive)) => *case_insensitive,
+            Some(_) => return Err(InvalidSchema),
+            None => false,
+        };
         let mut var_schemas: Vec<VarSchema> = Vec::new();
         let vars = Self::get_key_value(&kv_pairs, Self::VAR_KEY)?;
         if let Value::Mapping(map) = vars {
             for (key, value) in map {
-                match (key, value) {
-                    (Value::String(name), Value::String(regex)) => {
-                        var_schemas.push(VarSchema::new(name.clone(), regex.clone())?);
+                let Value::String(name) = key else {
+                    return Err(InvalidSchema);
+                };
+                let var_schema = match Self::load_var_schema(name, value, case_insensitive) {
+                    Ok(var_schema) => var_schema,
+                    Err(e) => match lint_warnings.as_deref_mut() {
+                        Some(lint_warnings) if Self::is_unsupported_regex_err(&e) => {
+                            lint_warnings.push(LintWarning::UnsupportedRegex {
+                                name: name.trim().to_string(),
+                                reason: e.to_string(),
+                            });
+                            continue;
+                        }
+                        _ => return Err(Self::wrap_var_regex_error(name, e)),
+                    },
+                };
+                if let Some(lint_warnings) = lint_warnings.as_deref_mut() {
+                    if var_schema.matches_empty()? {
+                        lint_warnings.push(LintWarning::EmptyMatchingVariable {
+                            name: name.trim().to_string(),
+                        });
+                        continue;
                     }
-                    _ => return Err(InvalidSchema),
                 }
+                var_schemas.push(var_schema);
             }
         } else {
             return Err(InvalidSchema);
         }
 
         // Handle delimiter
-        let mut delimiters = [false; 128];
         let delimiter = Self::get_key_value(&kv_pairs, Self::DELIMITER_EKY)?;
-        if let Value::String(delimiter_str) = delimiter {
-            for c in delimiter_str.chars() {
-                if false == c.is_ascii() {
-                    return Err(NoneASCIICharacters);
+        let delimiter_str = Self::load_delimiter_str(delimiter)?;
+        let mut schema_config = Self::new(ts_schemas, var_schemas, delimiter_str.as_str())?;
+
+        // Handle named delimiter sets
+        match kv_pairs.get(Self::DELIMITER_SETS_KEY) {
+            Some(Value::Mapping(map)) => {
+                for (key, value) in map {
+                    let Value::String(name) = key else {
+                        return Err(InvalidSchema);
+                    };
+                    let delimiter_str = Self::load_delimiter_str(value)?;
+                    schema_config.add_delimiter_set(name, delimiter_str.as_str())?;
                 }
-                delimiters[c as usize] = true;
             }
-        } else {
+            Some(_) => return Err(InvalidSchema),
+            None => {}
+        }
+        let ts_switch_delimiters = match kv_pairs.get(Self::TIMESTAMP_SWITCH_DELIMITERS_KEY) {
+            Some(Value::String(name)) => Some(
+                schema_config
+                    .get_delimiter_set_id(name)
+                    .ok_or(InvalidSchema)?,
+            ),
+            Some(_) => return Err(InvalidSchema),
+            None => None,
+        };
+        Arc::make_mut(&mut schema_config.data).ts_switch_delimiters = ts_switch_delimiters;
+        for var_schema in schema_config.get_var_schemas() {
+            if let Some(name) = var_schema.get_switch_delimiters() {
+                schema_config
+                    .get_delimiter_set_id(name)
+                    .ok_or(InvalidSchema)?;
+            }
+        }
+
+        Ok(schema_config)
+    }
+
+    fn add_delimiter_set(&mut self, name: &str, delimiter_str: &str) -> Result<()> {
+        let data = Arc::make_mut(&mut self.data);
+        if data.delimiter_set_name_to_id.contains_key(name) {
             return Err(InvalidSchema);
         }
+        data.delimiter_set_name_to_id
+            .insert(name.to_string(), data.delimiter_sets.len());
+        data.delimiter_sets
+            .push(Self::parse_delimiters(delimiter_str)?);
+        Ok(())
+    }
+
+    // `\n` is always a delimiter
+    fn parse_delimiters(delimiter_str: &str) -> Result<[bool; 128]> {
+        let mut delimiters = [false; 128];
+        for c in delimiter_str.chars() {
+            if false == c.is_ascii() {
+                return Err(NoneASCIICharacters);
+            }
+            delimiters[c as usize] = true;
+        }
         delimiters['\n' as usize] = true;
+        Ok(delimiters)
+    }
+
+    pub(crate) fn new(
+        ts_schemas: Vec<TimestampSchema>,
+        var_schemas: Vec<VarSchema>,
+        delimiter_str: &str,
+    ) -> Result<Self> {
+        let mut var_name_to_id: HashMap<String, usize> = HashMap::new();
+        for (var_id, var_schema) in var_schemas.iter().enumerate() {
+            // YAML only dedupes identical keys, so names differing by surrounding whitespace
+            // are checked here.
+            let name = var_schema.get_name().trim();
+            if var_name_to_id.contains_key(name) {
+                return Err(DuplicateVariableName(name.to_string()));
+            }
+            var_name_to_id.insert(name.to_string(), var_id);
+            // A variable matching the empty string would be extracted as a zero-width token
+            if var_schema.matches_empty()? {
+                return Err(EmptyMatchingVariable(name.to_string()));
+            }
+        }
+
+        let mut delimiter_set_name_to_id: HashMap<String, usize> = HashMap::new();
+        delimiter_set_name_to_id.insert(
+            Self::DEFAULT_DELIMITER_SET_NAME.to_string(),
+            Self::DEFAULT_DELIMITER_SET_ID,
+        );
+
+        Ok(Self {
+            data: Arc::new(SchemaConfigData {
+                ts_schemas,
+                var_schemas,
+                var_name_to_id,
+                delimiter_sets: vec![Self::parse_delimiters(delimiter_str)?],
+                delimiter_set_name_to_id,
+                ts_switch_delimiters: None,
+            }),
+        })
+    }
+
+    // A timestamp is either a regex, or a mapping with a `regex`, an optional `name`, and an
+    // optional strftime-style `format`
+    fn load_ts_schema_from_value(index: usize, value: &Value) -> Result<TimestampSchema> {
+        match value {
+            Value::String(regex) => Self::load_ts_schema(index, regex),
+            Value::Mapping(map) => {
+                let regex = match map.get(Self::VAR_REGEX_KEY) {
+                    Some(Value::String(regex)) => regex,
+                    Some(_) => return Err(InvalidSchema),
+                    None => return Err(MissingSchemaKey(Self::VAR_REGEX_KEY)),
+                };
+                let mut ts_schema = Self::load_ts_schema(index, regex)?;
+                match map.get(Self::TIMESTAMP_NAME_KEY) {
+                    Some(Value::String(name)) => ts_schema.set_name(Some(name.clone())),
+                    Some(_) => return Err(InvalidSchema),
+                    None => {}
+                }
+                match map.get(Self::TIMESTAMP_FORMAT_KEY) {
+                    Some(Value::String(format)) => ts_schema.set_format(Some(format.clone())),
+                    Some(_) => return Err(InvalidSchema),
+                    None => {}
+                }
+                Ok(ts_schema)
+            }
+            _ => Err(InvalidSchema),
+        }
+    }
 
-        Ok((Self {
-            ts_schemas,
-            var_schemas,
-            delimiters,
-        }))
+    pub(crate) fn load_ts_schema(index: usize, regex: &str) -> Result<TimestampSchema> {
+        TimestampSchema::new(regex.to_string()).map_err(|e| match e {
+            RegexParsingError(source) => TimestampRegexError(Box::new(TimestampRegexErrorInfo {
+                index,
+                regex: regex.to_string(),
+                source: *source,
+            })),
+            e => e,
+        })
+    }
+
+    fn is_unsupported_regex_err(e: &Error) -> bool {
+        matches!(
+            e,
+            UnsupportedAstNodeType(_)
+                | UnsupportedRegexConstruct(_)
+                | NegationNotSupported(_)
+                | NonGreedyRepetitionNotSupported
+                | UnsupportedAstBracketedKind
+                | UnsupportedClassSetType
+                | UnsupportedGroupKindType
+        )
+    }
+
+    pub(crate) fn wrap_var_regex_error(name: &str, e: Error) -> Error {
+        match e {
+            RegexParsingError(source) => VariableRegexError(Box::new(VariableRegexErrorInfo {
+                name: name.trim().to_string(),
+                regex: source.pattern().to_string(),
+                source: *source,
+            })),
+            e => e,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error_handling::Error::UnsupportedRegexConstruct;
     use clap::builder::Str;
 
     #[test]
@@ -212,4 +1121,550 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_var_name_lookup() -> Result<()> {
+        let project_root = env!("CARGO_MANIFEST_DIR");
+        let schema_path = std::path::Path::new(project_root)
+            .join("examples")
+            .join("schema.yaml");
+        let parsed_schema = SchemaConfig::parse_from_file(schema_path.to_str().unwrap())?;
+
+        assert_eq!(parsed_schema.get_var_id_by_name("hex"), Some(2));
+        assert_eq!(parsed_schema.get_var_name(2), Some("hex"));
+        for (var_id, schema) in parsed_schema.get_var_schemas().iter().enumerate() {
+            assert_eq!(
+                parsed_schema.get_var_id_by_name(schema.get_name()),
+                Some(var_id)
+            );
+            assert_eq!(parsed_schema.get_var_name(var_id), Some(schema.get_name()));
+        }
+
+        assert_eq!(parsed_schema.get_var_id_by_name("not_a_var"), None);
+        assert_eq!(
+            parsed_schema.get_var_name(parsed_schema.get_var_schemas().len()),
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_var_names() {
+        let schema = r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " \t\n"
+variables:
+  path: '(/\w+)+'
+  int: '\d+'
+  path: '/\w+'
+"#;
+        match SchemaConfig::parse_from_str(schema) {
+            Err(DuplicateVariableName(name)) => assert_eq!(name, "path"),
+            _ => panic!("Duplicate variable name not detected"),
+        }
+
+        let schema = r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " \t\n"
+variables:
+  path: '(/\w+)+'
+  ' path ': '/\w+'
+"#;
+        match SchemaConfig::parse_from_str(schema) {
+            Err(DuplicateVariableName(name)) => assert_eq!(name, "path"),
+            _ => panic!("Duplicate variable name not detected"),
+        }
+
+        // The mapping form, and a duplicated key nested in a variable, which isn't a variable name
+        let schema = r#"
+timestamp: []
+delimiters: " \t\n"
+variables:
+  int:
+    regex: '\d+'
+    regex: '\d{2}'
+  path: '(/\w+)+'
+  int:
+    regex: '\-\d+'
+"#;
+        match SchemaConfig::parse_from_str(schema) {
+            Err(DuplicateVariableName(name)) => assert_eq!(name, "int"),
+            _ => panic!("Duplicate variable name not detected"),
+        }
+        match SchemaConfig::lint_str(schema) {
+            Err(DuplicateVariableName(name)) => assert_eq!(name, "int"),
+            _ => panic!("Duplicate variable name not detected"),
+        }
+
+        let schema = r#"
+timestamp: []
+delimiters: " \t\n"
+variables:
+  int:
+    regex: '\d+'
+    regex: '\d{2}'
+"#;
+        assert!(matches!(
+            SchemaConfig::parse_from_str(schema),
+            Err(Error::YamlParsingError(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_matching_var() -> Result<()> {
+        for regex in ["a*", "(foo)?", "^(\\d+|x?)$"] {
+            let schema = format!(
+                r#"
+timestamp:
+  - '\d{{4}}\-\d{{2}}\-\d{{2}}'
+delimiters: " "
+variables:
+  int: '\d+'
+  empty: '{}'
+"#,
+                regex
+            );
+            match SchemaConfig::parse_from_str(schema.as_str()) {
+                Err(EmptyMatchingVariable(name)) => assert_eq!(name, "empty"),
+                _ => panic!("Empty matching variable not detected: {}", regex),
+            }
+        }
+
+        let var_schema = VarSchema::new("int".to_string(), r"a+b*".to_string())?;
+        assert!(false == var_schema.matches_empty()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_delimiter_byte_values() -> Result<()> {
+        let schema = r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2}'
+delimiters: [9, 32, 0]
+variables:
+  int: '\d+'
+"#;
+        let schema_config = SchemaConfig::parse_from_str(schema)?;
+        for byte in 0u8..128 {
+            assert_eq!(
+                schema_config.has_delimiter(byte),
+                [b'\t', b' ', 0, b'\n'].contains(&byte),
+                "{}",
+                byte
+            );
+        }
+
+        for (delimiters, invalid_ascii) in [
+            ("[32, 128]", true),
+            ("[-1]", true),
+            ("['é']", true),
+            ("['ab']", false),
+            ("['']", false),
+            ("[[32]]", false),
+        ] {
+            let schema = schema.replace("[9, 32, 0]", delimiters);
+            match SchemaConfig::parse_from_str(schema.as_str()) {
+                Err(NoneASCIICharacters) => assert!(invalid_ascii, "{}", delimiters),
+                Err(InvalidSchema) => assert!(false == invalid_ascii, "{}", delimiters),
+                _ => panic!("Invalid delimiters not detected: {}", delimiters),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_delimiter_sequence() -> Result<()> {
+        let schema = r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2}'
+delimiters: DELIMITERS
+variables:
+  int: '\d+'
+"#;
+        let load_delimiters = |delimiters: &str| -> Result<Vec<bool>> {
+            let schema_config =
+                SchemaConfig::parse_from_str(schema.replace("DELIMITERS", delimiters).as_str())?;
+            Ok((0u8..128)
+                .map(|byte| schema_config.has_delimiter(byte))
+                .collect())
+        };
+        let expected = load_delimiters(r#"",:\t""#)?;
+        assert_eq!(load_delimiters(r#"[",", ":", "\t"]"#)?, expected);
+        assert_eq!(load_delimiters(r#"[',', ':', '\t']"#)?, expected);
+        assert_eq!(load_delimiters(r#"[":", 44, "\t", ","]"#)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint_unsupported_timestamp_regex() -> Result<()> {
+        // Bypasses the checks of `TimestampSchema::new`, which reject such a regex up front
+        let unsupported_ts_schema = TimestampSchema {
+            regex: r"\D+".to_string(),
+            ast: RegexParser::new().parse_into_ast(r"\D+")?,
+            name: None,
+            format: None,
+        };
+        let ts_schemas = vec![
+            TimestampSchema::new(r"\d{4}\-\d{2}".to_string())?,
+            unsupported_ts_schema,
+            TimestampSchema::new(r"\d{4}\-\d{2}(\-\d{2})?".to_string())?,
+        ];
+        let var_schemas = vec![VarSchema::new(
+            "time".to_string(),
+            r"\d{2}:\d{2}".to_string(),
+        )?];
+        let lint_warnings = SchemaConfig::new(ts_schemas, var_schemas, " :")?.lint();
+        assert_eq!(lint_warnings.len(), 3);
+        assert!(matches!(
+            lint_warnings[0],
+            LintWarning::UnsupportedTimestampRegex { index: 1, .. }
+        ));
+        // The other checks still run, with the original timestamp schema indices
+        assert_eq!(
+            lint_warnings[1..],
+            [
+                LintWarning::OverlappingTimestamps {
+                    first: 0,
+                    second: 2
+                },
+                LintWarning::VariableMatchesDelimiters {
+                    name: "time".to_string(),
+                    delimiters: ":".to_string(),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_escaped_delimiters() -> Result<()> {
+        let schema = r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2}'
+delimiters: '\t,'
+variables:
+  int: '\d+'
+"#;
+        let assert_delimiters = |schema: &str, expected: &[u8]| -> Result<()> {
+            let schema_config = SchemaConfig::parse_from_str(schema)?;
+            for byte in 0u8..128 {
+                assert_eq!(
+                    schema_config.has_delimiter(byte),
+                    expected.contains(&byte) || b'\n' == byte,
+                    "{}",
+                    byte
+                );
+            }
+            Ok(())
+        };
+        assert_delimiters(schema, b"\t,")?;
+        // YAML resolves the escapes of double-quoted strings itself
+        assert_delimiters(schema.replace("'\\t,'", "\"\\t,\"").as_str(), b"\t,")?;
+        assert_delimiters(
+            schema.replace("'\\t,'", "' \\x1f\\\\'").as_str(),
+            b" \x1f\\",
+        )?;
+
+        // Unknown or incomplete escapes are kept as is
+        assert_delimiters(schema.replace("'\\t,'", "'\\q'").as_str(), b"\\q")?;
+        assert_delimiters(schema.replace("'\\t,'", "'\\x4 '").as_str(), b"\\x4 ")?;
+        assert_delimiters(schema.replace("'\\t,'", "', \\'").as_str(), b", \\")?;
+
+        let schema = schema.replace("'\\t,'", "'\\xff'");
+        assert!(matches!(
+            SchemaConfig::parse_from_str(schema.as_str()),
+            Err(NoneASCIICharacters)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_var_allowed_delimiters() -> Result<()> {
+        let schema = r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " \t\n:/"
+variables:
+  int: '\d+'
+  path:
+    regex: '(/(\w|:)+)+'
+    allow_delimiters: ":/"
+"#;
+        let parsed_schema = SchemaConfig::parse_from_str(schema)?;
+        let int_schema = &parsed_schema.get_var_schemas()[0];
+        let path_schema = &parsed_schema.get_var_schemas()[1];
+        assert_eq!(path_schema.get_regex(), r"(/(\w|:)+)+");
+        assert!(path_schema.is_delimiter_allowed(b':'));
+        assert!(path_schema.is_delimiter_allowed(b'/'));
+        assert!(false == path_schema.is_delimiter_allowed(b' '));
+        assert!(false == int_schema.is_delimiter_allowed(b':'));
+
+        let schema = r#"
+timestamp: []
+delimiters: " \t\n:/"
+variables:
+  path:
+    allow_delimiters: ":/"
+"#;
+        assert!(matches!(
+            SchemaConfig::parse_from_str(schema),
+            Err(MissingSchemaKey("regex"))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_case_insensitive() -> Result<()> {
+        let schema = r#"
+timestamp: []
+delimiters: " \t\n"
+variables:
+  loglevel:
+    regex: 'error'
+    case_insensitive: true
+  int: '\d+'
+"#;
+        let parsed_schema = SchemaConfig::parse_from_str(schema)?;
+        assert!(parsed_schema.get_var_schemas()[0].is_case_insensitive());
+        assert!(false == parsed_schema.get_var_schemas()[1].is_case_insensitive());
+
+        let schema = r#"
+timestamp: []
+delimiters: " \t\n"
+case_insensitive: true
+variables:
+  loglevel: 'error'
+  int:
+    regex: '\d+'
+    case_insensitive: false
+"#;
+        let parsed_schema = SchemaConfig::parse_from_str(schema)?;
+        assert!(parsed_schema.get_var_schemas()[0].is_case_insensitive());
+        assert!(false == parsed_schema.get_var_schemas()[1].is_case_insensitive());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timestamp_format() -> Result<()> {
+        let schema = r#"
+timestamp:
+  - regex: '\d{4}\-\d{2}\-\d{2}T\d{2}:\d{2}:\d{2}\.\d{1,3}'
+    format: '%Y-%m-%dT%H:%M:%S%.f'
+  - '\d{10}'
+delimiters: " \t\n"
+variables:
+  int: '\d+'
+"#;
+        let parsed_schema = SchemaConfig::parse_from_str(schema)?;
+        assert_eq!(
+            parsed_schema.get_ts_schemas()[0].get_format(),
+            Some("%Y-%m-%dT%H:%M:%S%.f")
+        );
+        assert_eq!(parsed_schema.get_ts_schemas()[1].get_format(), None);
+
+        let schema = r#"
+timestamp:
+  - format: '%Y'
+delimiters: " \t\n"
+variables:
+  int: '\d+'
+"#;
+        assert!(matches!(
+            SchemaConfig::parse_from_str(schema),
+            Err(MissingSchemaKey("regex"))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_regex_reports_schema_entry() {
+        let schema = r#"
+timestamp:
+  - '\d{4}'
+  - '\d{2}('
+delimiters: " "
+variables:
+  int: '\d+'
+"#;
+        match SchemaConfig::parse_from_str(schema) {
+            Err(TimestampRegexError(info)) => {
+                assert_eq!(info.index, 1);
+                assert_eq!(info.regex, "\\d{2}(");
+            }
+            _ => panic!("expected a timestamp regex error"),
+        }
+
+        let schema = r#"
+timestamp: []
+delimiters: " "
+variables:
+  int: '\d+'
+  hex:
+    regex: '0x[a-f'
+  float: '\d+\.\d+'
+"#;
+        match SchemaConfig::parse_from_str(schema) {
+            Err(e @ VariableRegexError(_)) => {
+                assert!(e.to_string().contains("`hex`"));
+                let VariableRegexError(info) = e else {
+                    unreachable!()
+                };
+                assert_eq!(info.name, "hex");
+                assert_eq!(info.regex, "0x[a-f");
+            }
+            _ => panic!("expected a variable regex error"),
+        }
+    }
+
+    #[test]
+    fn test_unsupported_regex_constructs() {
+        // Rejected by the regex parser itself
+        for (regex, message) in [
+            (r"(?=foo)bar", "look-around"),
+            (r"(?<!foo)bar", "look-around"),
+            (r"(a)\1", "backreferences"),
+        ] {
+            match VarSchema::new("var".to_string(), regex.to_string()) {
+                Err(RegexParsingError(e)) => assert!(e.to_string().contains(message), "{}", regex),
+                _ => panic!("expected a regex parsing error: {}", regex),
+            }
+        }
+
+        for (regex, expected_construct, expected_offset) in [
+            (r"id=\D+", "negated Perl class", 3),
+            (r"[a-z]+[^\d]", "negated bracket class", 6),
+            (r"(\w|[\S])", "negated Perl class", 5),
+            (r"\d+?", "non-greedy repetition", 0),
+            (r"(?i:ab)+", "group flags", 2),
+            (r"(?i)abc", "inline flags", 0),
+            (r"\bfoo", "word boundary", 0),
+            (r"\pL+", "Unicode class", 0),
+            (r"[[:alpha:]]", "ASCII class", 1),
+            (r"a|", "empty pattern", 2),
+            (r"é", "non-ASCII character", 0),
+            (r"id=\x{e9}", "non-ASCII escape", 3),
+            (r"\xff", "non-ASCII escape", 0),
+            (r"[\x00-\u{100}]", "non-ASCII escape", 6),
+        ] {
+            match VarSchema::new("var".to_string(), regex.to_string()) {
+                Err(UnsupportedRegexConstruct(info)) => {
+                    assert_eq!(info.construct, expected_construct, "{}", regex);
+                    assert_eq!(info.offset, expected_offset, "{}", regex);
+                }
+                _ => panic!("expected an unsupported regex construct: {}", regex),
+            }
+        }
+
+        assert!(TimestampSchema::new(r"\d{4}\D".to_string()).is_err());
+        assert!(VarSchema::new("var".to_string(), r"^(\d|[a-f])+(\.\w)?$".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_var_anchors() -> Result<()> {
+        let var_schema = VarSchema::new("level".to_string(), "^ERROR".to_string())?;
+        assert!(var_schema.is_start_anchored());
+        assert!(false == var_schema.is_end_anchored());
+
+        let var_schema = VarSchema::new("done".to_string(), "^done$".to_string())?;
+        assert!(var_schema.is_start_anchored());
+        assert!(var_schema.is_end_anchored());
+
+        for regex in ["a^b", "(^a)|b", "a$b", "$a"] {
+            assert!(matches!(
+                VarSchema::new("invalid".to_string(), regex.to_string()),
+                Err(UnsupportedAstNodeType(_))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone_shares_data() -> Result<()> {
+        let schema_config = SchemaConfig::parse_from_str(
+            r#"
+timestamp: []
+delimiters: " "
+variables:
+  int: '\d+'
+"#,
+        )?;
+        let mut clone = (*schema_config).clone();
+        assert!(Arc::ptr_eq(&schema_config.data, &clone.data));
+
+        // Modifying the clone leaves the original untouched
+        let overlay = SchemaConfig::parse_from_str(
+            r#"
+timestamp: []
+delimiters: ":"
+variables:
+  hex: '0x[0-9a-f]+'
+"#,
+        )?;
+        clone.merge(&overlay)?;
+        assert!(false == Arc::ptr_eq(&schema_config.data, &clone.data));
+        assert_eq!(schema_config.get_var_schemas().len(), 1);
+        assert_eq!(clone.get_var_schemas().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fingerprint() -> Result<()> {
+        let schema = r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2}'
+delimiters: " :"
+variables:
+  int: '\d+'
+  hex: '0x[0-9a-f]+'
+"#;
+        let fingerprint = SchemaConfig::parse_from_str(schema)?.fingerprint();
+        assert_eq!(
+            SchemaConfig::parse_from_str(schema)?.fingerprint(),
+            fingerprint
+        );
+
+        // Same schema with a different YAML formatting and delimiter order
+        let equivalent_schema = r#"
+variables:
+  int: "\\d+"
+  hex:
+    regex: '0x[0-9a-f]+'
+delimiters: ": "
+timestamp: ['\d{4}\-\d{2}\-\d{2}']
+"#;
+        assert_eq!(
+            SchemaConfig::parse_from_str(equivalent_schema)?.fingerprint(),
+            fingerprint
+        );
+
+        let changed_schemas = [
+            // Variable order changes precedence
+            schema.replace(
+                "  int: '\\d+'\n  hex: '0x[0-9a-f]+'",
+                "  hex: '0x[0-9a-f]+'\n  int: '\\d+'",
+            ),
+            schema.replace("int:", "integer:"),
+            schema.replace("'\\d+'", "'\\d{2,}'"),
+            schema.replace("\\d{2}'", "\\d{1}'"),
+            schema.replace("\" :\"", "\" \""),
+            schema.replace(
+                "  hex: '0x[0-9a-f]+'",
+                "  hex:\n    regex: '0x[0-9a-f]+'\n    case_insensitive: true",
+            ),
+        ];
+        for changed_schema in changed_schemas {
+            assert_ne!(changed_schema, schema);
+            assert_ne!(
+                SchemaConfig::parse_from_str(changed_schema.as_str())?.fingerprint(),
+                fingerprint
+            );
+        }
+
+        Ok(())
+    }
 }
@@ -1,17 +1,71 @@
-use log_surgeon::error_handling::Result;
+#![cfg(feature = "std")]
+
+use log_surgeon::error_handling::{Error, Result, Warning};
 use log_surgeon::lexer::BufferedFileStream;
+use log_surgeon::lexer::CrlfMode;
 use log_surgeon::lexer::Lexer;
-use log_surgeon::parser::SchemaConfig;
+use log_surgeon::lexer::{LexerStream, Token, TokenSink, TokenType, UnmatchedBytesMode};
+use log_surgeon::parser::{LintWarning, SchemaConfig};
 
 use std::fs::File;
 use std::io::{self, BufRead};
+use std::rc::Rc;
 
-#[test]
-fn test_lexer_simple() -> Result<()> {
+struct StringStream {
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl StringStream {
+    fn new(input: &str) -> Self {
+        Self {
+            buf: input.as_bytes().to_vec(),
+            pos: 0,
+        }
+    }
+}
+
+impl LexerStream for StringStream {
+    fn get_next_char(&mut self) -> Result<Option<u8>> {
+        if self.pos == self.buf.len() {
+            return Ok(None);
+        }
+        self.pos += 1;
+        Ok(Some(self.buf[self.pos - 1]))
+    }
+}
+
+fn load_example_schema() -> Result<Rc<SchemaConfig>> {
     let project_root = env!("CARGO_MANIFEST_DIR");
     let schema_path = std::path::Path::new(project_root)
         .join("examples")
         .join("schema.yaml");
+    SchemaConfig::parse_from_file(schema_path.to_str().unwrap())
+}
+
+fn collect_var_tokens(lexer: &mut Lexer, input: &str) -> Result<Vec<(usize, String)>> {
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    let mut var_tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        if let TokenType::Variable(var_id) = token.get_token_type() {
+            var_tokens.push((var_id, token.get_buf_as_string()));
+        }
+    }
+    Ok(var_tokens)
+}
+
+fn collect_token_bufs(lexer: &mut Lexer, input: &str) -> Result<Vec<String>> {
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        tokens.push(token.get_buf_as_string());
+    }
+    Ok(tokens)
+}
+
+#[test]
+fn test_lexer_simple() -> Result<()> {
+    let project_root = env!("CARGO_MANIFEST_DIR");
     let log_path_dir = std::path::Path::new(project_root)
         .join("examples")
         .join("logs");
@@ -20,7 +74,7 @@ fn test_lexer_simple() -> Result<()> {
         log_path_dir.clone().join("hive-24h_large.log"),
     ];
 
-    let schema_config = SchemaConfig::parse_from_file(schema_path.to_str().unwrap())?;
+    let schema_config = load_example_schema()?;
     let mut lexer = Lexer::new(schema_config)?;
 
     for path in &log_paths {
@@ -63,3 +117,2127 @@ fn test_lexer_simple() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_lexer_verify_reconstruction() -> Result<()> {
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let log_path_dir = std::path::Path::new(project_root)
+        .join("examples")
+        .join("logs");
+    let log_paths = vec![
+        log_path_dir.clone().join("hive-24h.log"),
+        log_path_dir.clone().join("hive-24h_large.log"),
+    ];
+
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+
+    for path in &log_paths {
+        let buffered_file_stream = Box::new(BufferedFileStream::new(path.to_str().unwrap())?);
+        lexer.set_input_stream(buffered_file_stream);
+
+        let mut num_bytes = 0;
+        while let Some(token) = lexer.get_next_token()? {
+            num_bytes += token.get_buf().len();
+        }
+        assert_eq!(num_bytes as u64, path.metadata().unwrap().len());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_var_allowed_delimiters() -> Result<()> {
+    let input = "open /usr/a:b/c done\n";
+
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \t\n:"
+variables:
+  path: '(/\w+)+'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    assert_eq!(
+        collect_var_tokens(&mut lexer, input)?,
+        vec![(0, "/usr/a".to_string())]
+    );
+
+    // Without `allow_delimiters`, the pattern also matches across the space
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \t\n:"
+variables:
+  path: '(/(\w|:| )+)+'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    assert_eq!(
+        collect_var_tokens(&mut lexer, input)?,
+        vec![(0, "/usr/a:b/c done".to_string())]
+    );
+
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \t\n:"
+variables:
+  path:
+    regex: '(/(\w|:| )+)+'
+    allow_delimiters: ":"
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    assert_eq!(
+        collect_var_tokens(&mut lexer, input)?,
+        vec![(0, "/usr/a:b/c".to_string())]
+    );
+
+    Ok(())
+}
+
+// The tokens are the ones produced before per-variable delimiters were introduced: a schema that
+// doesn't use `allow_delimiters` must lex the same way. Partial matches are left out, as their
+// rewinding was fixed afterwards.
+#[test]
+fn test_lexer_var_delimiters_default() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " \t\r\n:,="
+variables:
+  time: '\d{2}:\d{2}(:\d{2})?'
+  int: '\-{0,1}\d+'
+  path: '(/(\w|\.|:)+)+'
+  kv: '\w+=\w+'
+  phrase: 'user \w+'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    let input = "2024-01-02 03:04:05 user alice opened /usr/a:b/c at 12:30 with id=7, done\n\
+                 mode=fast user  bob 10:20:30 /x:\n";
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        tokens.push((token.get_token_type(), token.get_buf_as_string()));
+    }
+    let expected = vec![
+        (TokenType::Timestamp(0), "2024-01-02 03:04:05"),
+        (TokenType::StaticText, " "),
+        (TokenType::Variable(4), "user alice"),
+        (TokenType::StaticText, " opened "),
+        (TokenType::Variable(2), "/usr/a:b/c"),
+        (TokenType::StaticText, " at "),
+        (TokenType::Variable(0), "12:30"),
+        (TokenType::StaticText, " with "),
+        (TokenType::Variable(3), "id=7"),
+        (TokenType::StaticTextWithEndLine, ", done\n"),
+        (TokenType::Variable(3), "mode=fast"),
+        (TokenType::StaticText, " user  bob "),
+        (TokenType::Variable(0), "10:20:30"),
+        (TokenType::StaticText, " "),
+        (TokenType::Variable(2), "/x:"),
+        (TokenType::StaticTextWithEndLine, "\n"),
+    ];
+    assert_eq!(
+        tokens,
+        expected
+            .into_iter()
+            .map(|(token_type, buf)| (token_type, buf.to_string()))
+            .collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_hex_escaped_var() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \n"
+variables:
+  field: '\w+\x09\d+'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    assert_eq!(
+        collect_var_tokens(&mut lexer, "set id\t42 and id 7\n")?,
+        vec![(0, "id\t42".to_string())]
+    );
+
+    let result = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \n"
+variables:
+  accented: 'caf\x{e9}'
+"#,
+    );
+    match result {
+        Err(Error::UnsupportedRegexConstruct(info)) => {
+            assert_eq!(info.construct, "non-ASCII escape");
+            assert_eq!(info.offset, 3);
+        }
+        _ => panic!("expected an unsupported regex construct"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_case_insensitive_var() -> Result<()> {
+    let input = "error Error ERROR eRRor errors\n";
+    let schema = r#"
+timestamp: []
+delimiters: " \t\n"
+variables:
+  loglevel:
+    regex: 'error'
+    case_insensitive: CASE_INSENSITIVE
+"#;
+
+    let schema_config = SchemaConfig::parse_from_str(&schema.replace("CASE_INSENSITIVE", "true"))?;
+    let mut lexer = Lexer::new(schema_config)?;
+    let expected: Vec<(usize, String)> = vec!["error", "Error", "ERROR", "eRRor"]
+        .into_iter()
+        .map(|s| (0, s.to_string()))
+        .collect();
+    assert_eq!(collect_var_tokens(&mut lexer, input)?, expected);
+
+    let schema_config = SchemaConfig::parse_from_str(&schema.replace("CASE_INSENSITIVE", "false"))?;
+    let mut lexer = Lexer::new(schema_config)?;
+    assert_eq!(
+        collect_var_tokens(&mut lexer, input)?,
+        vec![(0, "error".to_string())]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_named_timestamps() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - name: iso8601
+    regex: '\d{4}\-\d{2}\-\d{2}T\d{2}:\d{2}:\d{2}'
+    format: '%Y-%m-%dT%H:%M:%S'
+  - name: syslog
+    regex: '(Jan|Feb|Mar) ( |\d)\d \d{2}:\d{2}:\d{2}'
+  - '\d{10}'
+delimiters: " "
+variables:
+  int: '\d+'
+"#,
+    )?;
+    assert_eq!(schema_config.get_ts_name(0), Some("iso8601"));
+    assert_eq!(schema_config.get_ts_schemas()[1].get_format(), None);
+    assert_eq!(schema_config.get_ts_name(2), None);
+    assert_eq!(schema_config.get_ts_name(3), None);
+
+    let mut lexer = Lexer::new(schema_config.clone())?;
+    lexer.set_input_stream(Box::new(StringStream::new(concat!(
+        "2015-01-31T15:50:45 Took 42 ms\n",
+        "Jan 31 15:50:46 Took 7 ms\n",
+        "1422719447 Took 9 ms\n",
+    ))));
+    let mut ts_names = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        if let Some(ts_id) = token.timestamp_id() {
+            ts_names.push(schema_config.get_ts_name(ts_id));
+        }
+    }
+    assert_eq!(ts_names, vec![Some("iso8601"), Some("syslog"), None]);
+
+    Ok(())
+}
+
+#[test]
+fn test_lint_overlapping_timestamps() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2},\d{3}'
+  - '\d{4}\-\d{2}\-\d{2}T\d{2}:\d{2}:\d{2}'
+  - '\d{4}\-\d{2}\-\d{2}T\d{2}:\d{2}:\d{2}(\.\d{3})?'
+delimiters: " "
+variables:
+  int: '\d+'
+"#,
+    )?;
+    let warnings = schema_config.lint();
+    assert_eq!(
+        warnings,
+        vec![
+            LintWarning::OverlappingTimestamps {
+                first: 0,
+                second: 1
+            },
+            LintWarning::OverlappingTimestamps {
+                first: 2,
+                second: 3
+            },
+        ]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "timestamp schemas #0 and #1 match overlapping strings"
+    );
+
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+  - '\d{4}\-\d{2}\-\d{2}T\d{2}:\d{2}:\d{2}'
+delimiters: " "
+variables:
+  int: '\d+'
+"#,
+    )?;
+    assert!(schema_config.lint().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_lint_variable_matching_delimiters() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " :"
+variables:
+  time: '\d{2}:\d{2}'
+  ipv6:
+    regex: '(\w|:)+'
+    allow_delimiters: ":"
+  int: '\d+'
+  word: '[a-z ]+'
+"#,
+    )?;
+    let warnings: Vec<_> = schema_config
+        .lint()
+        .into_iter()
+        .filter(|warning| matches!(warning, LintWarning::VariableMatchesDelimiters { .. }))
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            LintWarning::VariableMatchesDelimiters {
+                name: "time".to_string(),
+                delimiters: ":".to_string(),
+            },
+            LintWarning::VariableMatchesDelimiters {
+                name: "word".to_string(),
+                delimiters: " ".to_string(),
+            },
+        ]
+    );
+    assert!(warnings[0].to_string().contains("allow_delimiters"));
+
+    Ok(())
+}
+
+#[test]
+fn test_var_matcher() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " "
+variables:
+  int: '\-{0,1}\d+'
+  float: '\-{0,1}\d+\.\d+'
+  hex: '0x(\d|[a-f])+'
+"#,
+    )?;
+    let lexer = Lexer::new(schema_config.clone())?;
+    let var_matcher = lexer.var_matcher();
+    let int_id = schema_config.get_var_id_by_name("int").unwrap();
+    let float_id = schema_config.get_var_id_by_name("float").unwrap();
+    let hex_id = schema_config.get_var_id_by_name("hex").unwrap();
+
+    let buf = b"id=42 took 3.14s at 0xbeef";
+    assert_eq!(var_matcher.longest_match_at(buf, 0), None);
+    assert_eq!(var_matcher.longest_match_at(buf, 3), Some((int_id, 2)));
+    assert_eq!(var_matcher.longest_match_at(buf, 4), Some((int_id, 1)));
+    assert_eq!(var_matcher.longest_match_at(buf, 11), Some((float_id, 4)));
+    // The longest match is an int, since `3.` isn't accepted by any variable
+    assert_eq!(var_matcher.longest_match_at(b"3.x", 0), Some((int_id, 1)));
+    assert_eq!(var_matcher.longest_match_at(buf, 20), Some((hex_id, 6)));
+    // `0` is an int on its own
+    assert_eq!(var_matcher.longest_match_at(b"0x", 0), Some((int_id, 1)));
+    assert_eq!(var_matcher.longest_match_at(buf, buf.len()), None);
+    assert_eq!(var_matcher.longest_match_at(buf, buf.len() + 1), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_token_matched_pattern() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config.clone())?;
+    lexer.set_input_stream(Box::new(StringStream::new("Took 3.14 seconds\n")));
+
+    let mut num_vars = 0;
+    while let Some(token) = lexer.get_next_token()? {
+        match token.get_token_type() {
+            TokenType::Variable(_) => {
+                assert_eq!(token.get_buf_as_string(), "3.14");
+                assert_eq!(
+                    token.matched_pattern(&schema_config),
+                    Some(r"\-{0,1}[0-9]+\.[0-9]+")
+                );
+                num_vars += 1;
+            }
+            _ => assert_eq!(token.matched_pattern(&schema_config), None),
+        }
+    }
+    assert_eq!(num_vars, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_input_stream_with_line_offset() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_input_stream_with_line_offset(
+        Box::new(StringStream::new(concat!(
+            "2015-01-31 15:50:45,392 Took 42 ms\n",
+            "  at /usr/lib/foo.so\n",
+        ))),
+        100,
+    );
+
+    let mut line_nums = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        line_nums.push(token.get_line_num());
+    }
+    assert_eq!(line_nums.first(), Some(&100));
+    assert_eq!(line_nums.last(), Some(&101));
+
+    // A new input stream numbers the lines from 1 again
+    lexer.set_input_stream(Box::new(StringStream::new("Took 42 ms\n")));
+    assert_eq!(lexer.get_next_token()?.unwrap().get_line_num(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_merged_schemas() -> Result<()> {
+    let base = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " "
+variables:
+  int: '\d+'
+"#,
+    )?;
+    let overlay = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{10}'
+delimiters: ","
+variables:
+  user: 'user_\w+'
+"#,
+    )?;
+    let mut schema_config = (*base).clone();
+    schema_config.merge(&overlay)?;
+    assert_eq!(schema_config.get_ts_schemas().len(), 2);
+    assert_eq!(schema_config.get_var_id_by_name("int"), Some(0));
+    assert_eq!(schema_config.get_var_id_by_name("user"), Some(1));
+    assert!(schema_config.has_delimiter(b' '));
+    assert!(schema_config.has_delimiter(b','));
+
+    let mut lexer = Lexer::new(std::rc::Rc::new(schema_config.clone()))?;
+    let input = concat!(
+        "2015-01-31 15:50:45 Took 42 ms for user_alice,user_bob\n",
+        "1422719446 Took 7 ms\n",
+    );
+    assert_eq!(
+        collect_var_tokens(&mut lexer, input)?,
+        vec![
+            (0, "42".to_string()),
+            (1, "user_alice".to_string()),
+            (1, "user_bob".to_string()),
+            (0, "7".to_string()),
+        ]
+    );
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    let mut ts_ids = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        ts_ids.extend(token.timestamp_id());
+    }
+    assert_eq!(ts_ids, vec![0, 1]);
+
+    // A variable defined by both schemas is rejected, leaving the schema untouched
+    let fingerprint = schema_config.fingerprint();
+    assert!(matches!(
+        schema_config.merge(&base),
+        Err(Error::DuplicateVariableName(name)) if name == "int"
+    ));
+    assert_eq!(schema_config.fingerprint(), fingerprint);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_end_token() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_emit_end_token(true);
+    lexer.set_verify_reconstruction(true);
+
+    for input in ["2015-01-31 15:50:45,392 Took 42 ms\n", "Took 42 ms", ""] {
+        lexer.set_input_stream(Box::new(StringStream::new(input)));
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.get_next_token()? {
+            tokens.push(token);
+        }
+        let (end_token, tokens) = tokens.split_last().unwrap();
+        assert!(end_token.is_end());
+        assert!(end_token.get_buf().is_empty());
+        assert!(tokens.iter().all(|token| false == token.is_end()));
+        let reconstructed: String = tokens
+            .iter()
+            .map(|token| token.get_buf_as_string())
+            .collect();
+        assert_eq!(reconstructed, input);
+        assert!(lexer.get_next_token()?.is_none());
+    }
+
+    lexer.set_emit_end_token(false);
+    lexer.set_input_stream(Box::new(StringStream::new("Took 42 ms\n")));
+    while let Some(token) = lexer.get_next_token()? {
+        assert!(false == token.is_end());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_try_clone() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let input = concat!(
+        "2015-01-31 15:50:45,392 INFO Took 42 ms to read 0xdeadbeef\n",
+        "  at /usr/lib/foo.so\n",
+        "2015-01-31 15:50:46,001 WARN Retried 3 times\n",
+    );
+    let collect_tokens = |lexer: &mut Lexer| -> Result<Vec<(TokenType, String, usize)>> {
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.get_next_token()? {
+            tokens.push((
+                token.get_token_type(),
+                token.get_buf_as_string(),
+                token.get_line_num(),
+            ));
+        }
+        Ok(tokens)
+    };
+
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_emit_delimiter_tokens(true);
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    lexer.get_next_token()?;
+    lexer.get_next_token()?;
+
+    // The clone has the settings, but neither the input stream nor the state
+    let mut cloned_lexer = lexer.try_clone();
+    assert!(matches!(
+        cloned_lexer.get_next_token(),
+        Err(Error::LexerInputStreamNotSet)
+    ));
+    cloned_lexer.set_input_stream(Box::new(StringStream::new(input)));
+    let cloned_tokens = collect_tokens(&mut cloned_lexer)?;
+    assert!(cloned_tokens
+        .iter()
+        .any(|(token_type, _, _)| TokenType::Delimiter == *token_type));
+
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    assert_eq!(collect_tokens(&mut lexer)?, cloned_tokens);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_unmatched_bytes_mode() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    let input = "Took 42 ms\n  \nStarting up\n";
+    let collect_tokens = |lexer: &mut Lexer| -> Vec<Result<(TokenType, String)>> {
+        lexer.set_input_stream(Box::new(StringStream::new(input)));
+        let mut tokens = Vec::new();
+        loop {
+            match lexer.get_next_token() {
+                Ok(Some(token)) => {
+                    tokens.push(Ok((token.get_token_type(), token.get_buf_as_string())))
+                }
+                Ok(None) => return tokens,
+                Err(e) => tokens.push(Err(e)),
+            }
+        }
+    };
+
+    let tokens = collect_tokens(&mut lexer);
+    assert!(tokens
+        .iter()
+        .all(|token| false == matches!(token, Ok((TokenType::Unmatched, _)))));
+
+    lexer.set_unmatched_bytes_mode(UnmatchedBytesMode::Unmatched);
+    lexer.set_verify_reconstruction(true);
+    let tokens: Vec<(TokenType, String)> = collect_tokens(&mut lexer)
+        .into_iter()
+        .collect::<Result<_>>()?;
+    let expected = [
+        (TokenType::Unmatched, "Took"),
+        (TokenType::StaticText, " "),
+        (TokenType::Variable(0), "42"),
+        (TokenType::StaticText, " "),
+        (TokenType::Unmatched, "ms"),
+        (TokenType::StaticTextWithEndLine, "\n"),
+        (TokenType::StaticTextWithEndLine, "  \n"),
+        (TokenType::Unmatched, "Starting"),
+        (TokenType::StaticText, " "),
+        (TokenType::Unmatched, "up"),
+        (TokenType::StaticTextWithEndLine, "\n"),
+    ];
+    assert_eq!(
+        tokens,
+        expected
+            .iter()
+            .map(|(token_type, text)| (token_type.clone(), text.to_string()))
+            .collect::<Vec<_>>()
+    );
+
+    // Each unmatched run fails once, then the lexer moves on
+    lexer.set_unmatched_bytes_mode(UnmatchedBytesMode::Error);
+    let tokens = collect_tokens(&mut lexer);
+    assert_eq!(tokens.len(), expected.len());
+    for (token, (expected_type, expected_text)) in tokens.iter().zip(expected.iter()) {
+        match token {
+            Err(Error::UnmatchedContent(info)) => {
+                assert_eq!(TokenType::Unmatched, *expected_type);
+                assert_eq!(&info.text, expected_text);
+            }
+            Ok((token_type, text)) => {
+                assert_eq!(token_type, expected_type);
+                assert_eq!(text, expected_text);
+            }
+            Err(e) => panic!("Unexpected error: {}", e),
+        }
+    }
+    assert!(matches!(
+        &tokens[7],
+        Err(Error::UnmatchedContent(info)) if 3 == info.line
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_retokenize_line() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    let get_vars = |tokens: &Vec<Token>| -> Vec<(usize, String)> {
+        tokens
+            .iter()
+            .filter_map(|token| match token.get_token_type() {
+                TokenType::Variable(var_id) => Some((var_id, token.get_buf_as_string())),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let line = "2015-01-31 15:50:45,392 Took 3.14 seconds\n";
+    let tokens = lexer.retokenize_line(line, 7)?;
+    assert!(matches!(
+        tokens[0].get_token_type(),
+        TokenType::Timestamp(_)
+    ));
+    assert_eq!(get_vars(&tokens), vec![(1, "3.14".to_string())]);
+
+    let edited_line = "2015-01-31 15:50:45,392 Took 42 seconds\n";
+    let tokens = lexer.retokenize_line(edited_line, 7)?;
+    assert_eq!(get_vars(&tokens), vec![(0, "42".to_string())]);
+    assert!(tokens.iter().all(|token| token.get_line_num() == 7));
+    let reconstructed: String = tokens
+        .iter()
+        .map(|token| token.get_buf_as_string())
+        .collect();
+    assert_eq!(reconstructed, edited_line);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_multiline_var() -> Result<()> {
+    let input = "failed 1\nException\n\tat\tfoo\nnext 2\nException\nplain 3\n";
+
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \n"
+variables:
+  trace:
+    regex: 'Exception(\n\tat\t\w+)+'
+    multiline: true
+  int: '\d+'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+
+    let mut var_tokens = Vec::new();
+    let mut line_ends = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        match token.get_token_type() {
+            TokenType::Variable(var_id) => {
+                var_tokens.push((var_id, token.get_buf_as_string(), token.get_line_num()))
+            }
+            TokenType::StaticTextWithEndLine => line_ends.push(token.get_line_num()),
+            _ => {}
+        }
+    }
+    assert_eq!(
+        var_tokens,
+        vec![
+            (1, "1".to_string(), 1),
+            (0, "Exception\n\tat\tfoo".to_string(), 2),
+            (1, "2".to_string(), 4),
+            (1, "3".to_string(), 6),
+        ]
+    );
+    // The newline consumed by the multiline variable is still counted
+    assert_eq!(line_ends, vec![1, 3, 4, 5, 6]);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_var_id_indexes_schema() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config.clone())?;
+
+    let input = "WARN took 42 3.14 0xdeadbeef at /usr/lib/libc.so in [main]\n";
+    let var_names: Vec<(&str, String)> = collect_var_tokens(&mut lexer, input)?
+        .into_iter()
+        .map(|(var_id, buf)| {
+            let var_schema = &schema_config.get_var_schemas()[var_id];
+            assert_eq!(
+                schema_config.get_var_id_by_name(var_schema.get_name()),
+                Some(var_id)
+            );
+            (schema_config.get_var_name(var_id).unwrap(), buf)
+        })
+        .collect();
+    assert_eq!(
+        var_names,
+        vec![
+            ("loglevel", "WARN".to_string()),
+            ("int", "42".to_string()),
+            ("float", "3.14".to_string()),
+            ("hex", "0xdeadbeef".to_string()),
+            ("path", "/usr/lib/libc.so".to_string()),
+            ("field_identifier", "[main]".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_anchored_var() -> Result<()> {
+    let input = "ERROR at start\nsaw ERROR here\nstill done\ndone twice done\n";
+
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \n"
+variables:
+  loglevel: '^ERROR'
+  status: 'done$'
+  word: '[A-Z]+'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+    assert_eq!(
+        collect_var_tokens(&mut lexer, input)?,
+        vec![
+            (0, "ERROR".to_string()),
+            (2, "ERROR".to_string()),
+            (1, "done".to_string()),
+            (1, "done".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_delimiter_sets() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2}'
+delimiters: " \n"
+delimiter_sets:
+  csv: ","
+timestamp_switch_delimiters: csv
+variables:
+  name:
+    regex: '[a-z]+ [a-z]+'
+    allow_delimiters: ""
+  end:
+    regex: 'END'
+    switch_delimiters: default
+  int: '\d+'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+
+    // After the timestamp, only `,` delimits variables, so `alice smith` is a single variable.
+    // `END` switches back to the default delimiters after its own trailing `,`, so `7,8` is static
+    // text. The default set is also restored at the start of each line, where `name` can't match
+    // across the space.
+    let input = "2015-01-31,alice smith,42,END,7,8 9\nbob smith 10\n";
+    assert_eq!(
+        collect_var_tokens(&mut lexer, input)?,
+        vec![
+            (0, "alice smith".to_string()),
+            (2, "42".to_string()),
+            (1, "END".to_string()),
+            (2, "9".to_string()),
+            (2, "10".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_var_allowed_bytes() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \n"
+variables:
+  int:
+    regex: '\w+'
+    allowed_bytes: "0123456789"
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    assert_eq!(
+        collect_var_tokens(&mut lexer, "12 34\n")?,
+        vec![(0, "12".to_string()), (0, "34".to_string())]
+    );
+
+    // The pattern is too permissive: `took` is accepted by the DFA but rejected by the allowlist
+    let result = collect_var_tokens(&mut lexer, "12 took\n");
+    match result {
+        Err(Error::VariableByteViolation { name, byte }) => {
+            assert_eq!(name, "int");
+            assert_eq!(byte, b't');
+        }
+        _ => panic!("Unexpected result: {:?}", result),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_lexer_var_strip_enclosing() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \n"
+variables:
+  value:
+    regex: '(\(|\)|"|\w)+'
+    strip_enclosing: ["()", '""']
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+    let input = "\"hello\" (42) ((x)) (a)(b) \"a (b \"\" 7\n";
+    assert_eq!(
+        collect_var_tokens(&mut lexer, input)?,
+        ["hello", "42", "(x)", "(a)(b)", "\"a", "(b", "\"\"", "7"]
+            .iter()
+            .map(|value| (0, value.to_string()))
+            .collect::<Vec<_>>()
+    );
+    // The enclosing pairs are kept as static text
+    assert_eq!(
+        collect_token_bufs(&mut lexer, "Say \"hi\"\n")?,
+        vec!["Say", " ", "\"", "hi", "\"", "\n"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_buffered_file_stream_bom() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+
+    let log_path = std::env::temp_dir().join("log_surgeon_test_bom.log");
+    let content = "2015-01-31 15:50:45,392 INFO Took 42 ms\n";
+    std::fs::write(&log_path, [b"\xEF\xBB\xBF", content.as_bytes()].concat()).unwrap();
+
+    let mut first_tokens = Vec::new();
+    for skip_bom in [true, false] {
+        let mut buffered_file_stream = BufferedFileStream::new(log_path.to_str().unwrap())?;
+        buffered_file_stream.set_skip_bom(skip_bom);
+        lexer.set_input_stream(Box::new(buffered_file_stream));
+        first_tokens.push(lexer.get_next_token()?.unwrap());
+    }
+    std::fs::remove_file(&log_path).unwrap();
+
+    assert!(matches!(
+        first_tokens[0].get_token_type(),
+        TokenType::Timestamp(_)
+    ));
+    assert_eq!(first_tokens[0].get_buf(), b"2015-01-31 15:50:45,392");
+    assert!(first_tokens[1].get_buf().starts_with(b"\xEF\xBB\xBF"));
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_rewind_to_first_delimiter_in_var() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " :\n"
+variables:
+  time:
+    regex: '\d+:\d+:\d+'
+    allow_delimiters: ":"
+  int: '\d+'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+
+    // `12:34:x` only partially matches `time`: the match must be split at the first `:` instead
+    // of being skipped as static text up to the last consumed delimiter.
+    let input = "at 12:34:x then 5:6:7\n";
+    assert_eq!(
+        collect_var_tokens(&mut lexer, input)?,
+        vec![
+            (1, "12".to_string()),
+            (1, "34".to_string()),
+            (0, "5:6:7".to_string()),
+        ]
+    );
+
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " :\n"
+variables:
+  time:
+    regex: '\d+:\d+:\d+'
+    allow_delimiters: ":"
+  word: '[a-z]+'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+    assert_eq!(
+        collect_var_tokens(&mut lexer, "12:ab:x\n")?,
+        vec![(1, "ab".to_string()), (1, "x".to_string())]
+    );
+
+    Ok(())
+}
+
+// Yields each chunk in turn, with a `WouldBlock` pause in between
+struct PausingStream {
+    chunks: Vec<Vec<u8>>,
+    chunk_idx: usize,
+    pos: usize,
+}
+
+impl LexerStream for PausingStream {
+    fn get_next_char(&mut self) -> Result<Option<u8>> {
+        if self.pos == self.chunks[self.chunk_idx].len() {
+            if self.chunk_idx + 1 == self.chunks.len() {
+                return Ok(None);
+            }
+            self.chunk_idx += 1;
+            self.pos = 0;
+            return Err(Error::IOError(std::io::ErrorKind::WouldBlock.into()));
+        }
+        self.pos += 1;
+        Ok(Some(self.chunks[self.chunk_idx][self.pos - 1]))
+    }
+}
+
+#[test]
+fn test_lexer_pending_bytes() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    let chunks = ["2015-01-31 15:5", "0:45 Took 12", "34 ms\n"];
+    lexer.set_input_stream(Box::new(PausingStream {
+        chunks: chunks
+            .iter()
+            .map(|chunk| chunk.as_bytes().to_vec())
+            .collect(),
+        chunk_idx: 0,
+        pos: 0,
+    }));
+
+    // Paused in the middle of the timestamp
+    assert!(matches!(lexer.get_next_token(), Err(Error::IOError(_))));
+    assert_eq!(lexer.pending_bytes(), b"");
+
+    // Paused in the middle of the variable
+    let token = lexer.get_next_token()?.unwrap();
+    assert_eq!(token.get_buf(), b"2015-01-31 15:50:45");
+    assert!(matches!(lexer.get_next_token(), Err(Error::IOError(_))));
+    assert_eq!(lexer.pending_bytes(), b" Took 12");
+
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        tokens.push(token.get_buf_as_string());
+    }
+    assert_eq!(tokens, vec![" Took ", "1234", " ms\n"]);
+    assert_eq!(lexer.pending_bytes(), b"");
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_feed() -> Result<()> {
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let log_path = std::path::Path::new(project_root)
+        .join("examples")
+        .join("logs")
+        .join("hive-24h.log");
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    let input = std::fs::read_to_string(log_path)?;
+    let get_token_info = |token: &Token| {
+        (
+            token.get_token_type(),
+            token.get_buf_as_string(),
+            token.get_line_num(),
+        )
+    };
+
+    lexer.set_input_stream(Box::new(StringStream::new(input.as_str())));
+    let mut expected = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        expected.push(get_token_info(&token));
+    }
+
+    assert!(matches!(lexer.finish(), Err(Error::LexerInputStreamNotSet)));
+    for _ in 0..2 {
+        let mut tokens = Vec::new();
+        for chunk in input.as_bytes().chunks(3) {
+            tokens.extend(lexer.feed(chunk)?.iter().map(get_token_info));
+        }
+        tokens.extend(lexer.finish()?.iter().map(get_token_info));
+        assert_eq!(tokens, expected);
+    }
+
+    // A token is returned as soon as the byte following it is fed
+    assert!(lexer.feed(b"Took 12")?.is_empty());
+    let tokens: Vec<_> = lexer.feed(b"34 ms")?.iter().map(get_token_info).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::StaticText, "Took ".to_string(), 1),
+            (TokenType::Variable(0), "1234".to_string(), 1)
+        ]
+    );
+    let tokens: Vec<_> = lexer.finish()?.iter().map(get_token_info).collect();
+    assert_eq!(tokens, vec![(TokenType::StaticText, " ms".to_string(), 1)]);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_current_line() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    let input = concat!(
+        "2015-01-31 15:50:45 Took 1234 ms to load the cache\r\n",
+        "Done\n",
+    );
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    let mut lines = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        lines.push((
+            token.get_buf_as_string(),
+            String::from_utf8_lossy(lexer.current_line().unwrap()).to_string(),
+        ));
+    }
+    // The line is read up to the delimiter following the variable, and kept whole after its line
+    // ending token
+    assert_eq!(
+        lines,
+        [
+            ("2015-01-31 15:50:45", "2015-01-31 15:50:45 "),
+            (" Took ", "2015-01-31 15:50:45 Took 1234 "),
+            ("1234", "2015-01-31 15:50:45 Took 1234 "),
+            (
+                " ms to load the cache\r\n",
+                "2015-01-31 15:50:45 Took 1234 ms to load the cache",
+            ),
+            ("Done\n", "Done"),
+        ]
+        .iter()
+        .map(|(token, line)| (token.to_string(), line.to_string()))
+        .collect::<Vec<_>>()
+    );
+
+    // Peeking into the next line doesn't change the line of the last returned token
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    for _ in 0..4 {
+        lexer.get_next_token()?;
+    }
+    assert_eq!(
+        lexer.peek_next_token()?.unwrap().get_buf_as_string(),
+        "Done\n"
+    );
+    assert_eq!(
+        lexer.current_line(),
+        Some(&b"2015-01-31 15:50:45 Took 1234 ms to load the cache"[..])
+    );
+
+    // A multiline variable is on the line it starts, and the lines it spans are tracked too
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \n"
+variables:
+  trace:
+    regex: 'Exception(\n\tat\t\w+)+'
+    multiline: true
+  int: '\d+'
+"#,
+    )?;
+    let mut multiline_lexer = Lexer::new(schema_config)?;
+    multiline_lexer.set_input_stream(Box::new(StringStream::new(
+        "failed 1\nException\n\tat\tfoo\nnext 2\n",
+    )));
+    let mut lines = Vec::new();
+    while let Some(token) = multiline_lexer.get_next_token()? {
+        lines.push((
+            token.get_buf_as_string(),
+            String::from_utf8_lossy(multiline_lexer.current_line().unwrap()).to_string(),
+        ));
+    }
+    assert_eq!(
+        lines,
+        [
+            ("failed ", "failed 1"),
+            ("1", "failed 1"),
+            ("\n", "failed 1"),
+            ("Exception\n\tat\tfoo", "Exception"),
+            ("\n", "\tat\tfoo"),
+            ("next ", "next 2"),
+            ("2", "next 2"),
+            ("\n", "next 2"),
+        ]
+        .iter()
+        .map(|(token, line)| (token.to_string(), line.to_string()))
+        .collect::<Vec<_>>()
+    );
+
+    // The start of a line longer than the GC threshold is compacted away
+    lexer.set_gc_threshold(8);
+    let line = format!("Took {} ms\n", "0 ".repeat(64));
+    lexer.set_input_stream(Box::new(StringStream::new(line.as_str())));
+    assert_eq!(lexer.current_line(), Some(&b""[..]));
+    while lexer.get_next_token()?.is_some() {}
+    assert_eq!(lexer.current_line(), None);
+    lexer.set_input_stream(Box::new(StringStream::new(line.as_str())));
+    for _ in 0..64 {
+        lexer.get_next_token()?;
+    }
+    assert_eq!(lexer.current_line(), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_peek_next_token() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+    let input = "2015-01-31 15:50:45 Took 1234 ms\nDone 0x1f\n";
+
+    let expected = collect_token_bufs(&mut lexer, input)?;
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    let mut tokens = Vec::new();
+    loop {
+        let peeked = lexer
+            .peek_next_token()?
+            .map(|token| (token.get_buf_as_string(), token.get_line_num()));
+        let peeked_again = lexer
+            .peek_next_token()?
+            .map(|token| (token.get_buf_as_string(), token.get_line_num()));
+        assert_eq!(peeked, peeked_again);
+        let token = lexer
+            .get_next_token()?
+            .map(|token| (token.get_buf_as_string(), token.get_line_num()));
+        assert_eq!(peeked, token);
+        match token {
+            Some((buf, _)) => tokens.push(buf),
+            None => break,
+        }
+    }
+    assert_eq!(tokens, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_gc_threshold() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let line = "2015-01-31 15:50:45,392 INFO Took 1234 ms to read /var/log/app.log\n";
+    let input = line.repeat(200);
+
+    // Returns the tokens and the max buffer size observed
+    let lex = |gc_threshold: Option<usize>| -> Result<(Vec<String>, usize)> {
+        let mut lexer = Lexer::new(schema_config.clone())?;
+        if let Some(gc_threshold) = gc_threshold {
+            lexer.set_gc_threshold(gc_threshold);
+        }
+        lexer.set_verify_reconstruction(true);
+        lexer.set_input_stream(Box::new(StringStream::new(input.as_str())));
+        let mut tokens = Vec::new();
+        let mut max_buf_size = 0;
+        while let Some(token) = lexer.get_next_token()? {
+            tokens.push(token.get_buf_as_string());
+            max_buf_size = max_buf_size.max(lexer.get_buf_size());
+        }
+        Ok((tokens, max_buf_size))
+    };
+
+    let (expected, default_max_buf_size) = lex(None)?;
+    let (tokens, max_buf_size) = lex(Some(64))?;
+    assert_eq!(tokens, expected);
+    assert!(default_max_buf_size > 2048);
+    assert!(max_buf_size <= 2 * (64 + line.len()));
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_crlf() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2}'
+delimiters: " "
+variables:
+  int: '\d+'
+  status: '((done)|(failed))$'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+
+    let content = "2015-01-31 Took 42\r\n2015-01-31 Step 7 done\r\nRetried 3 times\r\n";
+    let log_path = std::env::temp_dir().join("log_surgeon_test_crlf.log");
+    std::fs::write(&log_path, content).unwrap();
+    lexer.set_input_stream(Box::new(BufferedFileStream::new(
+        log_path.to_str().unwrap(),
+    )?));
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        tokens.push(token);
+    }
+    std::fs::remove_file(&log_path).unwrap();
+
+    let reconstructed: Vec<u8> = tokens
+        .iter()
+        .flat_map(|token| token.get_buf().to_vec())
+        .collect();
+    assert_eq!(reconstructed, content.as_bytes());
+
+    let vars: Vec<(String, usize)> = tokens
+        .iter()
+        .filter(|token| matches!(token.get_token_type(), TokenType::Variable(_)))
+        .map(|token| (token.get_buf_as_string(), token.get_line_num()))
+        .collect();
+    assert_eq!(
+        vars,
+        vec![
+            ("42".to_string(), 1),
+            ("7".to_string(), 2),
+            ("done".to_string(), 2),
+            ("3".to_string(), 3),
+        ]
+    );
+    for token in &tokens {
+        if matches!(token.get_token_type(), TokenType::StaticTextWithEndLine) {
+            assert!(token.get_buf().ends_with(b"\r\n"));
+        }
+    }
+    assert_eq!(tokens.last().unwrap().get_line_num(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_crlf_mode() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2}'
+delimiters: " "
+variables:
+  int: '\d+'
+  status: '((done)|(failed))$'
+"#,
+    )?;
+    let input = "Took 42 ms\r\nStep done\r\n";
+
+    let mut lexer = Lexer::new(schema_config.clone())?;
+    assert_eq!(
+        collect_token_bufs(&mut lexer, input)?,
+        vec!["Took ", "42", " ms\r\n", "Step ", "done", "\r\n"]
+    );
+
+    let mut lexer = Lexer::new(schema_config.clone())?;
+    lexer.set_crlf_mode(CrlfMode::Strip);
+    assert_eq!(
+        collect_token_bufs(&mut lexer, input)?,
+        vec!["Took ", "42", " ms\n", "Step ", "done", "\n"]
+    );
+
+    // `done` is no longer at the end of the line, as the `\r` isn't part of the line ending
+    let mut lexer = Lexer::new(schema_config.clone())?;
+    lexer.set_crlf_mode(CrlfMode::TreatCrAsDelimiter);
+    assert_eq!(
+        collect_token_bufs(&mut lexer, input)?,
+        vec!["Took ", "42", " ms\r", "\n", "Step ", "done\r", "\n"]
+    );
+
+    // A `\r` only ends a token before a `\n`, since it's not a delimiter
+    assert!(false == schema_config.has_delimiter(b'\r'));
+    let mut lexer = Lexer::new(schema_config)?;
+    assert_eq!(
+        collect_token_bufs(&mut lexer, "Took 42\r ms\r\nNext 7\n")?,
+        vec!["Took 42\r ms\r\n", "Next ", "7", "\n"]
+    );
+
+    // The `\r` is stripped before the line ending is split into runs
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp: []
+delimiters: " \t\n"
+variables:
+  int: '\d+'
+"#,
+    )?;
+    let input = "Took 42 ms\r\n";
+    let mut lexer = Lexer::new(schema_config.clone())?;
+    lexer.set_crlf_mode(CrlfMode::Strip);
+    lexer.set_emit_delimiter_tokens(true);
+    assert_eq!(
+        collect_token_bufs(&mut lexer, input)?,
+        vec!["Took", " ", "42", " ", "ms", "\n"]
+    );
+    // Otherwise, the `\r` is part of the line ending run
+    lexer.set_crlf_mode(CrlfMode::Preserve);
+    assert_eq!(
+        collect_token_bufs(&mut lexer, input)?,
+        vec!["Took", " ", "42", " ", "ms", "\r\n"]
+    );
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_crlf_mode(CrlfMode::Strip);
+    lexer.set_unmatched_bytes_mode(UnmatchedBytesMode::Unmatched);
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        tokens.push((token.get_token_type(), token.get_buf_as_string()));
+    }
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::Unmatched, "Took".to_string()),
+            (TokenType::StaticText, " ".to_string()),
+            (TokenType::Variable(0), "42".to_string()),
+            (TokenType::StaticText, " ".to_string()),
+            (TokenType::Unmatched, "ms".to_string()),
+            (TokenType::StaticTextWithEndLine, "\n".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_token_trailing_whitespace_len() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_input_stream(Box::new(StringStream::new(
+        "Took 42 ms   \nTook 42 ms\nTook 42 \t\r\nTook 42",
+    )));
+
+    let mut end_of_lines = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        match token.get_token_type() {
+            TokenType::StaticTextWithEndLine => {
+                end_of_lines.push((token.get_buf_as_string(), token.trailing_whitespace_len()))
+            }
+            _ => assert_eq!(token.trailing_whitespace_len(), 0),
+        }
+    }
+    assert_eq!(
+        end_of_lines,
+        vec![
+            (" ms   \n".to_string(), 3),
+            (" ms\n".to_string(), 0),
+            (" \t\r\n".to_string(), 2),
+        ]
+    );
+
+    Ok(())
+}
+
+const STATIC_TIMESTAMPS: &[&str] = &[r"\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}"];
+const STATIC_VARIABLES: &[(&str, &str)] = &[("int", r"\-{0,1}\d+"), ("hex", r"0x[0-9a-f]+")];
+const STATIC_DELIMITERS: &str = " :,";
+
+#[test]
+fn test_schema_config_from_static() -> Result<()> {
+    let schema_config =
+        SchemaConfig::from_static(STATIC_TIMESTAMPS, STATIC_VARIABLES, STATIC_DELIMITERS)?;
+    assert_eq!(schema_config.get_var_id_by_name("hex"), Some(1));
+    assert!(schema_config.has_delimiter(b','));
+
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_input_stream(Box::new(StringStream::new(
+        "2015-01-31 15:50:45 Read 42 bytes at 0x1f\n",
+    )));
+    let first_token = lexer.get_next_token()?.unwrap();
+    assert!(matches!(
+        first_token.get_token_type(),
+        TokenType::Timestamp(0)
+    ));
+    let mut var_tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        if let TokenType::Variable(var_id) = token.get_token_type() {
+            var_tokens.push((var_id, token.get_buf_as_string()));
+        }
+    }
+    assert_eq!(
+        var_tokens,
+        vec![(0, "42".to_string()), (1, "0x1f".to_string())]
+    );
+
+    assert!(matches!(
+        SchemaConfig::from_static(&[], &[("int", r"\d+"), ("int", r"\d")], " "),
+        Err(Error::DuplicateVariableName(_))
+    ));
+    Ok(())
+}
+
+struct CountingSink {
+    num_tokens: usize,
+    num_variables: usize,
+}
+
+impl TokenSink for CountingSink {
+    fn accept(&mut self, token: &Token) -> Result<()> {
+        self.num_tokens += 1;
+        if token.is_variable() {
+            self.num_variables += 1;
+        }
+        Ok(())
+    }
+}
+
+struct CollectingSink {
+    tokens: Vec<String>,
+}
+
+impl TokenSink for CollectingSink {
+    fn accept(&mut self, token: &Token) -> Result<()> {
+        self.tokens.push(token.get_buf_as_string());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_lexer_run_to_sink() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    let input = "2015-01-31 15:50:45,392 INFO Took 1234 ms\nRetry 0x1f in 3.5 s\n";
+
+    let expected = collect_token_bufs(&mut lexer, input)?;
+    let expected_num_variables = collect_var_tokens(&mut lexer, input)?.len();
+
+    let mut counting_sink = CountingSink {
+        num_tokens: 0,
+        num_variables: 0,
+    };
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    lexer.run_to_sink(&mut counting_sink)?;
+    assert_eq!(counting_sink.num_tokens, expected.len());
+    assert_eq!(counting_sink.num_variables, expected_num_variables);
+
+    let mut collecting_sink = CollectingSink { tokens: Vec::new() };
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    lexer.run_to_sink(&mut collecting_sink)?;
+    assert_eq!(collecting_sink.tokens, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_buffered_file_stream_mixed_line_endings() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+
+    let log_path = std::env::temp_dir().join("log_surgeon_test_mixed_line_endings.log");
+    for (content, expected_warnings) in [
+        (
+            "a\r\nb\r\nc\nd\ne\r\n",
+            vec![Warning::MixedLineEndings { line: 3 }],
+        ),
+        ("a\nb\nc\n", vec![]),
+        ("a\r\nb\r\nc", vec![]),
+    ] {
+        std::fs::write(&log_path, content).unwrap();
+        let (warning_sender, warning_receiver) = std::sync::mpsc::channel();
+        let mut buffered_file_stream = BufferedFileStream::new(log_path.to_str().unwrap())?;
+        buffered_file_stream.set_line_ending_warnings(warning_sender);
+        lexer.set_input_stream(Box::new(buffered_file_stream));
+        while lexer.get_next_token()?.is_some() {}
+        let warnings: Vec<Warning> = warning_receiver.try_iter().collect();
+        assert_eq!(warnings, expected_warnings);
+    }
+    std::fs::remove_file(&log_path).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_shared_schema_config() -> Result<()> {
+    let schema_config = std::sync::Arc::new((*load_example_schema()?).clone());
+
+    let inputs = [
+        "Took 42 ms to read 0x1f bytes\n",
+        "Wrote 3.5 MB to /tmp/out.log\n",
+    ];
+    let handles: Vec<_> = inputs
+        .into_iter()
+        .map(|input| {
+            let schema_config = schema_config.clone();
+            std::thread::spawn(move || -> Result<Vec<(usize, String)>> {
+                let mut lexer = Lexer::new_arc(schema_config)?;
+                collect_var_tokens(&mut lexer, input)
+            })
+        })
+        .collect();
+    let results: Vec<Vec<(usize, String)>> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect::<Result<_>>()?;
+
+    for (input, result) in inputs.iter().zip(results) {
+        let mut lexer = Lexer::new_arc(schema_config.clone())?;
+        assert_eq!(result, collect_var_tokens(&mut lexer, input)?);
+        assert!(false == result.is_empty());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_emit_static_text() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+    lexer.set_emit_static_text(false);
+
+    let input = "2015-01-31 15:50:45 INFO Took 1234 ms\nNothing to see here\nDone 0x1f\n";
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    assert_eq!(
+        lexer.peek_next_token()?.unwrap().get_buf_as_string(),
+        "2015-01-31 15:50:45"
+    );
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        assert!(false == token.is_static_text());
+        tokens.push((
+            token.get_token_type(),
+            token.get_buf_as_string(),
+            token.get_line_num(),
+        ));
+    }
+    assert_eq!(
+        tokens,
+        vec![
+            (
+                TokenType::Timestamp(4),
+                "2015-01-31 15:50:45".to_string(),
+                1
+            ),
+            (TokenType::Variable(3), "INFO".to_string(), 1),
+            (TokenType::Variable(0), "1234".to_string(), 1),
+            (TokenType::Variable(2), "0x1f".to_string(), 3),
+        ]
+    );
+
+    Ok(())
+}
+
+// Yields the given prefix, followed by digits indefinitely
+struct EndlessDigitStream {
+    prefix: Vec<u8>,
+    pos: usize,
+}
+
+impl LexerStream for EndlessDigitStream {
+    fn get_next_char(&mut self) -> Result<Option<u8>> {
+        self.pos += 1;
+        match self.prefix.get(self.pos - 1) {
+            Some(c) => Ok(Some(*c)),
+            None => Ok(Some(b'0' + (self.pos % 10) as u8)),
+        }
+    }
+}
+
+#[test]
+fn test_lexer_max_lookahead() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+    lexer.set_max_lookahead(Some(16));
+
+    // The digits could always extend the `int` match, which would never be finalized without a cap
+    lexer.set_input_stream(Box::new(EndlessDigitStream {
+        prefix: b"Took 1 ms, read ".to_vec(),
+        pos: 0,
+    }));
+    let mut tokens = Vec::new();
+    for _ in 0..8 {
+        let token = lexer.get_next_token()?.unwrap();
+        assert!(token.get_buf().len() <= 16);
+        assert!(lexer.pending_bytes().len() < 16);
+        tokens.push((token.get_token_type(), token.get_buf_as_string()));
+    }
+    assert_eq!(
+        tokens[..4],
+        [
+            (TokenType::StaticText, "Took ".to_string()),
+            (TokenType::Variable(0), "1".to_string()),
+            (TokenType::StaticText, " ms, read ".to_string()),
+            (TokenType::StaticText, "7890123456789012".to_string()),
+        ]
+    );
+    for (token_type, buf) in &tokens[4..] {
+        assert_eq!(*token_type, TokenType::StaticText);
+        assert_eq!(buf.len(), 16);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_max_lookahead_with_gc() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+
+    // Hitting the cap mid-match tokenizes the static text before the match, after which the GC
+    // compacts the buffer while the match is still in progress
+    lexer.set_gc_threshold(1);
+    lexer.set_max_lookahead(Some(8));
+    let tokens = collect_token_bufs(&mut lexer, "abcdefg 12")?;
+    assert_eq!(tokens.concat(), "abcdefg 12");
+    assert_eq!(tokens.last().map(String::as_str), Some("12"));
+
+    // The same with the default GC threshold
+    let mut lexer = Lexer::new(load_example_schema()?)?;
+    lexer.set_verify_reconstruction(true);
+    lexer.set_max_lookahead(Some(5001));
+    let input = format!("{} 12\n", "x".repeat(5000));
+    let tokens = collect_token_bufs(&mut lexer, input.as_str())?;
+    assert_eq!(tokens.concat(), input);
+    assert_eq!(tokens[tokens.len() - 2], "12");
+
+    Ok(())
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn test_gzip_file_stream() -> Result<()> {
+    use log_surgeon::lexer::GzipFileStream;
+    use std::io::Write;
+
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let log_path = std::path::Path::new(project_root)
+        .join("examples")
+        .join("logs")
+        .join("hive-24h_large.log");
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+
+    let gzip_path = std::env::temp_dir().join("log_surgeon_test_gzip_file_stream.log.gz");
+    let mut encoder = flate2::write::GzEncoder::new(
+        File::create(&gzip_path).unwrap(),
+        flate2::Compression::default(),
+    );
+    encoder
+        .write_all(&std::fs::read(&log_path).unwrap())
+        .unwrap();
+    encoder.finish().unwrap();
+
+    let mut collect_tokens = |input_stream: Box<dyn LexerStream>| -> Result<Vec<Token>> {
+        lexer.set_input_stream(input_stream);
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.get_next_token()? {
+            tokens.push(token);
+        }
+        Ok(tokens)
+    };
+    let expected = collect_tokens(Box::new(BufferedFileStream::new(
+        log_path.to_str().unwrap(),
+    )?))?;
+    let tokens = collect_tokens(Box::new(GzipFileStream::new(gzip_path.to_str().unwrap())?))?;
+    std::fs::remove_file(&gzip_path).unwrap();
+
+    assert!(false == expected.is_empty());
+    assert_eq!(tokens.len(), expected.len());
+    for (token, expected_token) in tokens.iter().zip(&expected) {
+        assert_eq!(token.get_buf(), expected_token.get_buf());
+        assert_eq!(token.get_token_type(), expected_token.get_token_type());
+        assert_eq!(token.get_line_num(), expected_token.get_line_num());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_reset() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let input_b = "2015-01-31 15:50:45 Took 1234 ms\nDone 0x1f\n";
+    let mut fresh_lexer = Lexer::new(schema_config.clone())?;
+    let mut expected = Vec::new();
+    fresh_lexer.set_input_stream(Box::new(StringStream::new(input_b)));
+    while let Some(token) = fresh_lexer.get_next_token()? {
+        expected.push((token.get_buf_as_string(), token.get_line_num()));
+    }
+
+    let mut lexer = Lexer::new(schema_config)?;
+    // Stops in the middle of the stream A, with unconsumed and buffered input left
+    lexer.set_input_stream(Box::new(StringStream::new(
+        "Line 1\nLine 2\n2015-01-31 15:50:45 Line 3 has 0x1",
+    )));
+    for _ in 0..5 {
+        lexer.get_next_token()?;
+    }
+    lexer.reset();
+    assert!(matches!(
+        lexer.get_next_token(),
+        Err(Error::LexerInputStreamNotSet)
+    ));
+
+    lexer.set_input_stream(Box::new(StringStream::new(input_b)));
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        tokens.push((token.get_buf_as_string(), token.get_line_num()));
+    }
+    assert_eq!(tokens, expected);
+    assert_eq!(tokens.first().unwrap().1, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_emit_delimiter_tokens() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+    lexer.set_emit_delimiter_tokens(true);
+
+    let input = "2015-01-31 15:50:45  Took 1234 ms, done!\r\nBye \n";
+    lexer.set_input_stream(Box::new(StringStream::new(input)));
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        tokens.push((token.get_token_type(), token.get_buf_as_string()));
+    }
+    let expected = vec![
+        (TokenType::Timestamp(4), "2015-01-31 15:50:45"),
+        (TokenType::Delimiter, "  "),
+        (TokenType::StaticText, "Took"),
+        (TokenType::Delimiter, " "),
+        (TokenType::Variable(0), "1234"),
+        (TokenType::Delimiter, " "),
+        (TokenType::StaticText, "ms"),
+        (TokenType::Delimiter, ", "),
+        (TokenType::StaticText, "done"),
+        (TokenType::StaticTextWithEndLine, "!\r\n"),
+        (TokenType::StaticText, "Bye"),
+        (TokenType::StaticTextWithEndLine, " \n"),
+    ];
+    assert_eq!(
+        tokens,
+        expected
+            .into_iter()
+            .map(|(token_type, buf)| (token_type, buf.to_string()))
+            .collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_dfa_stats() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '(a)|(ab)'
+delimiters: " "
+delimiter_sets:
+  csv: ","
+variables:
+  word: '(a)|(ab)'
+"#,
+    )?;
+    let lexer = Lexer::new(schema_config)?;
+    // Only matches `a` or `ab`: a start state, and a state after each of `a` and `b`
+    assert_eq!(lexer.ts_dfa_stats(), (3, 2));
+    assert_eq!(lexer.var_dfa_stats(), vec![(3, 2), (3, 2)]);
+    Ok(())
+}
+
+#[test]
+fn test_stdin_stream() -> Result<()> {
+    use log_surgeon::lexer::StdinStream;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    const CHILD_ENV_VAR: &str = "LOG_SURGEON_TEST_STDIN_STREAM_CHILD";
+    const TOKEN_PREFIX: &str = "token: ";
+
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let log_path = std::path::Path::new(project_root)
+        .join("examples")
+        .join("logs")
+        .join("hive-24h.log");
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    let mut collect_tokens = |input_stream: Box<dyn LexerStream>| -> Result<Vec<String>> {
+        lexer.set_input_stream(input_stream);
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.get_next_token()? {
+            tokens.push(format!(
+                "{}{:?} {:?} {}",
+                TOKEN_PREFIX,
+                token.get_buf_as_string(),
+                token.get_token_type(),
+                token.get_line_num()
+            ));
+        }
+        Ok(tokens)
+    };
+
+    // The child process is this very test, lexing its standard input
+    if std::env::var_os(CHILD_ENV_VAR).is_some() {
+        for token in collect_tokens(Box::new(StdinStream::new()))? {
+            println!("{}", token);
+        }
+        return Ok(());
+    }
+
+    let expected = collect_tokens(Box::new(BufferedFileStream::new(
+        log_path.to_str().unwrap(),
+    )?))?;
+
+    let mut child = Command::new(std::env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "test_stdin_stream",
+            "--nocapture",
+            "--test-threads=1",
+        ])
+        .env(CHILD_ENV_VAR, "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut child_stdin = child.stdin.take().unwrap();
+    let log = std::fs::read(&log_path).unwrap();
+    let writer = std::thread::spawn(move || child_stdin.write_all(&log).unwrap());
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    assert!(output.status.success());
+
+    let tokens: Vec<&str> = std::str::from_utf8(&output.stdout)
+        .unwrap()
+        .lines()
+        // The test harness may print its own output ahead of the first token on the same line
+        .filter_map(|line| line.find(TOKEN_PREFIX).map(|pos| &line[pos..]))
+        .collect();
+    assert!(false == expected.is_empty());
+    assert_eq!(tokens, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_mid_line_timestamps() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_mid_line_timestamps(true);
+    lexer.set_verify_reconstruction(true);
+    lexer.set_input_stream(Box::new(StringStream::new(
+        "2015-01-31 15:50:45 sent at 2015-01-31 15:50:47,001 took 12 ms\n",
+    )));
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        tokens.push((token.get_buf_as_string(), token.get_token_type()));
+    }
+    assert_eq!(
+        tokens,
+        vec![
+            ("2015-01-31 15:50:45".to_string(), TokenType::Timestamp(4)),
+            (" sent at ".to_string(), TokenType::StaticText),
+            (
+                "2015-01-31 15:50:47,001".to_string(),
+                TokenType::Timestamp(3)
+            ),
+            (" took ".to_string(), TokenType::StaticText),
+            ("12".to_string(), TokenType::Variable(0)),
+            (" ms\n".to_string(), TokenType::StaticTextWithEndLine),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_timestamp_after_prefix() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_mid_line_timestamps(true);
+    lexer.set_verify_reconstruction(true);
+    lexer.set_input_stream(Box::new(StringStream::new(concat!(
+        "[prefix] 2015-01-31T15:50:45.392 message\n",
+        "from node-1 2015-01-31T15:50:46.001 message\n",
+    ))));
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.get_next_token()? {
+        tokens.push((token.get_buf_as_string(), token.get_token_type()));
+    }
+    assert_eq!(
+        tokens,
+        vec![
+            ("[prefix]".to_string(), TokenType::Variable(4)),
+            (" ".to_string(), TokenType::StaticText),
+            (
+                "2015-01-31T15:50:45.392".to_string(),
+                TokenType::Timestamp(0)
+            ),
+            (" message\n".to_string(), TokenType::StaticTextWithEndLine),
+            ("from node-1 ".to_string(), TokenType::StaticText),
+            (
+                "2015-01-31T15:50:46.001".to_string(),
+                TokenType::Timestamp(0)
+            ),
+            (" message\n".to_string(), TokenType::StaticTextWithEndLine),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_embedded_timestamps() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " \n"
+variables:
+  int: '\d+'
+  loglevel: '(INFO)|(WARN)|(ERROR)'
+"#,
+    )?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_embedded_timestamps(true);
+    lexer.set_verify_reconstruction(true);
+    let input = concat!(
+        "INFO pid=123 2024-01-01 00:00:00 took 5 ms\n",
+        "2024-01-01 00:00:01 WARN retried 2 times\n",
+        "no timestamp in 42 lines\n",
+        "ERROR 2024-01-01 00:00:02 retry at 2024-01-01 00:00:03\n",
+    );
+    let mut collect_tokens = |lexer: &mut Lexer| -> Result<Vec<(String, TokenType)>> {
+        lexer.set_input_stream(Box::new(StringStream::new(input)));
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.get_next_token()? {
+            tokens.push((token.get_buf_as_string(), token.get_token_type()));
+        }
+        Ok(tokens)
+    };
+    // The text leading an embedded timestamp is lexed as usual, and only the first timestamp of a
+    // line is matched
+    let expected: Vec<(String, TokenType)> = [
+        ("INFO", TokenType::Variable(1)),
+        (" pid=123 ", TokenType::StaticText),
+        ("2024-01-01 00:00:00", TokenType::Timestamp(0)),
+        (" took ", TokenType::StaticText),
+        ("5", TokenType::Variable(0)),
+        (" ms\n", TokenType::StaticTextWithEndLine),
+        ("2024-01-01 00:00:01", TokenType::Timestamp(0)),
+        (" ", TokenType::StaticText),
+        ("WARN", TokenType::Variable(1)),
+        (" retried ", TokenType::StaticText),
+        ("2", TokenType::Variable(0)),
+        (" times\n", TokenType::StaticTextWithEndLine),
+        ("no timestamp in ", TokenType::StaticText),
+        ("42", TokenType::Variable(0)),
+        (" lines\n", TokenType::StaticTextWithEndLine),
+        ("ERROR", TokenType::Variable(1)),
+        (" ", TokenType::StaticText),
+        ("2024-01-01 00:00:02", TokenType::Timestamp(0)),
+        (
+            " retry at 2024-01-01 00:00:03\n",
+            TokenType::StaticTextWithEndLine,
+        ),
+    ]
+    .into_iter()
+    .map(|(buf, token_type)| (buf.to_string(), token_type))
+    .collect();
+    assert_eq!(collect_tokens(&mut lexer)?, expected);
+
+    // Without the mode, the leading text is lexed as usual, and the timestamp isn't matched
+    lexer.set_embedded_timestamps(false);
+    let tokens = collect_tokens(&mut lexer)?;
+    assert_eq!(tokens[0], ("INFO".to_string(), TokenType::Variable(1)));
+    let first_line_len = tokens
+        .iter()
+        .position(|(_, token_type)| TokenType::StaticTextWithEndLine == *token_type)
+        .unwrap();
+    assert!(tokens[..first_line_len]
+        .iter()
+        .all(|(_, token_type)| false == matches!(token_type, TokenType::Timestamp(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_max_line_len() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_max_line_len(Some(16));
+    let mut lex_until_error = |input_stream: Box<dyn LexerStream>| -> (Vec<String>, Error) {
+        lexer.set_input_stream(input_stream);
+        let mut tokens = Vec::new();
+        loop {
+            match lexer.get_next_token() {
+                Ok(Some(token)) => tokens.push(token.get_buf_as_string()),
+                Ok(None) => panic!("The over-long line isn't detected"),
+                Err(e) => return (tokens, e),
+            }
+        }
+    };
+
+    // A line of exactly the cap is fine, as its `\n` doesn't count
+    let (tokens, error) = lex_until_error(Box::new(StringStream::new(
+        "Took 1234 ms ok\nTook 12345 ms ok!\nTook 1 ms\n",
+    )));
+    assert_eq!(tokens, vec!["Took ", "1234", " ms ok\n", "Took ", "12345"]);
+    assert!(matches!(
+        error,
+        Error::LineTooLong {
+            line_num: 2,
+            limit: 16
+        }
+    ));
+
+    // An endless line fails once the cap is exceeded, instead of being buffered without limit
+    let (tokens, error) = lex_until_error(Box::new(EndlessDigitStream {
+        prefix: b"Took 1 ms\nread ".to_vec(),
+        pos: 0,
+    }));
+    assert_eq!(tokens, vec!["Took ", "1", " ms\n"]);
+    assert!(matches!(
+        error,
+        Error::LineTooLong {
+            line_num: 2,
+            limit: 16
+        }
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_lexer_substring_matching() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " \n:"
+variables:
+  ip: '\d+\.\d+\.\d+\.\d+'
+  int: '\d+'
+"#,
+    )?;
+    let input = "host=10.0.0.1:8080 via gw10.0.0.254/24 at 1.2.3\n";
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_verify_reconstruction(true);
+    let mut lex = |lexer: &mut Lexer| -> Result<Vec<(TokenType, String)>> {
+        lexer.set_input_stream(Box::new(StringStream::new(input)));
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.get_next_token()? {
+            tokens.push((token.get_token_type(), token.get_buf_as_string()));
+        }
+        Ok(tokens)
+    };
+
+    // Only the variables bounded by delimiters are extracted by default
+    let variables: Vec<String> = lex(&mut lexer)?
+        .into_iter()
+        .filter(|(token_type, _)| matches!(token_type, TokenType::Variable(_)))
+        .map(|(_, buf)| buf)
+        .collect();
+    assert_eq!(variables, vec!["8080"]);
+
+    lexer.set_substring_matching(true);
+    assert_eq!(
+        lex(&mut lexer)?,
+        vec![
+            (TokenType::StaticText, "host=".to_string()),
+            (TokenType::Variable(0), "10.0.0.1".to_string()),
+            (TokenType::StaticText, ":".to_string()),
+            (TokenType::Variable(1), "8080".to_string()),
+            (TokenType::StaticText, " via gw".to_string()),
+            (TokenType::Variable(0), "10.0.0.254".to_string()),
+            (TokenType::StaticText, "/".to_string()),
+            (TokenType::Variable(1), "24".to_string()),
+            (TokenType::StaticText, " at ".to_string()),
+            // The failed `ip` match restarts right after its start, so `2` and `3` are found too
+            (TokenType::Variable(1), "1".to_string()),
+            (TokenType::StaticText, ".".to_string()),
+            (TokenType::Variable(1), "2".to_string()),
+            (TokenType::StaticText, ".".to_string()),
+            (TokenType::Variable(1), "3".to_string()),
+            (TokenType::StaticTextWithEndLine, "\n".to_string()),
+        ]
+    );
+
+    Ok(())
+}
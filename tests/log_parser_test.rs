@@ -1,9 +1,47 @@
-use log_surgeon::error_handling::Result;
-use log_surgeon::log_parser::LogParser;
+#![cfg(feature = "std")]
+
+use log_surgeon::error_handling::{Error, Result};
+use log_surgeon::lexer::{LexerStream, TokenType};
+use log_surgeon::log_parser::{
+    parse_files_in_parallel, DedupMode, LogParser, ParseProgress, PositionalSchema,
+};
 use log_surgeon::parser::SchemaConfig;
 
 use std::fs::File;
 use std::io::{self, BufRead};
+use std::rc::Rc;
+
+struct StringStream {
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl StringStream {
+    fn new(input: &str) -> Self {
+        Self {
+            buf: input.as_bytes().to_vec(),
+            pos: 0,
+        }
+    }
+}
+
+impl LexerStream for StringStream {
+    fn get_next_char(&mut self) -> Result<Option<u8>> {
+        if self.pos == self.buf.len() {
+            return Ok(None);
+        }
+        self.pos += 1;
+        Ok(Some(self.buf[self.pos - 1]))
+    }
+}
+
+fn load_example_schema() -> Result<Rc<SchemaConfig>> {
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let schema_path = std::path::Path::new(project_root)
+        .join("examples")
+        .join("schema.yaml");
+    SchemaConfig::parse_from_file(schema_path.to_str().unwrap())
+}
 
 #[test]
 fn test_lexer_simple() -> Result<()> {
@@ -50,3 +88,1232 @@ fn test_lexer_simple() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_logfmt_mode() -> Result<()> {
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_logfmt_mode(true);
+    log_parser.set_input_stream(Box::new(StringStream::new(
+        "2015-01-31 15:50:45,392 level=info msg=\"user logged in\" user_id=42\n",
+    )))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    let actual: Vec<(&str, &str)> = log_event
+        .logfmt_pairs()
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(
+        actual,
+        vec![
+            ("level", "info"),
+            ("msg", "user logged in"),
+            ("user_id", "42")
+        ]
+    );
+    assert!(log_parser.parse_next_log_event()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_positional_schema() -> Result<()> {
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_positional_schema(Some(PositionalSchema::new(&["time", "level", "msg"])));
+    log_parser.set_input_stream(Box::new(StringStream::new(concat!(
+        "2015-01-31 15:50:45 INFO Started\n",
+        "2015-01-31 15:50:46 WARN\n",
+    ))))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    let actual: Vec<(&str, &str)> = log_event
+        .positional_fields()
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(
+        actual,
+        vec![
+            ("time", "2015-01-31 15:50:45"),
+            ("level", "INFO"),
+            ("msg", "Started")
+        ]
+    );
+
+    // A missing trailing field is left out
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.positional_fields().len(), 2);
+    assert_eq!(log_event.positional_fields()["level"], "WARN");
+    assert!(log_event.positional_fields().get("msg").is_none());
+
+    log_parser.set_positional_schema(None);
+    log_parser.set_input_stream(Box::new(StringStream::new(
+        "2015-01-31 15:50:45 INFO Started\n",
+    )))?;
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert!(log_event.positional_fields().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_max_tokens_per_event() -> Result<()> {
+    let max_tokens_per_event = 4;
+    let mut input = String::new();
+    for i in 0..100 {
+        input += format!("line {} has no timestamp at all\n", i).as_str();
+    }
+
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_max_tokens_per_event(Some(max_tokens_per_event));
+    log_parser.set_input_stream(Box::new(StringStream::new(input.as_str())))?;
+
+    let mut actual = String::new();
+    let mut num_log_events = 0;
+    while let Some(log_event) = log_parser.parse_next_log_event()? {
+        assert!(log_event.get_num_tokens() <= max_tokens_per_event);
+        actual += log_event.to_string().as_str();
+        num_log_events += 1;
+    }
+    assert!(num_log_events > 1);
+    assert_eq!(actual, input);
+
+    Ok(())
+}
+
+#[test]
+fn test_single_line_mode() -> Result<()> {
+    let lines = [
+        "Starting service on port 8080\n",
+        "Connected to /var/run/db.sock\n",
+        "2015-01-31 15:50:45,392 This one has a timestamp\n",
+        "Shutting down\n",
+    ];
+
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_multiline_mode(false);
+    log_parser.set_input_stream(Box::new(StringStream::new(lines.concat().as_str())))?;
+
+    for (idx, line) in lines.iter().enumerate() {
+        let log_event = log_parser.parse_next_log_event()?.unwrap();
+        assert_eq!(log_event.get_line_range(), (idx + 1, idx + 1));
+        assert_eq!(log_event.to_string(), line.to_string());
+    }
+    assert!(log_parser.parse_next_log_event()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_log_parser_from_lexer() -> Result<()> {
+    use log_surgeon::lexer::Lexer;
+
+    let input = concat!(
+        "2015-01-31 15:50:45,392 Took 42 ms\n",
+        "  at Foo.bar\n",
+        "2015-01-31 15:50:46,001 Done",
+    );
+    let parse = |mut log_parser: LogParser| -> Result<Vec<Vec<TokenType>>> {
+        log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+        let mut log_events = Vec::new();
+        while let Some(log_event) = log_parser.parse_next_log_event()? {
+            log_events.push(
+                log_event
+                    .get_log_message_tokens()
+                    .iter()
+                    .map(|token| token.get_token_type())
+                    .collect(),
+            );
+        }
+        Ok(log_events)
+    };
+    let expected = parse(LogParser::new(load_example_schema()?)?)?;
+    assert_eq!(expected.len(), 2);
+
+    // The end token of a lexer configured to emit it isn't part of the last log event
+    let mut lexer = Lexer::new(load_example_schema()?)?;
+    lexer.set_emit_end_token(true);
+    assert_eq!(parse(LogParser::from_lexer(lexer))?, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_log_parser_from_lexer_keeps_input_bytes() -> Result<()> {
+    use log_surgeon::lexer::{CrlfMode, Lexer, UnmatchedBytesMode};
+
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " "
+variables:
+  int: '\d+'
+"#,
+    )?;
+    let input = "2020-01-01 00:00:00 a 1\r\ncont 2\n2020-01-01 00:00:01 b 3\n";
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_emit_static_text(false);
+    lexer.set_crlf_mode(CrlfMode::Strip);
+    lexer.set_unmatched_bytes_mode(UnmatchedBytesMode::Error);
+    let mut log_parser = LogParser::from_lexer(lexer);
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+    let mut log_events = Vec::new();
+    while let Some(log_event) = log_parser.parse_next_log_event()? {
+        log_events.push(log_event.to_string());
+    }
+    assert_eq!(
+        log_events,
+        vec![
+            "2020-01-01 00:00:00 a 1\r\ncont 2\n",
+            "2020-01-01 00:00:01 b 3\n"
+        ]
+    );
+    assert_eq!(log_parser.last_committed_offset(), input.len() as u64);
+
+    Ok(())
+}
+
+#[test]
+fn test_log_parser_with_static_text_off() -> Result<()> {
+    use log_surgeon::lexer::Lexer;
+
+    let input = concat!(
+        "2015-01-31 15:50:45,392 INFO Took 42 ms\n",
+        "  at /usr/lib/foo.so\n",
+        "Done\n",
+        "2015-01-31 15:50:46,001 ERROR Failed with 0x1f\n",
+    );
+    let mut lexer = Lexer::new(load_example_schema()?)?;
+    lexer.set_emit_static_text(false);
+    let mut log_parser = LogParser::from_lexer(lexer);
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.get_line_range(), (1, 3));
+    assert_eq!(log_event.get_line_text(2), Some("  at /usr/lib/foo.so"));
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.get_line_range(), (4, 4));
+    assert_eq!(
+        log_event.to_string(),
+        "2015-01-31 15:50:46,001 ERROR Failed with 0x1f\n"
+    );
+    assert!(log_parser.parse_next_log_event()?.is_none());
+    assert_eq!(log_parser.last_committed_offset(), input.len() as u64);
+
+    Ok(())
+}
+
+#[test]
+fn test_log_event_to_json() -> Result<()> {
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(
+        "2015-01-31 15:50:45,392 Took 42 ms\n",
+    )))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(
+        log_event.to_json(),
+        serde_json::json!({
+            "timestamp": "2015-01-31 15:50:45,392",
+            "line_range": [1, 1],
+            "tokens": [
+                {"type": "static_text", "text": " Took "},
+                {"type": "variable", "name": "int", "text": "42"},
+                {"type": "static_text_with_end_line", "text": " ms\n"},
+            ],
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_log_event_get_variables() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    let int_id = schema_config.get_var_id_by_name("int").unwrap();
+    let mut log_parser = LogParser::new(schema_config)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(
+        "2015-01-31 15:50:45,392 Moved 42 rows in 3.5 ms from 0xdeadbeef to -7\n",
+    )))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    let get_bufs = |tokens: Vec<&log_surgeon::lexer::Token>| -> Vec<String> {
+        tokens
+            .iter()
+            .map(|token| token.get_buf_as_string())
+            .collect()
+    };
+    assert_eq!(
+        get_bufs(log_event.get_variables_by_name("int")),
+        vec!["42", "-7"]
+    );
+    assert_eq!(
+        get_bufs(log_event.get_variables_by_id(int_id)),
+        vec!["42", "-7"]
+    );
+    assert_eq!(
+        get_bufs(log_event.get_variables_by_name("hex")),
+        vec!["0xdeadbeef"]
+    );
+    assert!(log_event.get_variables_by_name("path").is_empty());
+    assert!(log_event.get_variables_by_name("unknown").is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_log_event_contains_variable() -> Result<()> {
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(concat!(
+        "Starting up with 0xbeef\n",
+        "2015-01-31 15:50:45,392 INFO Took 42 ms\n",
+    ))))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert!(false == log_event.contains_timestamp());
+    assert!(log_event.contains_variable_named("hex"));
+    assert!(false == log_event.contains_variable_named("int"));
+    assert!(false == log_event.contains_variable_named("loglevel"));
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert!(log_event.contains_timestamp());
+    assert!(log_event.contains_variable_named("int"));
+    assert!(log_event.contains_variable_named("loglevel"));
+    assert!(false == log_event.contains_variable_named("hex"));
+    assert!(false == log_event.contains_variable_named("unknown"));
+
+    Ok(())
+}
+
+#[test]
+fn test_log_event_describe_variables() -> Result<()> {
+    let schema_config = load_example_schema()?;
+    for (var_id, var_schema) in schema_config.get_var_schemas().iter().enumerate() {
+        assert_eq!(
+            schema_config.get_var_regex(var_id),
+            Some(var_schema.get_regex())
+        );
+    }
+    assert_eq!(schema_config.get_var_regex(0), Some(r"\-{0,1}\d+"));
+    assert_eq!(
+        schema_config.get_var_regex(schema_config.get_var_schemas().len()),
+        None
+    );
+
+    let mut log_parser = LogParser::new(schema_config)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(concat!(
+        "Starting up\n",
+        "2015-01-31 15:50:45,392 INFO Took 42 ms\n",
+    ))))?;
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.describe_variables(), "");
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(
+        log_event.describe_variables(),
+        concat!(
+            "loglevel: \"INFO\" matched by '(INFO)|(DEBUG)|(WARN)|(ERROR)|(TRACE)|(FATAL)'\n",
+            "int: \"42\" matched by '\\-{0,1}\\d+'\n",
+        )
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_embedded_timestamps() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " \n"
+variables:
+  int: '\d+'
+  loglevel: '(INFO)|(WARN)|(ERROR)'
+"#,
+    )?;
+    let input = concat!(
+        "Starting up\n",
+        "INFO pid=123 2024-01-01 00:00:00 took 5 ms\n",
+        "  at worker 7\n",
+        "2024-01-01 00:00:01 WARN retried 2 times\n",
+        "ERROR pid=124 2024-01-01 00:00:02 failed\n",
+        "ERROR pid=125 2024-01-01 00:00:03 failed\n",
+    );
+    let mut log_parser = LogParser::new(schema_config.clone())?;
+    log_parser.set_embedded_timestamps(true);
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+    let mut log_events = Vec::new();
+    while let Some(log_event) = log_parser.parse_next_log_event()? {
+        log_events.push(log_event);
+    }
+
+    let summary: Vec<_> = log_events
+        .iter()
+        .map(|log_event| (log_event.get_line_range(), log_event.timestamp_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ((1, 1), None),
+            ((2, 3), Some("2024-01-01 00:00:00")),
+            ((4, 4), Some("2024-01-01 00:00:01")),
+            ((5, 5), Some("2024-01-01 00:00:02")),
+            ((6, 6), Some("2024-01-01 00:00:03")),
+        ]
+    );
+    assert_eq!(
+        log_events
+            .iter()
+            .map(|log_event| log_event.to_string())
+            .collect::<String>(),
+        input
+    );
+    // The text leading an embedded timestamp is kept in the log message
+    let message: String = log_events[1]
+        .get_log_message_tokens()
+        .iter()
+        .map(|token| token.get_buf_as_string())
+        .collect();
+    assert_eq!(
+        message,
+        "INFO pid=123 2024-01-01 00:00:00 took 5 ms\n  at worker 7\n"
+    );
+    assert!(log_events[1].contains_variable_named("int"));
+
+    // Only the embedded timestamps differ
+    let mut log_parser = LogParser::new(schema_config)?;
+    log_parser.set_embedded_timestamps(true);
+    log_parser.set_dedup_mode(Some(DedupMode::Exact));
+    log_parser.set_input_stream(Box::new(StringStream::new(concat!(
+        "ERROR pid=124 2024-01-01 00:00:02 failed\n",
+        "ERROR pid=124 2024-01-01 00:00:03 failed\n",
+    ))))?;
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.repeat_count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_log_event_get_line_text() -> Result<()> {
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(concat!(
+        "2015-01-31 15:50:45,392 INFO Loaded 0x1f entries into the cache\r\n",
+        "  after 3 retries in /var/cache\n",
+        "2015-01-31 15:50:46,001 INFO Done\n",
+    ))))?;
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    let hex_token = log_event.get_variables_by_name("hex")[0];
+    assert_eq!(
+        log_event.get_line_text(hex_token.get_line_num()),
+        Some("2015-01-31 15:50:45,392 INFO Loaded 0x1f entries into the cache")
+    );
+    let path_token = log_event.get_variables_by_name("path")[0];
+    assert_eq!(
+        log_event.get_line_text(path_token.get_line_num()),
+        Some("  after 3 retries in /var/cache")
+    );
+    assert_eq!(log_event.get_line_text(0), None);
+    assert_eq!(log_event.get_line_text(3), None);
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(
+        log_event.get_line_text(3),
+        Some("2015-01-31 15:50:46,001 INFO Done")
+    );
+
+    // The lines spanned by a multiline variable
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " \n"
+variables:
+  trace:
+    regex: 'Exception(\n\tat\t\w+)+'
+    multiline: true
+  int: '\d+'
+"#,
+    )?;
+    let mut log_parser = LogParser::new(schema_config)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(concat!(
+        "2015-01-31 15:50:45 failed 1 Exception\n",
+        "\tat\tfoo\n",
+        "\tat\tbar 2\n",
+    ))))?;
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.get_line_range(), (1, 3));
+    assert_eq!(
+        log_event.get_line_text(1),
+        Some("2015-01-31 15:50:45 failed 1 Exception")
+    );
+    assert_eq!(log_event.get_line_text(2), Some("\tat\tfoo"));
+    assert_eq!(log_event.get_line_text(3), Some("\tat\tbar 2"));
+
+    Ok(())
+}
+
+#[test]
+fn test_strict_mode() -> Result<()> {
+    use log_surgeon::lexer::{Lexer, UnmatchedBytesMode};
+
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " :"
+variables:
+  loglevel: '(INFO)|(WARN)'
+  int: '\d+'
+"#,
+    )?;
+    let input = "2015-01-31 15:50:45 INFO 42\n2015-01-31 15:50:46 WARN user=alice 7\n";
+    let mut log_parser = LogParser::new(schema_config.clone())?;
+    log_parser.set_strict_mode(true);
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.to_string(), "2015-01-31 15:50:45 INFO 42\n");
+    match log_parser.parse_next_log_event() {
+        Err(Error::UnmatchedContent(info)) => {
+            assert_eq!(info.line, 2);
+            assert_eq!(info.text, " user=alice ");
+        }
+        _ => panic!("expected unmatched content"),
+    }
+
+    // Same with the unmatched bytes reported as `Unmatched` tokens
+    let mut lexer = Lexer::new(schema_config)?;
+    lexer.set_unmatched_bytes_mode(UnmatchedBytesMode::Unmatched);
+    let mut log_parser = LogParser::from_lexer(lexer);
+    log_parser.set_strict_mode(true);
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.to_string(), "2015-01-31 15:50:45 INFO 42\n");
+    match log_parser.parse_next_log_event() {
+        Err(Error::UnmatchedContent(info)) => {
+            assert_eq!(info.line, 2);
+            assert_eq!(info.text, "user=alice");
+        }
+        _ => panic!("expected unmatched content"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_raw_line_mode() -> Result<()> {
+    let lines = [
+        "{\"ts\":\"2015-01-31 15:50:45,392\",\"level\":\"INFO\",\"msg\":\"Took 42 ms\"}\n",
+        "{\"ts\":\"2015-01-31 15:50:46,001\",\"level\":\"WARN\",\"msg\":\"Retry 0x1f\"}\n",
+        "{\"ts\":\"2015-01-31 15:50:47,123\",\"msg\":\"unterminated\"}",
+    ];
+    let log_path = std::env::temp_dir().join("log_surgeon_test_raw_line_mode.jsonl");
+    std::fs::write(&log_path, lines.concat()).unwrap();
+
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_raw_line_mode(true);
+    log_parser.set_strict_mode(true);
+    log_parser.set_input_file(log_path.to_str().unwrap())?;
+
+    let mut log_events = Vec::new();
+    while let Some(log_event) = log_parser.parse_next_log_event()? {
+        log_events.push(log_event);
+    }
+    std::fs::remove_file(&log_path).unwrap();
+
+    assert_eq!(log_events.len(), lines.len());
+    for (idx, (log_event, line)) in log_events.iter().zip(lines).enumerate() {
+        assert_eq!(log_event.get_num_tokens(), 1);
+        assert!(log_event.get_timestamp_token().is_none());
+        assert!(log_event.get_variables_by_name("int").is_empty());
+        assert_eq!(log_event.get_line_range(), (idx + 1, idx + 1));
+        assert_eq!(log_event.to_string(), line);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_log_parser_input_not_set() -> Result<()> {
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    assert!(matches!(
+        log_parser.parse_next_log_event(),
+        Err(Error::LogParserInputNotSet)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_log_event_to_bytes() -> Result<()> {
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    let input = b"2015-01-31 15:50:45,392 INFO Read \xff\xfe at 0x1f\n".to_vec();
+    log_parser.set_input_stream(Box::new(StringStream {
+        buf: input.clone(),
+        pos: 0,
+    }))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.to_bytes(), input);
+    assert_eq!(
+        log_event.to_string(),
+        "2015-01-31 15:50:45,392 INFO Read \u{fffd}\u{fffd} at 0x1f\n"
+    );
+    assert!(log_parser.parse_next_log_event()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_last_committed_offset() -> Result<()> {
+    let content = concat!(
+        "2015-01-31 15:50:45,392 INFO Took 42 ms\n",
+        "2015-01-31 15:50:46,001 ERROR Failed with 0x1f\n",
+        "  at /usr/lib/foo.so\n",
+        "2015-01-31 15:50:47,123 INFO Retried 3 times\n",
+        "2015-01-31 15:50:48,000 INFO Done\n",
+    );
+    let log_path = std::env::temp_dir().join("log_surgeon_test_last_committed_offset.log");
+    let log_path_str = log_path.to_str().unwrap();
+    let parse_all = |log_parser: &mut LogParser| -> Result<Vec<String>> {
+        let mut log_events = Vec::new();
+        while let Some(log_event) = log_parser.parse_next_log_event()? {
+            log_events.push(log_event.to_string());
+        }
+        Ok(log_events)
+    };
+
+    // The offsets don't count the BOM, which is skipped on both runs
+    for bom in [&b""[..], &b"\xEF\xBB\xBF"[..]] {
+        std::fs::write(&log_path, [bom, content.as_bytes()].concat()).unwrap();
+        let mut log_parser = LogParser::new(load_example_schema()?)?;
+        log_parser.set_input_file(log_path_str)?;
+        let expected = parse_all(&mut log_parser)?;
+        assert_eq!(expected.len(), 4);
+        assert_eq!(log_parser.last_committed_offset(), content.len() as u64);
+
+        // Simulates a crash after processing the first two log events
+        log_parser.set_input_file(log_path_str)?;
+        assert_eq!(log_parser.last_committed_offset(), 0);
+        let mut log_events = Vec::new();
+        for _ in 0..2 {
+            log_events.push(log_parser.parse_next_log_event()?.unwrap().to_string());
+        }
+        let offset = log_parser.last_committed_offset();
+        assert_eq!(offset, (expected[0].len() + expected[1].len()) as u64);
+
+        let mut log_parser = LogParser::new(load_example_schema()?)?;
+        log_parser.set_input_file_from_offset(log_path_str, offset)?;
+        log_events.extend(parse_all(&mut log_parser)?);
+        assert_eq!(log_events, expected);
+        assert_eq!(log_parser.last_committed_offset(), content.len() as u64);
+    }
+    std::fs::remove_file(&log_path).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn test_tail_file_stream() -> Result<()> {
+    use log_surgeon::lexer::TailFileStream;
+    use std::io::Write;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    let lines = [
+        "2015-01-31 15:50:45,392 INFO Took 42 ms\n",
+        "2015-01-31 15:50:46,001 WARN Retried 3 times\n",
+        "2015-01-31 15:50:47,123 INFO Done\n",
+    ];
+    let log_path = std::env::temp_dir().join(format!(
+        "log_surgeon_test_tail_file_stream_{}.log",
+        std::process::id()
+    ));
+
+    let append_line = |line: &str| {
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&log_path)
+            .unwrap();
+        file.write_all(line.as_bytes()).unwrap();
+    };
+
+    // With a dedup mode, the last parsed log event is held back until the next one is known, and
+    // must survive the pauses of the stream
+    for dedup_mode in [None, Some(DedupMode::Exact)] {
+        std::fs::write(&log_path, lines[0]).unwrap();
+        let mut tail_file_stream = TailFileStream::new(log_path.to_str().unwrap())?;
+        tail_file_stream.set_poll_interval(None);
+        let stop_signal = tail_file_stream.get_stop_signal();
+        let mut log_parser = LogParser::new(load_example_schema()?)?;
+        log_parser.set_multiline_mode(false);
+        log_parser.set_dedup_mode(dedup_mode);
+        log_parser.set_input_stream(Box::new(tail_file_stream))?;
+
+        // Parses the log events available so far, until the stream pauses or ends
+        let mut log_events = Vec::new();
+        let mut parse_available = |log_parser: &mut LogParser| -> Result<()> {
+            loop {
+                match log_parser.parse_next_log_event() {
+                    Ok(Some(log_event)) => log_events.push(log_event.to_string()),
+                    Ok(None) => return Ok(()),
+                    Err(Error::IOError(e)) if io::ErrorKind::WouldBlock == e.kind() => {
+                        return Ok(())
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        parse_available(&mut log_parser)?;
+        for line in &lines[1..] {
+            append_line(line);
+            parse_available(&mut log_parser)?;
+        }
+        stop_signal.store(true, Ordering::Relaxed);
+        parse_available(&mut log_parser)?;
+        assert_eq!(log_events, lines, "{:?}", dedup_mode);
+        assert!(log_parser.parse_next_log_event()?.is_none());
+    }
+
+    // With a poll interval, the stream waits for the lines appended by another thread instead
+    std::fs::write(&log_path, lines[0]).unwrap();
+    let mut tail_file_stream = TailFileStream::new(log_path.to_str().unwrap())?;
+    tail_file_stream.set_poll_interval(Some(Duration::from_millis(1)));
+    let stop_signal = tail_file_stream.get_stop_signal();
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_multiline_mode(false);
+    log_parser.set_input_stream(Box::new(tail_file_stream))?;
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for line in &lines[1..] {
+                std::thread::sleep(Duration::from_millis(20));
+                append_line(line);
+            }
+            std::thread::sleep(Duration::from_millis(20));
+            stop_signal.store(true, Ordering::Relaxed);
+        });
+        let mut log_events = Vec::new();
+        while let Some(log_event) = log_parser.parse_next_log_event()? {
+            log_events.push(log_event.to_string());
+        }
+        assert_eq!(log_events, lines);
+        Ok::<(), Error>(())
+    })?;
+
+    std::fs::remove_file(&log_path).unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_log_event_header() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " \n[]"
+variables:
+  loglevel: '(INFO)|(WARN)|(ERROR)'
+  level: '(I|W|E)'
+"#,
+    )?;
+    let mut log_parser = LogParser::new(schema_config)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(concat!(
+        "2015-01-31 15:50:45 [W] WARN Disk full, then ERROR\n",
+        "2015-01-31 15:50:46 Nothing to report\n",
+    ))))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    let header = log_event.header();
+    assert_eq!(header.get_timestamp(), Some("2015-01-31 15:50:45"));
+    assert_eq!(header.get_severity(), Some("WARN"));
+    assert_eq!(
+        log_event.header_with_severity_var("level").get_severity(),
+        Some("W")
+    );
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    let header = log_event.header();
+    assert_eq!(header.get_timestamp(), Some("2015-01-31 15:50:46"));
+    assert_eq!(header.get_severity(), None);
+    assert_eq!(
+        log_event.header_with_severity_var("unknown"),
+        log_event.header()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_mid_line_timestamps() -> Result<()> {
+    let input = concat!(
+        "2015-01-31 15:50:45 GET /index sent at 2015-01-31 15:50:47 in 2015-01-31 15:50:48\n",
+        "2015-01-31 15:50:49 Done\n",
+    );
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    for mid_line_timestamps in [false, true] {
+        log_parser.set_mid_line_timestamps(mid_line_timestamps);
+        log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+
+        let log_event = log_parser.parse_next_log_event()?.unwrap();
+        assert_eq!(log_event.get_line_range(), (1, 1));
+        assert_eq!(
+            log_event.header().get_timestamp(),
+            Some("2015-01-31 15:50:45")
+        );
+        let mid_line_timestamps_found: Vec<String> = log_event
+            .get_log_message_tokens()
+            .iter()
+            .filter(|token| token.is_timestamp())
+            .map(|token| token.get_buf_as_string())
+            .collect();
+        match mid_line_timestamps {
+            // The substrings are lexed like any other text
+            false => assert!(mid_line_timestamps_found.is_empty()),
+            true => assert_eq!(
+                mid_line_timestamps_found,
+                vec!["2015-01-31 15:50:47", "2015-01-31 15:50:48"]
+            ),
+        }
+        assert_eq!(
+            log_event.to_string(),
+            input.lines().next().unwrap().to_string() + "\n"
+        );
+
+        let log_event = log_parser.parse_next_log_event()?.unwrap();
+        assert_eq!(
+            log_event.header().get_timestamp(),
+            Some("2015-01-31 15:50:49")
+        );
+        assert!(log_parser.parse_next_log_event()?.is_none());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_log_event_is_complete() -> Result<()> {
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(
+        "2015-01-31 15:50:45,392 INFO Took 42 ms\n2015-01-31 15:50:46,001 INFO Took 4",
+    )))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert!(log_event.is_complete());
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.to_string(), "2015-01-31 15:50:46,001 INFO Took 4");
+    assert!(false == log_event.is_complete());
+    assert!(log_parser.parse_next_log_event()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_log_event_has_only_static_text() -> Result<()> {
+    use log_surgeon::lexer::{Lexer, UnmatchedBytesMode};
+
+    let input = concat!(
+        "2015-01-31 15:50:45,392 Nothing to report\n",
+        "2015-01-31 15:50:46,001 INFO Took 42 ms\n",
+        "2015-01-31 15:50:47,123 Still nothing\n",
+    );
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+
+    let mut has_only_static_text = Vec::new();
+    while let Some(log_event) = log_parser.parse_next_log_event()? {
+        assert!(log_event.get_timestamp_token().is_some());
+        has_only_static_text.push(log_event.has_only_static_text());
+    }
+    assert_eq!(has_only_static_text, vec![true, false, true]);
+    assert_eq!(log_parser.get_num_static_only_events(), 2);
+
+    // `Unmatched` tokens aren't variables either
+    let mut lexer = Lexer::new(load_example_schema()?)?;
+    lexer.set_unmatched_bytes_mode(UnmatchedBytesMode::Unmatched);
+    let mut log_parser = LogParser::from_lexer(lexer);
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+
+    let mut has_only_static_text = Vec::new();
+    while let Some(log_event) = log_parser.parse_next_log_event()? {
+        assert!(log_event
+            .get_log_message_tokens()
+            .iter()
+            .any(|token| TokenType::Unmatched == token.get_token_type()));
+        has_only_static_text.push(log_event.has_only_static_text());
+    }
+    assert_eq!(has_only_static_text, vec![true, false, true]);
+    assert_eq!(log_parser.get_num_static_only_events(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_numeric_summary() -> Result<()> {
+    let schema_config = SchemaConfig::parse_from_str(
+        r#"
+timestamp:
+  - '\d{4}\-\d{2}\-\d{2} \d{2}:\d{2}:\d{2}'
+delimiters: " \n"
+variables:
+  int: '(\-{0,1}\d+)|(N/A)'
+"#,
+    )?;
+    let input = concat!(
+        "2015-01-31 15:50:45 Took 42 ms\n",
+        "2015-01-31 15:50:46 Took -7 ms, retried 10 times\n",
+        "2015-01-31 15:50:47 Took N/A ms\n",
+    );
+
+    let mut log_parser = LogParser::new(schema_config.clone())?;
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+    let summary = log_parser.numeric_summary("int")?;
+    assert_eq!(summary.get_count(), 3);
+    assert_eq!(summary.get_skipped(), 1);
+    assert_eq!(summary.get_min(), Some(-7.0));
+    assert_eq!(summary.get_max(), Some(42.0));
+    assert_eq!(summary.get_mean(), Some(15.0));
+
+    let mut log_parser = LogParser::new(schema_config)?;
+    log_parser.set_input_stream(Box::new(StringStream::new("no numbers here\n")))?;
+    let summary = log_parser.numeric_summary("int")?;
+    assert_eq!(summary.get_count(), 0);
+    assert_eq!(summary.get_mean(), None);
+    assert!(matches!(
+        log_parser.numeric_summary("float"),
+        Err(Error::UnknownVariableName(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_all_with_progress() -> Result<()> {
+    let mut input = String::new();
+    for i in 0..25 {
+        input += format!("2015-01-31 15:50:45,392 INFO Event {}\n", i).as_str();
+    }
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(input.as_str())))?;
+    log_parser.set_progress_interval(10);
+    let mut reports: Vec<ParseProgress> = Vec::new();
+    let log_events = log_parser.parse_all_with_progress(|progress| reports.push(progress))?;
+    assert_eq!(log_events.len(), 25);
+    assert_eq!(
+        reports
+            .iter()
+            .map(|progress| progress.get_events_emitted())
+            .collect::<Vec<_>>(),
+        vec![10, 20, 25]
+    );
+    let last = reports.last().unwrap();
+    assert_eq!(last.get_bytes_processed(), input.len() as u64);
+    assert_eq!(last.get_current_line(), 25);
+    assert_eq!(reports[0].get_current_line(), 10);
+
+    log_parser.set_input_stream(Box::new(StringStream::new("")))?;
+    let mut reports: Vec<ParseProgress> = Vec::new();
+    assert!(log_parser
+        .parse_all_with_progress(|progress| reports.push(progress))?
+        .is_empty());
+    assert_eq!(reports, vec![ParseProgress::default()]);
+    Ok(())
+}
+
+#[test]
+fn test_dedup_mode() -> Result<()> {
+    let input = concat!(
+        "2015-01-31 15:50:45,392 INFO Took 42 ms\n",
+        "2015-01-31 15:50:45,392 INFO Took 42 ms\n",
+        "2015-01-31 15:50:45,392 INFO Took 42 ms\n",
+        "2015-01-31 15:50:46,001 INFO Took 7 ms\n",
+        "2015-01-31 15:50:47,123 WARN Disk full\n",
+    );
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_dedup_mode(Some(DedupMode::Exact));
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.repeat_count(), 3);
+    assert_eq!(log_event.get_line_range(), (1, 1));
+    assert_eq!(log_parser.last_committed_offset(), 3 * 40);
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.repeat_count(), 1);
+    assert_eq!(log_event.get_line_range(), (4, 4));
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.repeat_count(), 1);
+    assert!(log_parser.parse_next_log_event()?.is_none());
+    assert_eq!(log_parser.last_committed_offset(), input.len() as u64);
+
+    log_parser.set_dedup_mode(Some(DedupMode::Template));
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+    let repeat_counts: Vec<usize> = log_parser
+        .parse_all_with_progress(|_| {})?
+        .iter()
+        .map(|log_event| log_event.repeat_count())
+        .collect();
+    assert_eq!(repeat_counts, vec![4, 1]);
+
+    log_parser.set_dedup_mode(None);
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+    assert_eq!(log_parser.parse_all_with_progress(|_| {})?.len(), 5);
+    Ok(())
+}
+
+#[test]
+fn test_variable_length_histogram() -> Result<()> {
+    let input = concat!(
+        "2015-01-31 15:50:45,392 INFO Took 42 ms to read 1024 bytes\n",
+        "2015-01-31 15:50:46,001 WARN Retried 3 times, last code -17\n",
+        "2015-01-31 15:50:47,123 INFO Took 7 ms, id 12345678901234567890\n",
+    );
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(input)))?;
+    let histogram = log_parser.variable_length_histogram("int")?;
+    assert_eq!(
+        histogram.into_iter().collect::<Vec<_>>(),
+        vec![(1, 2), (2, 1), (3, 1), (4, 1), (20, 1)]
+    );
+
+    assert!(matches!(
+        log_parser.variable_length_histogram("integer"),
+        Err(Error::UnknownVariableName(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_stack_trace_mode() -> Result<()> {
+    let lines = [
+        "Picked up JAVA_TOOL_OPTIONS: -Xmx512m\n",
+        "Starting service\n",
+        "2015-01-31 15:50:45,392 ERROR Request failed\n",
+        "java.lang.IllegalStateException: closed\n",
+        "\tat org.apache.hive.Driver.run(Driver.java:42)\n",
+        "\tat org.apache.hive.Driver.main(Driver.java:7)\n",
+        "Caused by: java.io.IOException\n",
+        "2015-01-31 15:50:46,001 INFO Retrying\n",
+    ];
+
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_stack_trace_mode(true);
+    log_parser.set_input_stream(Box::new(StringStream::new(lines.concat().as_str())))?;
+    let mut log_events = Vec::new();
+    while let Some(log_event) = log_parser.parse_next_log_event()? {
+        log_events.push(log_event);
+    }
+
+    // The leading lines have no preceding timestamp, so each is its own log event
+    let line_ranges: Vec<(usize, usize)> = log_events
+        .iter()
+        .map(|log_event| log_event.get_line_range())
+        .collect();
+    assert_eq!(line_ranges, vec![(1, 1), (2, 2), (3, 7), (8, 8)]);
+    assert!(log_events[1].get_timestamp_token().is_none());
+    assert!(log_events[2].get_timestamp_token().is_some());
+    assert_eq!(log_events[2].to_string(), lines[2..7].concat());
+
+    // The cap splits the stack trace, and its overflowing lines become standalone log events
+    log_parser.set_max_lines_per_event(Some(3));
+    log_parser.set_input_stream(Box::new(StringStream::new(lines.concat().as_str())))?;
+    let mut line_ranges = Vec::new();
+    while let Some(log_event) = log_parser.parse_next_log_event()? {
+        line_ranges.push(log_event.get_line_range());
+    }
+    assert_eq!(
+        line_ranges,
+        vec![(1, 1), (2, 2), (3, 5), (6, 6), (7, 7), (8, 8)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_log_event_timestamp_bytes() -> Result<()> {
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(
+        "Starting up\n2015-01-31 15:50:45,392 INFO Took 42 ms\n",
+    )))?;
+
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.timestamp_bytes(), None);
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(
+        log_event.timestamp_bytes(),
+        Some(b"2015-01-31 15:50:45,392".as_slice())
+    );
+    assert_eq!(log_event.timestamp_str(), Some("2015-01-31 15:50:45,392"));
+
+    Ok(())
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_log_event_timestamp_datetime() -> Result<()> {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use log_surgeon::parser::SchemaConfigBuilder;
+
+    let expected: NaiveDateTime = NaiveDate::from_ymd_opt(2015, 1, 31)
+        .unwrap()
+        .and_hms_milli_opt(15, 50, 45, 390)
+        .unwrap();
+
+    let schema_config = SchemaConfigBuilder::new()
+        .add_timestamp_with_format(
+            r"\d{4}\-\d{2}\-\d{2}T\d{2}:\d{2}:\d{2}\.\d{1,3}",
+            "%Y-%m-%dT%H:%M:%S%.f",
+        )
+        .add_variable("int", r"\d+")
+        .set_delimiters(" ")
+        .build()?;
+    let mut log_parser = LogParser::new(schema_config)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(
+        "2015-01-31T15:50:45.39 Took 42 ms\n",
+    )))?;
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.timestamp_str(), Some("2015-01-31T15:50:45.39"));
+    assert_eq!(log_event.timestamp_datetime(), Some(expected));
+
+    // Without a format, it's inferred
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_input_stream(Box::new(StringStream::new(
+        "Starting up\n2015-01-31 15:50:45,390 INFO Took 42 ms\n",
+    )))?;
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.timestamp_datetime(), None);
+    let log_event = log_parser.parse_next_log_event()?.unwrap();
+    assert_eq!(log_event.timestamp_datetime(), Some(expected));
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_variables() -> Result<()> {
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let log_path = std::path::Path::new(project_root)
+        .join("examples")
+        .join("logs")
+        .join("hive-24h.log");
+    let mut log_parser = LogParser::new(load_example_schema()?)?;
+    log_parser.set_input_file(log_path.to_str().unwrap())?;
+    let records = log_parser.extract_variables()?;
+
+    let input = std::fs::read(&log_path)?;
+    for record in &records {
+        let start = record.get_start_offset() as usize;
+        assert_eq!(
+            &input[start..start + record.get_value().len()],
+            record.get_value().as_bytes()
+        );
+    }
+
+    let hex_records: Vec<_> = records
+        .iter()
+        .filter(|record| "hex" == record.get_name())
+        .map(|record| (record.get_value(), record.get_line()))
+        .collect();
+    assert_eq!(
+        hex_records,
+        vec![
+            ("0xddba9b95eeb3cfb9ccb3d8401d1610d42f0e3aad", 4),
+            ("0xad56993d052a6b692268e8aa013dd02e37e082bf", 5),
+        ]
+    );
+
+    let int_records: Vec<_> = records
+        .iter()
+        .filter(|record| "int" == record.get_name())
+        .map(|record| (record.get_value(), record.get_line()))
+        .collect();
+    assert_eq!(int_records, vec![("10", 1), ("0", 11)]);
+
+    Ok(())
+}
+
+#[test]
+fn test_log_event_relex() -> Result<()> {
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let log_path = std::path::Path::new(project_root)
+        .join("examples")
+        .join("logs")
+        .join("hive-24h.log");
+    let schema_config = load_example_schema()?;
+    let mut log_parser = LogParser::new(schema_config.clone())?;
+    log_parser.set_input_file(log_path.to_str().unwrap())?;
+    let mut lexer = log_surgeon::lexer::Lexer::new(schema_config)?;
+
+    let get_token_info = |token: &log_surgeon::lexer::Token| {
+        (
+            token.get_token_type(),
+            token.get_buf().to_vec(),
+            token.get_line_num(),
+        )
+    };
+    for _ in 0..200 {
+        let Some(log_event) = log_parser.parse_next_log_event()? else {
+            break;
+        };
+        let expected: Vec<_> = log_event
+            .get_timestamp_token()
+            .into_iter()
+            .chain(log_event.get_log_message_tokens())
+            .map(get_token_info)
+            .collect();
+        let relexed: Vec<_> = log_event
+            .relex(&mut lexer)?
+            .iter()
+            .map(get_token_info)
+            .collect();
+        assert_eq!(relexed, expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_files_in_parallel() -> Result<()> {
+    let project_root = env!("CARGO_MANIFEST_DIR");
+    let log_path_dir = std::path::Path::new(project_root)
+        .join("examples")
+        .join("logs");
+    let log_paths = vec![
+        log_path_dir.join("hive-24h.log"),
+        log_path_dir.join("hive-24h_large.log"),
+        log_path_dir.join("missing.log"),
+    ];
+    let schema_path = std::path::Path::new(project_root)
+        .join("examples")
+        .join("schema.yaml");
+    let schema_config = SchemaConfig::parse_from_file_arc(schema_path.to_str().unwrap())?;
+
+    let get_token_info = |tokens: &[log_surgeon::lexer::Token]| -> Vec<(String, usize)> {
+        tokens
+            .iter()
+            .map(|token| (token.get_buf_as_string(), token.get_line_num()))
+            .collect()
+    };
+    let mut log_parser = LogParser::new_arc(schema_config.clone())?;
+    let mut expected = Vec::new();
+    for log_path in &log_paths[..2] {
+        log_parser.set_input_file(log_path.to_str().unwrap())?;
+        let mut log_events = Vec::new();
+        while let Some(log_event) = log_parser.parse_next_log_event()? {
+            log_events.push(get_token_info(&log_event.into_tokens()));
+        }
+        expected.push(log_events);
+    }
+
+    for num_threads in [1, 2, 4] {
+        let mut results = parse_files_in_parallel(&schema_config, &log_paths, num_threads)?;
+        assert_eq!(results.len(), log_paths.len());
+        assert!(matches!(results.pop().unwrap(), Err(Error::IOError(_))));
+        for (result, expected_log_events) in results.into_iter().zip(&expected) {
+            let log_events: Vec<_> = result?
+                .iter()
+                .map(|tokens| get_token_info(tokens))
+                .collect();
+            assert_eq!(&log_events, expected_log_events);
+        }
+    }
+
+    Ok(())
+}
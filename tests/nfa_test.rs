@@ -0,0 +1,30 @@
+#![cfg(feature = "regex-engine")]
+
+use log_surgeon::error_handling::Result;
+use log_surgeon::nfa::{State, NFA};
+
+#[test]
+fn test_nfa_epsilon_closure_through_public_api() -> Result<()> {
+    let nfa = NFA::from_regex(r"a*b")?;
+
+    let start_closure = nfa.epsilon_closure(&[nfa.get_start()]);
+    assert!(start_closure.contains(&nfa.get_start()));
+    assert!(false == start_closure.contains(&nfa.get_accept()));
+
+    // Every state reached by consuming `b` from the start closure has the accept state in its
+    // closure
+    let mut after_b: Vec<State> = Vec::new();
+    for state in start_closure.iter() {
+        for transition in nfa.get_transitions_from_state(state).into_iter().flatten() {
+            if transition.get_symbol().contains(&'b') {
+                after_b.push(transition.get_to_state());
+            }
+        }
+    }
+    assert!(false == after_b.is_empty());
+    assert!(nfa.epsilon_closure(&after_b).contains(&nfa.get_accept()));
+
+    assert!(NFA::from_regex(r"\D").is_err());
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+// Exercises the regex engine as a `no_std + alloc` consumer would, without any `std` I/O. Run
+// without `std` with `cargo test --no-default-features --features regex-engine --test no_std_test`.
+#![cfg(feature = "regex-engine")]
+#![no_std]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use log_surgeon::dfa::{DfaSimulator, DFA};
+use log_surgeon::error_handling::Result;
+use log_surgeon::nfa::NFA;
+
+fn build_dfa(patterns: &[&str]) -> Result<DFA> {
+    let mut nfas = Vec::new();
+    for pattern in patterns {
+        nfas.push(NFA::from_regex(pattern)?);
+    }
+    Ok(DFA::from_multiple_nfas(nfas))
+}
+
+#[test]
+fn test_core_matching() -> Result<()> {
+    let dfa = build_dfa(&[r"\d+", r"0x(\d|[a-f])+", r"(INFO)|(WARN)"])?;
+
+    let run = |input: &[u8]| -> Option<usize> {
+        let mut state = dfa.get_root();
+        for c in input {
+            state = dfa.get_next_state(state, *c)?;
+        }
+        dfa.is_accept_state(state)
+    };
+    assert_eq!(run(b"1234"), Some(0));
+    assert_eq!(run(b"0x1f"), Some(1));
+    assert_eq!(run(b"WARN"), Some(2));
+    assert_eq!(run(b"0x"), None);
+    assert_eq!(run(b"12a"), None);
+
+    let mut simulator = DfaSimulator::new_arc(Arc::new(dfa));
+    let mut results = Vec::new();
+    for c in "0x1".chars() {
+        results.push(simulator.simulate_single_char(c));
+    }
+    assert_eq!(results, [(Some(0), true), (None, true), (Some(1), true)]);
+    simulator.reset_simulation();
+    assert_eq!(simulator.simulate_single_char('I'), (None, true));
+    assert_eq!(simulator.simulate_single_char('x'), (None, false));
+
+    Ok(())
+}